@@ -0,0 +1,67 @@
+// Spectral Mesh - Bloom Shader (WGSL)
+// Bright pass, separable Gaussian blur at half resolution, and an additive
+// composite of the glow over the finished frame
+
+struct BloomUniforms {
+    threshold: f32,             // brightness the glow starts at (0..1)
+    intensity: f32,             // glow strength added over the frame
+    texel: vec2<f32>,           // size of one texel of the half-resolution buffers
+}
+
+@group(0) @binding(0) var<uniform> bloom: BloomUniforms;
+@group(0) @binding(1) var source_texture: texture_2d<f32>;
+@group(0) @binding(2) var bloom_sampler: sampler;
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) tex_coord: vec2<f32>,
+}
+
+// Fullscreen triangle, no vertex buffer needed
+@vertex
+fn vs_fullscreen(@builtin(vertex_index) index: u32) -> VertexOutput {
+    var out: VertexOutput;
+    let x = f32((index << 1u) & 2u);
+    let y = f32(index & 2u);
+    out.clip_position = vec4<f32>(x * 2.0 - 1.0, y * 2.0 - 1.0, 0.0, 1.0);
+    out.tex_coord = vec2<f32>(x, 1.0 - y);
+    return out;
+}
+
+// Keep what is brighter than the threshold, fading in above it so the glow
+// doesn't pop on and off
+@fragment
+fn fs_bright(in: VertexOutput) -> @location(0) vec4<f32> {
+    let color = textureSample(source_texture, bloom_sampler, in.tex_coord).rgb;
+    let brightness = max(color.r, max(color.g, color.b));
+    let excess = max(brightness - bloom.threshold, 0.0) / max(1.0 - bloom.threshold, 0.001);
+    return vec4<f32>(color * min(excess, 1.0), 1.0);
+}
+
+// 9-tap Gaussian, taken as 5 bilinear samples along `direction`
+fn blur(tex_coord: vec2<f32>, direction: vec2<f32>) -> vec4<f32> {
+    let step = direction * bloom.texel;
+    var sum = textureSample(source_texture, bloom_sampler, tex_coord).rgb * 0.2270270270;
+    sum += textureSample(source_texture, bloom_sampler, tex_coord + step * 1.3846153846).rgb * 0.3162162162;
+    sum += textureSample(source_texture, bloom_sampler, tex_coord - step * 1.3846153846).rgb * 0.3162162162;
+    sum += textureSample(source_texture, bloom_sampler, tex_coord + step * 3.2307692308).rgb * 0.0702702703;
+    sum += textureSample(source_texture, bloom_sampler, tex_coord - step * 3.2307692308).rgb * 0.0702702703;
+    return vec4<f32>(sum, 1.0);
+}
+
+@fragment
+fn fs_blur_h(in: VertexOutput) -> @location(0) vec4<f32> {
+    return blur(in.tex_coord, vec2<f32>(1.0, 0.0));
+}
+
+@fragment
+fn fs_blur_v(in: VertexOutput) -> @location(0) vec4<f32> {
+    return blur(in.tex_coord, vec2<f32>(0.0, 1.0));
+}
+
+// Drawn with additive blending; alpha is left as it was
+@fragment
+fn fs_add(in: VertexOutput) -> @location(0) vec4<f32> {
+    let glow = textureSample(source_texture, bloom_sampler, in.tex_coord).rgb;
+    return vec4<f32>(glow * bloom.intensity, 0.0);
+}
@@ -4,7 +4,7 @@
 struct Uniforms {
     mvp: mat4x4<f32>,
     xy: vec2<f32>,              // displacement multiplier
-    xy_offset: vec2<f32>,       // center offset
+    pivot: vec2<f32>,           // NDC point the distortion radiates from
     x_lfo_arg: f32,
     x_lfo_amp: f32,
     x_lfo_other: f32,
@@ -15,19 +15,10 @@ struct Uniforms {
     z_lfo_amp: f32,
     z_lfo_other: f32,
     luma_key_level: f32,
-    invert_switch: f32,
-    b_w_switch: f32,
-    bright_switch: i32,
+    effect_flags: u32,          // FLAG_* bits
     x_lfo_shape: i32,
     y_lfo_shape: i32,
     z_lfo_shape: i32,
-    x_ringmod_switch: i32,
-    y_ringmod_switch: i32,
-    z_ringmod_switch: i32,
-    x_phasemod_switch: i32,
-    y_phasemod_switch: i32,
-    z_phasemod_switch: i32,
-    luma_switch: i32,
     width: i32,
     height: i32,
     audio_displacement: f32,
@@ -35,14 +26,47 @@ struct Uniforms {
     audio_wave_phase: f32,      // wave phase for line undulation
     audio_wave_amp: f32,        // wave amplitude from bass
     audio_wave_freq: f32,       // wave frequency from audio energy
+    video_mix: f32,             // crossfade to second video source
+    chroma_split: f32,          // RGB split offset in tex coords
+    x_source: i32,              // displacement source per axis (see displace_source)
+    y_source: i32,
+    z_source: i32,
+    audio_level: f32,           // bass envelope for the audio source
+    _align: f32,
+    ripples: array<vec4<f32>, 16>, // MAX_RIPPLES x [x, y, radius, intensity] in tex coords
+    ripple_shape: i32,          // 0 = gaussian, 1 = sine, 2 = saw, 3 = square
+    audio_displace_x: f32,      // per-axis audio displacement, added to audio_displacement
+    audio_displace_y: f32,
+    ripple_count: i32,          // ripples in use, the rest of the array is unused
+    extra_lfos: array<vec4<f32>, 6>, // 2 per axis (x, x, y, y, z, z): [phase, amp, frequency, shape]
+    input_exposure: f32,        // source gain (2^stops), applied before the effects
+    input_gamma: f32,
+    flash: f32,                 // strobe amount, 0 = none
+    flash_mode: i32,            // 0 = off, 1 = brighten, 2 = invert
+    mesh_smoothing: f32,        // LFO band-limiting to the grid, 0 = off
+    grid_cells: f32,            // mesh cells per side
+    output_level: f32,          // master fade, 1 = full, 0 = black
+    x_lfo_bias: f32,            // offsets the LFOs oscillate around
+    y_lfo_bias: f32,
+    z_lfo_bias: f32,
     _pad0: f32,
     _pad1: f32,
-    _pad2: f32,
-    _pad3: f32,
-    _pad4: f32,
-    _pad5: f32,
+    noise_amp: vec4<f32>,       // noise source gain per axis: x, y, z, unused
 }
 
+// Bits of effect_flags, mirroring renderer::effect_flags
+const FLAG_INVERT: u32 = 1u;
+const FLAG_GREYSCALE: u32 = 2u;
+const FLAG_BRIGHT_INVERT: u32 = 4u;
+const FLAG_LUMA_KEY_BRIGHT: u32 = 8u;
+const FLAG_X_RINGMOD: u32 = 16u;
+const FLAG_Y_RINGMOD: u32 = 32u;
+const FLAG_Z_RINGMOD: u32 = 64u;
+const FLAG_X_PHASEMOD: u32 = 128u;
+const FLAG_Y_PHASEMOD: u32 = 256u;
+const FLAG_Z_PHASEMOD: u32 = 512u;
+const FLAG_CHROMA_FOLLOW: u32 = 1024u;
+
 @group(0) @binding(0) var<uniform> uniforms: Uniforms;
 @group(0) @binding(1) var video_texture: texture_2d<f32>;
 @group(0) @binding(2) var video_sampler: sampler;
@@ -50,19 +74,78 @@ struct Uniforms {
 @group(0) @binding(4) var y_noise_texture: texture_2d<f32>;
 @group(0) @binding(5) var z_noise_texture: texture_2d<f32>;
 @group(0) @binding(6) var noise_sampler: sampler;
+@group(0) @binding(7) var video_texture2: texture_2d<f32>;
+@group(0) @binding(8) var video_sampler2: sampler;
 
 struct VertexInput {
     @location(0) position: vec3<f32>,
     @location(1) tex_coord: vec2<f32>,
+    @location(2) edge: f32,
 }
 
 struct VertexOutput {
     @builtin(position) clip_position: vec4<f32>,
     @location(0) tex_coord: vec2<f32>,
+    @location(1) displacement: vec2<f32>, // NDC offset from the undisplaced position
+}
+
+struct SmoothLineOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) tex_coord: vec2<f32>,
+    @location(1) edge: f32,     // across the quad, +-1 at the stroke's edge
+    @location(2) displacement: vec2<f32>,
+}
+
+struct DisplacedVertex {
+    clip_position: vec4<f32>,
+    displacement: vec2<f32>,    // how far the effects moved it, in NDC
 }
 
 const TWO_PI: f32 = 6.283185307;
 
+fn flag(bit: u32) -> bool {
+    return (uniforms.effect_flags & bit) != 0u;
+}
+
+// 1.0 when the effect is on, for switches that scale a modulation term
+fn flag_gain(bit: u32) -> f32 {
+    return select(0.0, 1.0, flag(bit));
+}
+
+// Sum of an axis' stacked LFOs, `pos` being the same spatial coordinate
+// its main LFO uses
+fn extra_lfo(axis: i32, pos: f32, tex_coord: vec2<f32>) -> f32 {
+    var sum = 0.0;
+    for (var k = 0; k < 2; k++) {
+        let lfo = uniforms.extra_lfos[axis * 2 + k];
+        sum += lfo.y * band_limit(lfo.z, grid_cell(axis)) * oscillate(lfo.x + pos * lfo.z, i32(lfo.w), tex_coord);
+    }
+    return sum;
+}
+
+// Size of one mesh cell in NDC along the coordinate an axis' LFOs run over:
+// X LFOs vary with y, Y LFOs with x, Z LFOs with the distance from the pivot
+fn grid_cell(axis: i32) -> f32 {
+    let size = vec2<f32>(f32(uniforms.width), f32(uniforms.height)) / uniforms.grid_cells;
+    let cell_x = length((uniforms.mvp * vec4<f32>(size.x, 0.0, 0.0, 0.0)).xy);
+    let cell_y = length((uniforms.mvp * vec4<f32>(0.0, size.y, 0.0, 0.0)).xy);
+    switch axis {
+        case 0: { return cell_y; }
+        case 1: { return cell_x; }
+        default: { return max(cell_x, cell_y); }
+    }
+}
+
+// Gain for an LFO whose phase moves `freq` radians per NDC unit, on a grid
+// with cells `cell` NDC units wide. Past half a cycle per cell (Nyquist) the
+// vertices can't follow the wave and the surface tears, so mesh_smoothing
+// fades such LFOs out from a quarter cycle per cell on.
+fn band_limit(freq: f32, cell: f32) -> f32 {
+    let step = abs(freq) * cell;
+    let cutoff = 1.0 - smoothstep(0.25 * TWO_PI, 0.5 * TWO_PI, step);
+    return mix(1.0, cutoff, uniforms.mesh_smoothing);
+}
+
 // Oscillator function with soft saw (triangle wave instead of hard saw)
 fn oscillate(theta: f32, shape: i32, tex_coord: vec2<f32>) -> f32 {
     if shape == 0 {
@@ -83,34 +166,101 @@ fn oscillate(theta: f32, shape: i32, tex_coord: vec2<f32>) -> f32 {
     }
 }
 
+// Video sample crossfaded with the second source (vertex stage, explicit LOD)
+fn sample_video_level(tex_coord: vec2<f32>) -> vec4<f32> {
+    let a = textureSampleLevel(video_texture, video_sampler, tex_coord, 0.0);
+    let b = textureSampleLevel(video_texture2, video_sampler2, tex_coord, 0.0);
+    return mix(a, b, uniforms.video_mix);
+}
+
+// Video sample crossfaded with the second source (fragment stage)
+fn sample_video(tex_coord: vec2<f32>) -> vec4<f32> {
+    let a = textureSample(video_texture, video_sampler, tex_coord);
+    let b = textureSample(video_texture2, video_sampler2, tex_coord);
+    return mix(a, b, uniforms.video_mix);
+}
+
+// Displacement source selector: 0 classic (luma + LFO), 1 noise, 2 LFO, 3 audio, 4 luma.
+// gain scales the non-LFO sources; noise is -1..1, audio and luma 0..1ish
+fn displace_source(source: i32, classic: f32, lfo: f32, gain: f32, noise: f32, bright: f32) -> f32 {
+    switch source {
+        case 1: { return gain * noise; }
+        case 2: { return lfo; }
+        case 3: { return gain * uniforms.audio_level; }
+        case 4: { return gain * bright; }
+        default: { return classic; }
+    }
+}
+
+fn axis_noise(noise_texture: texture_2d<f32>, tex_coord: vec2<f32>) -> f32 {
+    return 2.0 * (textureSampleLevel(noise_texture, noise_sampler, tex_coord, 0.0).r - 0.5);
+}
+
+// Ripples: rings expanding from their origin push vertices outward
+const RIPPLE_WIDTH: f32 = 0.05;
+const RIPPLE_AMP: f32 = 0.08;
+
+fn ripple_displacement(tex_coord: vec2<f32>) -> vec2<f32> {
+    var disp = vec2<f32>(0.0, 0.0);
+    for (var i = 0; i < uniforms.ripple_count; i = i + 1) {
+        let ripple = uniforms.ripples[i];
+        let delta = tex_coord - ripple.xy;
+        let dist = length(delta);
+        let ring = (dist - ripple.z) / RIPPLE_WIDTH;
+        disp = disp + delta / max(dist, 0.0001) * ripple_profile(ring) * ripple.w * RIPPLE_AMP;
+    }
+    return disp;
+}
+
+// Ring profile by distance from the front, in ripple widths (positive = outside)
+fn ripple_profile(ring: f32) -> f32 {
+    if uniforms.ripple_shape == 1 {
+        // Sine - a few crests, windowed around the front
+        return sin(ring * 3.14159265) * exp(-ring * ring * 0.25);
+    } else if uniforms.ripple_shape == 2 {
+        // Saw - jumps at the front, ramps down behind it
+        return select(0.0, 1.0 + ring * 0.5, ring <= 0.0 && ring > -2.0);
+    } else if uniforms.ripple_shape == 3 {
+        // Square - flat band
+        return select(0.0, 1.0, abs(ring) < 1.0);
+    }
+    // Gaussian - single smooth swell
+    return exp(-ring * ring);
+}
+
 // Audio-driven vibration effect - disabled for now
 fn audio_vibration(tex_coord: vec2<f32>) -> vec2<f32> {
     return vec2<f32>(0.0, 0.0);
 }
 
-@vertex
-fn vs_main(in: VertexInput) -> VertexOutput {
-    var out: VertexOutput;
-    out.tex_coord = in.tex_coord;
-
+// Displaced clip position of a mesh vertex, shared by every vertex entry point
+fn displace_vertex(position: vec3<f32>, tex_coord: vec2<f32>) -> DisplacedVertex {
     // Transform to clip space first (like original)
-    var new_position = uniforms.mvp * vec4<f32>(in.position, 1.0);
+    var new_position = uniforms.mvp * vec4<f32>(position, 1.0);
+
+    // Perspective divide up front so the displacements below stay in NDC units
+    // (w is 1 for the orthographic projection; w <= 0 is behind the camera and left to clipping)
+    if new_position.w > 0.0 {
+        new_position = vec4<f32>(new_position.xyz / new_position.w, 1.0);
+    }
+    let undisplaced = new_position.xy;
 
     // Sample video and calculate brightness
-    let color = textureSampleLevel(video_texture, video_sampler, in.tex_coord, 0.0);
+    let color = sample_video_level(tex_coord);
     var bright = 0.33 * color.r + 0.5 * color.g + 0.16 * color.b;
 
     // Logarithmic brightness boost (from original)
     bright = 2.0 * log(1.0 + bright);
 
-    // Invert brightness if bright_switch is on
-    if uniforms.bright_switch == 1 {
+    // Displace by darkness instead
+    if flag(FLAG_BRIGHT_INVERT) {
         bright = 1.0 - bright;
     }
 
-    // Add center offset
-    new_position.x = new_position.x + uniforms.xy_offset.x;
-    new_position.y = new_position.y + uniforms.xy_offset.y;
+    // Relative to the pivot from here on, so the zoom, the LFO phases and the
+    // radial Z LFO all radiate from it
+    new_position.x = new_position.x - uniforms.pivot.x;
+    new_position.y = new_position.y - uniforms.pivot.y;
 
     // Full LFO chain restored
 
@@ -118,81 +268,192 @@ fn vs_main(in: VertexInput) -> VertexOutput {
     let x_lfo_initial = uniforms.x_lfo_amp * oscillate(
         uniforms.x_lfo_arg + new_position.y * uniforms.x_lfo_other,
         uniforms.x_lfo_shape,
-        in.tex_coord
+        tex_coord
     );
 
     // Y LFO with optional ring/phase modulation from X
-    var y_lfo = (uniforms.y_lfo_amp + f32(uniforms.y_ringmod_switch) * 0.01 * x_lfo_initial) * oscillate(
-        uniforms.y_lfo_arg + new_position.x * uniforms.y_lfo_other + f32(uniforms.y_phasemod_switch) * 0.01 * x_lfo_initial,
+    var y_lfo = (uniforms.y_lfo_amp + flag_gain(FLAG_Y_RINGMOD) * 0.01 * x_lfo_initial) * oscillate(
+        uniforms.y_lfo_arg + new_position.x * uniforms.y_lfo_other + flag_gain(FLAG_Y_PHASEMOD) * 0.01 * x_lfo_initial,
         uniforms.y_lfo_shape,
-        in.tex_coord
+        tex_coord
     );
 
     // Z LFO (affects scale/zoom) with optional modulation from Y
-    let z_lfo_amp_mod = uniforms.z_lfo_amp + f32(uniforms.z_ringmod_switch) * 0.0025 * y_lfo + uniforms.audio_z;
-    let z_lfo_freq = uniforms.z_lfo_arg + uniforms.z_lfo_other * distance(
-        abs(new_position.xy),
-        vec2<f32>(uniforms.xy_offset.x / 2.0, uniforms.xy_offset.y / 2.0)
-    ) + f32(uniforms.z_phasemod_switch) * y_lfo;
-    let z_lfo = z_lfo_amp_mod * oscillate(z_lfo_freq, uniforms.z_lfo_shape, in.tex_coord);
+    let z_lfo_amp_mod = (uniforms.z_lfo_amp + flag_gain(FLAG_Z_RINGMOD) * 0.0025 * y_lfo + uniforms.audio_z)
+        * band_limit(uniforms.z_lfo_other, grid_cell(2));
+    let z_distance = length(new_position.xy);
+    let z_lfo_freq = uniforms.z_lfo_arg + uniforms.z_lfo_other * z_distance + flag_gain(FLAG_Z_PHASEMOD) * y_lfo;
+    let z_lfo_raw = z_lfo_amp_mod * oscillate(z_lfo_freq, uniforms.z_lfo_shape, tex_coord)
+        + extra_lfo(2, z_distance, tex_coord) + uniforms.z_lfo_bias;
+    let z_lfo = displace_source(
+        uniforms.z_source,
+        z_lfo_raw,
+        z_lfo_raw,
+        uniforms.z_lfo_amp,
+        uniforms.noise_amp.z * axis_noise(z_noise_texture, tex_coord),
+        bright
+    );
 
     // Apply Z LFO as scale
     new_position.x = new_position.x * (1.0 - z_lfo);
     new_position.y = new_position.y * (1.0 - z_lfo);
 
     // X LFO with optional ring/phase modulation from Z
-    let x_lfo_amp_mod = uniforms.x_lfo_amp + f32(uniforms.x_ringmod_switch) * 1000.0 * z_lfo;
-    let x_lfo_freq = uniforms.x_lfo_arg + new_position.y * uniforms.x_lfo_other + f32(uniforms.x_phasemod_switch) * 10.0 * z_lfo;
-    let x_lfo = x_lfo_amp_mod * oscillate(x_lfo_freq, uniforms.x_lfo_shape, in.tex_coord);
+    let x_lfo_amp_mod = (uniforms.x_lfo_amp + flag_gain(FLAG_X_RINGMOD) * 1000.0 * z_lfo)
+        * band_limit(uniforms.x_lfo_other, grid_cell(0));
+    let x_lfo_freq = uniforms.x_lfo_arg + new_position.y * uniforms.x_lfo_other + flag_gain(FLAG_X_PHASEMOD) * 10.0 * z_lfo;
+    let x_lfo = x_lfo_amp_mod * oscillate(x_lfo_freq, uniforms.x_lfo_shape, tex_coord)
+        + extra_lfo(0, new_position.y, tex_coord) + uniforms.x_lfo_bias;
 
-    // Apply X displacement: brightness * xy + x_lfo + audio
-    new_position.x = new_position.x + (uniforms.xy.x + uniforms.audio_displacement) * bright + x_lfo;
+    // Apply X displacement: brightness * xy + x_lfo + audio, or the selected source
+    new_position.x = new_position.x + displace_source(
+        uniforms.x_source,
+        (uniforms.xy.x + uniforms.audio_displacement + uniforms.audio_displace_x) * bright + x_lfo,
+        x_lfo,
+        uniforms.xy.x,
+        uniforms.noise_amp.x * axis_noise(x_noise_texture, tex_coord),
+        bright
+    );
 
     // Y LFO recalculated with optional ring/phase modulation from X
-    let y_lfo_amp_mod = uniforms.y_lfo_amp + f32(uniforms.y_ringmod_switch) * x_lfo;
-    let y_lfo_freq = uniforms.y_lfo_arg + new_position.x * uniforms.y_lfo_other + f32(uniforms.y_phasemod_switch) * 0.01 * x_lfo;
-    y_lfo = y_lfo_amp_mod * oscillate(y_lfo_freq, uniforms.y_lfo_shape, in.tex_coord);
+    let y_lfo_amp_mod = (uniforms.y_lfo_amp + flag_gain(FLAG_Y_RINGMOD) * x_lfo)
+        * band_limit(uniforms.y_lfo_other, grid_cell(1));
+    let y_lfo_freq = uniforms.y_lfo_arg + new_position.x * uniforms.y_lfo_other + flag_gain(FLAG_Y_PHASEMOD) * 0.01 * x_lfo;
+    y_lfo = y_lfo_amp_mod * oscillate(y_lfo_freq, uniforms.y_lfo_shape, tex_coord)
+        + extra_lfo(1, new_position.x, tex_coord) + uniforms.y_lfo_bias;
 
-    // Apply Y displacement: brightness * xy + y_lfo + audio
-    new_position.y = new_position.y + (uniforms.xy.y + uniforms.audio_displacement) * bright + y_lfo;
+    // Apply Y displacement: brightness * xy + y_lfo + audio, or the selected source
+    new_position.y = new_position.y + displace_source(
+        uniforms.y_source,
+        (uniforms.xy.y + uniforms.audio_displacement + uniforms.audio_displace_y) * bright + y_lfo,
+        y_lfo,
+        uniforms.xy.y,
+        uniforms.noise_amp.y * axis_noise(y_noise_texture, tex_coord),
+        bright
+    );
 
     // Apply audio vibration effect - lines tremble with the music
-    let vib_disp = audio_vibration(in.tex_coord);
+    let vib_disp = audio_vibration(tex_coord);
     new_position.x = new_position.x + vib_disp.x;
     new_position.y = new_position.y + vib_disp.y;
 
-    // Remove center offset
-    new_position.x = new_position.x - uniforms.xy_offset.x;
-    new_position.y = new_position.y - uniforms.xy_offset.y;
+    // Mouse ripples
+    let ripple_disp = ripple_displacement(tex_coord);
+    new_position.x = new_position.x + ripple_disp.x;
+    new_position.y = new_position.y + ripple_disp.y;
+
+    // Back from the pivot
+    new_position.x = new_position.x + uniforms.pivot.x;
+    new_position.y = new_position.y + uniforms.pivot.y;
+
+    return DisplacedVertex(new_position, new_position.xy - undisplaced);
+}
+
+// Filled triangles and hard lines: nothing varies across the primitive
+@vertex
+fn vs_main(in: VertexInput) -> VertexOutput {
+    var out: VertexOutput;
+    let displaced = displace_vertex(in.position, in.tex_coord);
+    out.clip_position = displaced.clip_position;
+    out.tex_coord = in.tex_coord;
+    out.displacement = displaced.displacement;
+    return out;
+}
 
-    out.clip_position = new_position;
+// Smooth line quads also carry the distance across the stroke
+@vertex
+fn vs_smooth_line(in: VertexInput) -> SmoothLineOutput {
+    var out: SmoothLineOutput;
+    let displaced = displace_vertex(in.position, in.tex_coord);
+    out.clip_position = displaced.clip_position;
+    out.tex_coord = in.tex_coord;
+    out.edge = in.edge;
+    out.displacement = displaced.displacement;
     return out;
 }
 
-@fragment
-fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
-    var color = textureSample(video_texture, video_sampler, in.tex_coord);
+// Post-effect chain from --effects, spliced in by the app (see effects.rs)
+fn post_effects(color: vec4<f32>, uv: vec2<f32>) -> vec4<f32> {
+    return color;
+}
+
+// Effected video color at `tex_coord`, straight alpha
+fn shade(tex_coord: vec2<f32>, displacement: vec2<f32>) -> vec4<f32> {
+    // Video is premultiplied on upload; the effects below work on straight color
+    var color = sample_video(tex_coord);
+    let coverage = max(color.a, 0.0001);
+
+    // Chromatic aberration - R and B pulled apart along a direction that
+    // drifts with the LFOs. The sampler clamps to edge, so offsets never wrap.
+    var split_dir = vec2<f32>(cos(uniforms.x_lfo_arg), sin(uniforms.y_lfo_arg));
+    // Motion smear: along the displacement instead (NDC to tex coords), at
+    // full strength once the mesh is moved 5% of the frame, none where it rests
+    if flag(FLAG_CHROMA_FOLLOW) {
+        let smear = vec2<f32>(displacement.x, -displacement.y) * 0.5;
+        split_dir = smear / max(length(smear), 0.05);
+    }
+    let split = split_dir * uniforms.chroma_split;
+    let red = sample_video(tex_coord + split).r;
+    let blue = sample_video(tex_coord - split).b;
+    color = vec4<f32>(vec3<f32>(red, color.g, blue) / coverage, color.a);
+
+    // Source exposure / gamma, ahead of the effects so it changes what gets keyed
+    let exposed = clamp(color.rgb * uniforms.input_exposure, vec3<f32>(0.0), vec3<f32>(1.0));
+    color = vec4<f32>(pow(exposed, vec3<f32>(1.0 / uniforms.input_gamma)), color.a);
     let bright = 0.33 * color.r + 0.5 * color.g + 0.16 * color.b;
 
-    // Greyscale blend (matches original: b_w_switch * grey + (1-b_w_switch) * color)
-    let grey = vec4<f32>(bright, bright, bright, color.a);
-    color = uniforms.b_w_switch * grey + (1.0 - uniforms.b_w_switch) * color;
+    // Greyscale (matches original)
+    if flag(FLAG_GREYSCALE) {
+        color = vec4<f32>(bright, bright, bright, color.a);
+    }
 
     // Invert (matches original)
-    color = vec4<f32>(
-        uniforms.invert_switch * (1.0 - color.r) + (1.0 - uniforms.invert_switch) * color.r,
-        uniforms.invert_switch * (1.0 - color.g) + (1.0 - uniforms.invert_switch) * color.g,
-        uniforms.invert_switch * (1.0 - color.b) + (1.0 - uniforms.invert_switch) * color.b,
-        color.a
-    );
+    if flag(FLAG_INVERT) {
+        color = vec4<f32>(vec3<f32>(1.0) - color.rgb, color.a);
+    }
 
-    // Luma key (matches original)
-    if uniforms.luma_switch == 0 && bright < uniforms.luma_key_level {
+    // Luma key (matches original). Keyed fragments get zero alpha, so with a
+    // transparent background they show whatever is behind the window
+    let key_bright = flag(FLAG_LUMA_KEY_BRIGHT);
+    if !key_bright && bright < uniforms.luma_key_level {
         color.a = 0.0;
     }
-    if uniforms.luma_switch == 1 && bright > uniforms.luma_key_level {
+    if key_bright && bright > uniforms.luma_key_level {
         color.a = 0.0;
     }
 
-    return color;
+    color = post_effects(color, tex_coord);
+
+    // Strobe: the whole frame fades toward white or its inverse
+    let flash_target = select(vec3<f32>(1.0), vec3<f32>(1.0) - color.rgb, uniforms.flash_mode == 2);
+    color = vec4<f32>(mix(color.rgb, flash_target, uniforms.flash), color.a);
+
+    // Master fade, e.g. to black outside --schedule hours
+    return vec4<f32>(color.rgb * uniforms.output_level, color.a);
+}
+
+// Premultiplied out, so transparent source areas blend over the background.
+// Fully transparent fragments are dropped so they don't hide the mesh behind
+// them in the depth buffer.
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    let color = shade(in.tex_coord, in.displacement);
+    if color.a <= 0.0 {
+        discard;
+    }
+    return vec4<f32>(color.rgb * color.a, color.a);
+}
+
+// Smooth lines: coverage falls off over one screen pixel at the stroke's
+// edge (distance field across the quad; 0 = fully covered)
+@fragment
+fn fs_smooth_line(in: SmoothLineOutput) -> @location(0) vec4<f32> {
+    let color = shade(in.tex_coord, in.displacement);
+    let edge = abs(in.edge);
+    let line_coverage = clamp((1.0 - edge) / max(fwidth(edge), 0.0001) + 0.5, 0.0, 1.0);
+    let alpha = color.a * line_coverage;
+    if alpha <= 0.0 {
+        discard;
+    }
+    return vec4<f32>(color.rgb * alpha, alpha);
 }
@@ -0,0 +1,8 @@
+// Contrast around mid grey, then saturation around the luma.
+// x: contrast (1 = unchanged), y: saturation (1 = unchanged, 0 = grey)
+fn effect(color: vec4<f32>, uv: vec2<f32>, params: vec4<f32>) -> vec4<f32> {
+    let contrasted = (color.rgb - vec3<f32>(0.5)) * params.x + vec3<f32>(0.5);
+    let luma = dot(contrasted, vec3<f32>(0.2126, 0.7152, 0.0722));
+    let saturated = mix(vec3<f32>(luma), contrasted, params.y);
+    return vec4<f32>(clamp(saturated, vec3<f32>(0.0), vec3<f32>(1.0)), color.a);
+}
@@ -0,0 +1,6 @@
+// Reduce each channel to a few flat levels.
+// x: levels per channel (2 or more)
+fn effect(color: vec4<f32>, uv: vec2<f32>, params: vec4<f32>) -> vec4<f32> {
+    let steps = max(params.x, 2.0) - 1.0;
+    return vec4<f32>(round(color.rgb * steps) / steps, color.a);
+}
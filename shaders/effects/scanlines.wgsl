@@ -0,0 +1,6 @@
+// Horizontal lines across the video, like a CRT.
+// x: lines from bottom to top, y: darkness of the gaps (0 - 1)
+fn effect(color: vec4<f32>, uv: vec2<f32>, params: vec4<f32>) -> vec4<f32> {
+    let wave = 0.5 + 0.5 * sin(uv.y * params.x * 6.2831853);
+    return vec4<f32>(color.rgb * (1.0 - params.y * wave), color.a);
+}
@@ -0,0 +1,5 @@
+// Multiply by a color.
+// x, y, z: red, green, blue (0 - 1), w: amount (0 = none, 1 = full)
+fn effect(color: vec4<f32>, uv: vec2<f32>, params: vec4<f32>) -> vec4<f32> {
+    return vec4<f32>(mix(color.rgb, color.rgb * params.xyz, params.w), color.a);
+}
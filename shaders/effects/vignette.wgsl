@@ -0,0 +1,7 @@
+// Darken toward the corners of the video.
+// x: strength (0 - 1), y: distance from the center where it starts (0 - 1.4)
+fn effect(color: vec4<f32>, uv: vec2<f32>, params: vec4<f32>) -> vec4<f32> {
+    let distance = length(uv - vec2<f32>(0.5)) * 2.0;
+    let shade = 1.0 - params.x * smoothstep(params.y, 1.42, distance);
+    return vec4<f32>(color.rgb * shade, color.a);
+}
@@ -0,0 +1,46 @@
+// Spectral Mesh - Feedback Shader (WGSL)
+// Composites the current frame over the decayed previous frame for trails,
+// and blits the result to the screen
+
+struct FeedbackUniforms {
+    decay: f32,                 // how much of the previous frame survives (0..0.99)
+    _pad0: f32,
+    _pad1: f32,
+    _pad2: f32,
+}
+
+@group(0) @binding(0) var<uniform> feedback: FeedbackUniforms;
+@group(0) @binding(1) var current_texture: texture_2d<f32>;
+@group(0) @binding(2) var previous_texture: texture_2d<f32>;
+@group(0) @binding(3) var feedback_sampler: sampler;
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) tex_coord: vec2<f32>,
+}
+
+// Fullscreen triangle, no vertex buffer needed
+@vertex
+fn vs_fullscreen(@builtin(vertex_index) index: u32) -> VertexOutput {
+    var out: VertexOutput;
+    let x = f32((index << 1u) & 2u);
+    let y = f32(index & 2u);
+    out.clip_position = vec4<f32>(x * 2.0 - 1.0, y * 2.0 - 1.0, 0.0, 1.0);
+    out.tex_coord = vec2<f32>(x, 1.0 - y);
+    return out;
+}
+
+// Current frame (premultiplied, transparent where nothing was drawn)
+// over the decayed previous frame
+@fragment
+fn fs_composite(in: VertexOutput) -> @location(0) vec4<f32> {
+    let current = textureSample(current_texture, feedback_sampler, in.tex_coord);
+    let previous = textureSample(previous_texture, feedback_sampler, in.tex_coord);
+    return current + feedback.decay * previous * (1.0 - current.a);
+}
+
+// Drawn with premultiplied blending over the background color
+@fragment
+fn fs_blit(in: VertexOutput) -> @location(0) vec4<f32> {
+    return textureSample(current_texture, feedback_sampler, in.tex_coord);
+}
@@ -0,0 +1,39 @@
+// Spectral Mesh - HUD Shader (WGSL)
+// Draws the parameter overlay image (premultiplied, with its own dimmed
+// backing) as a quad over the finished frame
+
+struct HudUniforms {
+    rect: vec4<f32>,            // left, top, right, bottom in clip space
+}
+
+@group(0) @binding(0) var<uniform> hud: HudUniforms;
+@group(0) @binding(1) var hud_texture: texture_2d<f32>;
+@group(0) @binding(2) var hud_sampler: sampler;
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) tex_coord: vec2<f32>,
+}
+
+// Two triangles over the rect, no vertex buffer needed
+@vertex
+fn vs_hud(@builtin(vertex_index) index: u32) -> VertexOutput {
+    var corners = array<vec2<f32>, 6>(
+        vec2<f32>(0.0, 0.0),
+        vec2<f32>(1.0, 0.0),
+        vec2<f32>(0.0, 1.0),
+        vec2<f32>(0.0, 1.0),
+        vec2<f32>(1.0, 0.0),
+        vec2<f32>(1.0, 1.0),
+    );
+    let corner = corners[index];
+    var out: VertexOutput;
+    out.clip_position = vec4<f32>(mix(hud.rect.xy, hud.rect.zw, corner), 0.0, 1.0);
+    out.tex_coord = corner;
+    return out;
+}
+
+@fragment
+fn fs_hud(in: VertexOutput) -> @location(0) vec4<f32> {
+    return textureSample(hud_texture, hud_sampler, in.tex_coord);
+}
@@ -1,20 +1,381 @@
 use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
+use rustfft::num_complex::Complex;
+use rustfft::{Fft, FftPlanner};
 use std::sync::atomic::{AtomicU32, Ordering};
 use std::sync::Arc;
+use std::time::Instant;
+
+/// Bass low-pass cutoff
+const BASS_CUTOFF_HZ: f32 = 150.0;
+/// Gain on the bass RMS so typical music reaches the 0-1 range
+const BASS_BOOST: f32 = 4.0;
+/// Envelope smoothing time constants in seconds, so the response doesn't depend on
+/// the device's sample rate or buffer size (tuned at 48 kHz / 512-frame buffers)
+const RMS_SMOOTHING_SECS: f32 = 0.048;
+const PEAK_SMOOTHING_SECS: f32 = 0.030; // Faster attack for peak
+const BASS_SMOOTHING_SECS: f32 = 0.066;
+/// Automatic gain control: how fast the reference level follows a louder
+/// signal, and the quietest level it will boost to full scale (so silence
+/// and hiss stay quiet)
+const AGC_ATTACK_SECS: f32 = 0.2;
+const AGC_FLOOR: f32 = 0.02;
+/// Level that reads 1.0 from the calibrated getters, in dBFS, unless set with
+/// `set_reference_level`
+pub const DEFAULT_REFERENCE_DB: f32 = -12.0;
+/// Range below the reference level that the calibrated getters spread over 0-1
+const CALIBRATED_RANGE_DB: f32 = 48.0;
+/// Rise in the bass level per second that counts as a kick, independent of
+/// how often `update` runs (0.15 per update at 60 Hz)
+pub const DEFAULT_KICK_THRESHOLD: f32 = 9.0;
+/// Kick intensity per unit of bass rise per second: twice the rise over one
+/// 60 Hz update, the scale the kick consumers were tuned for
+const KICK_GAIN_SECS: f32 = 2.0 / 60.0;
+/// Spectrum analysis: FFT length in samples, and how many new samples trigger
+/// the next FFT (half overlap, about 94 spectra a second at 48 kHz)
+const FFT_SIZE: usize = 1024;
+const FFT_HOP: usize = FFT_SIZE / 2;
+/// Frequency bands of `AudioAnalyzer::bands`: sub-bass, bass, low-mid, mid,
+/// high-mid, treble, as edges in Hz
+pub const SPECTRUM_BANDS: usize = 6;
+const BAND_EDGES_HZ: [f32; SPECTRUM_BANDS + 1] = [20.0, 60.0, 250.0, 500.0, 2000.0, 4000.0, 16000.0];
+/// Gain on the band levels so typical music reaches the 0-1 range, as BASS_BOOST
+const SPECTRUM_BOOST: f32 = 4.0;
+const SPECTRUM_SMOOTHING_SECS: f32 = 0.05;
+
+/// One-pole low-pass coefficient with its -3 dB point at `cutoff` Hz
+fn one_pole_alpha(cutoff: f32, sample_rate: f32) -> f32 {
+    1.0 - (-2.0 * std::f32::consts::PI * cutoff / sample_rate).exp()
+}
+
+/// Weight of the old value in a moving average updated every `dt` seconds
+fn smoothing(time_constant: f32, dt: f32) -> f32 {
+    (-dt / time_constant).exp()
+}
+
+/// Sample with NaN / infinity (seen from some virtual devices) replaced by
+/// silence, so one bad value can't poison the filter state for good
+fn finite(sample: f32) -> f32 {
+    if sample.is_finite() {
+        sample
+    } else {
+        0.0
+    }
+}
+
+/// Linear level to 0-1 on a dB scale, which follows loudness far more evenly
+/// than the amplitude: `reference_db` and louder read 1, CALIBRATED_RANGE_DB
+/// below it reads 0
+fn calibrate(level: f32, reference_db: f32) -> f32 {
+    if level <= 0.0 {
+        return 0.0;
+    }
+    let db = 20.0 * level.log10();
+    ((db - reference_db) / CALIBRATED_RANGE_DB + 1.0).clamp(0.0, 1.0)
+}
+
+/// Store a level for the render side, never NaN
+fn store_level(bits: &AtomicU32, value: f32) {
+    let value = if value.is_finite() { value } else { 0.0 };
+    bits.store(value.to_bits(), Ordering::Relaxed);
+}
+
+/// Audio feature used to drive a modulation
+#[derive(Clone, Copy, Debug, PartialEq, clap::ValueEnum)]
+pub enum AudioBand {
+    Bass,
+    Rms,
+    Peak,
+    /// Bass transients only
+    Kick,
+    /// Left channel RMS (the mono signal on single-channel inputs)
+    Left,
+    /// Right channel RMS (same as left on single-channel inputs)
+    Right,
+    /// Left channel bass
+    LeftBass,
+    /// Right channel bass
+    RightBass,
+    /// Spectrum band 20-60 Hz (see `AudioAnalyzer::bands`)
+    SubBass,
+    /// Spectrum band 250-500 Hz
+    LowMid,
+    /// Spectrum band 500 Hz - 2 kHz
+    Mid,
+    /// Spectrum band 2-4 kHz
+    HighMid,
+    /// Spectrum band 4-16 kHz
+    Treble,
+    /// Camera motion energy, works without audio input
+    Motion,
+}
+
+impl AudioBand {
+    /// Index into `AudioAnalyzer::bands` for the spectrum bands
+    fn spectrum_index(self) -> Option<usize> {
+        match self {
+            AudioBand::SubBass => Some(0),
+            AudioBand::LowMid => Some(2),
+            AudioBand::Mid => Some(3),
+            AudioBand::HighMid => Some(4),
+            AudioBand::Treble => Some(5),
+            _ => None,
+        }
+    }
+}
+
+/// Reference level for automatic gain control: the loudest level of the last
+/// window, so dividing by it spreads quiet and loud rooms over the full 0-1 range
+#[derive(Default)]
+struct AutoGain {
+    reference: f32,
+    /// Seconds left before the reference starts to fall
+    hold: f32,
+}
+
+impl AutoGain {
+    /// Follow `level` over the last `dt` seconds and return the gain to apply.
+    /// The reference rises quickly, holds for `window` seconds after the last
+    /// peak and then releases over `window` seconds, so the gain doesn't pump
+    /// between beats.
+    fn update(&mut self, level: f32, dt: f32, window: f32) -> f32 {
+        if level > self.reference {
+            let k = smoothing(AGC_ATTACK_SECS, dt);
+            self.reference = self.reference * k + level * (1.0 - k);
+            self.hold = window;
+        } else if self.hold > 0.0 {
+            self.hold -= dt;
+        } else {
+            let k = smoothing(window, dt);
+            self.reference = self.reference * k + level * (1.0 - k);
+        }
+        1.0 / self.reference.max(AGC_FLOOR)
+    }
+}
+
+/// FFT over a ring of the latest mono samples, run every FFT_HOP samples on
+/// the audio thread. Every buffer is allocated up front, so the callback
+/// never allocates.
+struct Spectrum {
+    fft: Arc<dyn Fft<f32>>,
+    /// Hann window, and the factor from squared bin magnitudes to signal power
+    window: Vec<f32>,
+    power_scale: f32,
+    ring: Vec<f32>,
+    /// Next ring slot to write, and samples written since the last FFT
+    write: usize,
+    pending: usize,
+    buffer: Vec<Complex<f32>>,
+    scratch: Vec<Complex<f32>>,
+    /// FFT bins [first, end) of each band; empty above the Nyquist frequency
+    band_bins: [(usize, usize); SPECTRUM_BANDS],
+    band_bits: [Arc<AtomicU32>; SPECTRUM_BANDS],
+}
+
+impl Spectrum {
+    fn new(sample_rate: f32, band_bits: [Arc<AtomicU32>; SPECTRUM_BANDS]) -> Self {
+        let fft = FftPlanner::new().plan_fft_forward(FFT_SIZE);
+        let window: Vec<f32> = (0..FFT_SIZE)
+            .map(|i| 0.5 - 0.5 * (2.0 * std::f32::consts::PI * i as f32 / FFT_SIZE as f32).cos())
+            .collect();
+        // Band edges as bins, each band at least one bin wide so the narrow low
+        // bands aren't lost at high sample rates
+        let nyquist_bin = FFT_SIZE / 2;
+        let mut edges = [1; SPECTRUM_BANDS + 1];
+        for (i, hz) in BAND_EDGES_HZ.iter().enumerate() {
+            let bin = (hz * FFT_SIZE as f32 / sample_rate).round() as usize;
+            let floor = if i == 0 { 1 } else { edges[i - 1] + 1 };
+            edges[i] = bin.max(floor).min(nyquist_bin);
+        }
+        let band_bins = std::array::from_fn(|band| (edges[band], edges[band + 1]));
+
+        Self {
+            scratch: vec![Complex::default(); fft.get_inplace_scratch_len()],
+            fft,
+            // Parseval for a windowed one-sided spectrum: a sine's power is
+            // spread over the window's noise bandwidth (1.5 bins for Hann)
+            power_scale: 2.0 / (FFT_SIZE as f32 * window.iter().map(|w| w * w).sum::<f32>()),
+            window,
+            ring: vec![0.0; FFT_SIZE],
+            write: 0,
+            pending: 0,
+            buffer: vec![Complex::default(); FFT_SIZE],
+            band_bins,
+            band_bits,
+        }
+    }
+
+    /// Add one mono sample; every FFT_HOP samples, analyze the latest FFT_SIZE
+    fn push(&mut self, sample: f32, sample_rate: f32) {
+        self.ring[self.write] = sample;
+        self.write = (self.write + 1) % FFT_SIZE;
+        self.pending += 1;
+        if self.pending >= FFT_HOP {
+            self.pending = 0;
+            self.analyze(FFT_HOP as f32 / sample_rate);
+        }
+    }
+
+    fn analyze(&mut self, dt: f32) {
+        // Oldest sample first, windowed
+        let (newer, older) = self.ring.split_at(self.write);
+        for ((slot, &sample), &weight) in self.buffer.iter_mut().zip(older.iter().chain(newer)).zip(&self.window) {
+            *slot = Complex::new(sample * weight, 0.0);
+        }
+        self.fft.process_with_scratch(&mut self.buffer, &mut self.scratch);
+
+        // The band's RMS amplitude, on the rms_raw scale
+        let k = smoothing(SPECTRUM_SMOOTHING_SECS, dt);
+        for (&(first, end), bits) in self.band_bins.iter().zip(&self.band_bits) {
+            let power: f32 = self.buffer[first..end].iter().map(|bin| bin.norm_sqr() * self.power_scale).sum();
+            let old = f32::from_bits(bits.load(Ordering::Relaxed));
+            store_level(bits, old * k + power.sqrt() * (1.0 - k));
+        }
+    }
+}
+
+/// Envelope followers run on every input buffer, writing their smoothed values
+/// to atomics the analyzer reads from the render side
+struct SampleProcessor {
+    channels: usize,
+    sample_rate: f32,
+    bass_alpha: f32,
+    // Low-pass filter state for bass extraction: mono mix, then left/right
+    bass_filter_state: f32,
+    channel_filter_state: [f32; 2],
+    rms_bits: Arc<AtomicU32>,
+    peak_bits: Arc<AtomicU32>,
+    bass_bits: Arc<AtomicU32>,
+    channel_rms_bits: [Arc<AtomicU32>; 2],
+    channel_bass_bits: [Arc<AtomicU32>; 2],
+    /// AGC window in seconds, 0 = off
+    agc_window_bits: Arc<AtomicU32>,
+    rms_agc: AutoGain,
+    bass_agc: AutoGain,
+    rms_gain_bits: Arc<AtomicU32>,
+    bass_gain_bits: Arc<AtomicU32>,
+    spectrum: Spectrum,
+}
+
+impl SampleProcessor {
+    /// Analyze one buffer of interleaved samples
+    fn process(&mut self, data: &[f32]) {
+        let channels = self.channels;
+        let bass_alpha = self.bass_alpha;
+        let mut sum_sq = 0.0f32;
+        let mut peak = 0.0f32;
+        let mut bass_sum = 0.0f32;
+        let mut channel_sum_sq = [0.0f32; 2];
+        let mut channel_bass_sum = [0.0f32; 2];
+
+        // Process samples (mix down to mono; single-channel input is used as is).
+        // A trailing partial frame is dropped, as in `num_samples`
+        for chunk in data.chunks_exact(channels) {
+            let sample = if channels == 1 {
+                finite(chunk[0])
+            } else {
+                chunk.iter().copied().map(finite).sum::<f32>() / channels as f32
+            };
+            sum_sq += sample * sample;
+            peak = peak.max(sample.abs());
+            self.spectrum.push(sample, self.sample_rate);
+
+            // Simple low-pass filter for bass
+            self.bass_filter_state = bass_alpha * sample + (1.0 - bass_alpha) * self.bass_filter_state;
+            bass_sum += self.bass_filter_state * self.bass_filter_state;
+
+            // First two channels as left/right; mono inputs feed both
+            let left = finite(chunk[0]);
+            let stereo = [left, chunk.get(1).copied().map_or(left, finite)];
+            for (ch, &s) in stereo.iter().enumerate() {
+                channel_sum_sq[ch] += s * s;
+                self.channel_filter_state[ch] = bass_alpha * s + (1.0 - bass_alpha) * self.channel_filter_state[ch];
+                channel_bass_sum[ch] += self.channel_filter_state[ch] * self.channel_filter_state[ch];
+            }
+        }
+
+        let num_samples = data.len() / channels;
+        if num_samples > 0 {
+            let rms = (sum_sq / num_samples as f32).sqrt();
+            let bass_rms = (bass_sum / num_samples as f32).sqrt() * BASS_BOOST;
+
+            // Smooth values (exponential moving average), weighted by
+            // how much time this buffer covers
+            let dt = num_samples as f32 / self.sample_rate;
+            let old_rms = f32::from_bits(self.rms_bits.load(Ordering::Relaxed));
+            let old_peak = f32::from_bits(self.peak_bits.load(Ordering::Relaxed));
+            let old_bass = f32::from_bits(self.bass_bits.load(Ordering::Relaxed));
+
+            let k = smoothing(RMS_SMOOTHING_SECS, dt);
+            let smoothed_rms = old_rms * k + rms * (1.0 - k);
+            let k = smoothing(PEAK_SMOOTHING_SECS, dt);
+            let smoothed_peak = old_peak * k + peak * (1.0 - k);
+            let k = smoothing(BASS_SMOOTHING_SECS, dt);
+            let smoothed_bass = old_bass * k + bass_rms * (1.0 - k);
+
+            store_level(&self.rms_bits, smoothed_rms);
+            store_level(&self.peak_bits, smoothed_peak);
+            store_level(&self.bass_bits, smoothed_bass);
+
+            let window = f32::from_bits(self.agc_window_bits.load(Ordering::Relaxed));
+            let (rms_gain, bass_gain) = if window > 0.0 {
+                (
+                    self.rms_agc.update(smoothed_rms, dt, window),
+                    self.bass_agc.update(smoothed_bass, dt, window),
+                )
+            } else {
+                (1.0, 1.0)
+            };
+            store_level(&self.rms_gain_bits, rms_gain);
+            store_level(&self.bass_gain_bits, bass_gain);
+
+            // Per-channel values, smoothed the same way
+            let k_rms = smoothing(RMS_SMOOTHING_SECS, dt);
+            let k_bass = smoothing(BASS_SMOOTHING_SECS, dt);
+            for ch in 0..2 {
+                let rms = (channel_sum_sq[ch] / num_samples as f32).sqrt();
+                let bass_rms = (channel_bass_sum[ch] / num_samples as f32).sqrt() * BASS_BOOST;
+                let old_rms = f32::from_bits(self.channel_rms_bits[ch].load(Ordering::Relaxed));
+                let old_bass = f32::from_bits(self.channel_bass_bits[ch].load(Ordering::Relaxed));
+                let rms = old_rms * k_rms + rms * (1.0 - k_rms);
+                let bass_rms = old_bass * k_bass + bass_rms * (1.0 - k_bass);
+                store_level(&self.channel_rms_bits[ch], rms);
+                store_level(&self.channel_bass_bits[ch], bass_rms);
+            }
+        }
+    }
+}
 
 /// Audio analyzer that captures input and computes RMS/peak values
 pub struct AudioAnalyzer {
-    _stream: cpal::Stream,
+    /// Input stream, None when samples are pushed by the caller
+    _stream: Option<cpal::Stream>,
+    /// Analysis for pushed samples (see `external`)
+    external: Option<SampleProcessor>,
     /// RMS value (0.0 - 1.0) stored as bits for atomic access
     rms_bits: Arc<AtomicU32>,
     /// Peak value (0.0 - 1.0) stored as bits
     peak_bits: Arc<AtomicU32>,
     /// Low frequency energy (bass)
     bass_bits: Arc<AtomicU32>,
-    /// Bass energy from previous frame for kick detection
+    /// Per-channel RMS and bass, [left, right]
+    channel_rms_bits: [Arc<AtomicU32>; 2],
+    channel_bass_bits: [Arc<AtomicU32>; 2],
+    /// Automatic gain control (see `set_agc`): window, and the current gains
+    /// applied to the RMS and bass values
+    agc_window_bits: Arc<AtomicU32>,
+    rms_gain_bits: Arc<AtomicU32>,
+    bass_gain_bits: Arc<AtomicU32>,
+    /// Spectrum band levels (see `bands`)
+    band_bits: [Arc<AtomicU32>; SPECTRUM_BANDS],
+    /// Bass energy and time of the previous update, for kick detection
     prev_bass: f32,
+    prev_update: Option<Instant>,
     /// Kick detection threshold
     kick_threshold: f32,
+    /// Kick intensity from the last `update`
+    kick: f32,
+    /// When set, `level` reads the calibrated getters against this dBFS level
+    reference_db: Option<f32>,
 }
 
 impl AudioAnalyzer {
@@ -60,61 +421,24 @@ impl AudioAnalyzer {
             config.sample_rate().0
         );
 
-        let rms_bits = Arc::new(AtomicU32::new(0));
-        let peak_bits = Arc::new(AtomicU32::new(0));
-        let bass_bits = Arc::new(AtomicU32::new(0));
-
-        let rms_bits_clone = rms_bits.clone();
-        let peak_bits_clone = peak_bits.clone();
-        let bass_bits_clone = bass_bits.clone();
-
-        let channels = config.channels() as usize;
-        let sample_rate = config.sample_rate().0 as f32;
+        // Some virtual devices report an empty config; analyzing it would
+        // divide by zero
+        if config.channels() == 0 || config.sample_rate().0 == 0 {
+            return Err(format!(
+                "Audio input '{}' reports {} channels at {} Hz, pick another with --audio <index>",
+                device_name,
+                config.channels(),
+                config.sample_rate().0
+            ));
+        }
 
-        // Simple low-pass filter state for bass extraction
-        let mut bass_filter_state = 0.0f32;
-        let bass_cutoff = 150.0; // Hz
-        let bass_alpha = (2.0 * std::f32::consts::PI * bass_cutoff / sample_rate)
-            / (2.0 * std::f32::consts::PI * bass_cutoff / sample_rate + 1.0);
+        let mut analyzer = Self::external(config.channels(), config.sample_rate().0);
+        let mut processor = analyzer.external.take().expect("external analyzer has a processor");
 
         let stream = device
             .build_input_stream(
                 &config.into(),
-                move |data: &[f32], _: &cpal::InputCallbackInfo| {
-                    let mut sum_sq = 0.0f32;
-                    let mut peak = 0.0f32;
-                    let mut bass_sum = 0.0f32;
-
-                    // Process samples (mix down to mono)
-                    for chunk in data.chunks(channels) {
-                        let sample: f32 = chunk.iter().sum::<f32>() / channels as f32;
-                        sum_sq += sample * sample;
-                        peak = peak.max(sample.abs());
-
-                        // Simple low-pass filter for bass
-                        bass_filter_state = bass_alpha * sample + (1.0 - bass_alpha) * bass_filter_state;
-                        bass_sum += bass_filter_state * bass_filter_state;
-                    }
-
-                    let num_samples = data.len() / channels;
-                    if num_samples > 0 {
-                        let rms = (sum_sq / num_samples as f32).sqrt();
-                        let bass_rms = (bass_sum / num_samples as f32).sqrt() * 4.0; // Boost bass
-
-                        // Smooth values (exponential moving average)
-                        let old_rms = f32::from_bits(rms_bits_clone.load(Ordering::Relaxed));
-                        let old_peak = f32::from_bits(peak_bits_clone.load(Ordering::Relaxed));
-                        let old_bass = f32::from_bits(bass_bits_clone.load(Ordering::Relaxed));
-
-                        let smoothed_rms = old_rms * 0.8 + rms * 0.2;
-                        let smoothed_peak = old_peak * 0.7 + peak * 0.3; // Faster attack for peak
-                        let smoothed_bass = old_bass * 0.85 + bass_rms * 0.15;
-
-                        rms_bits_clone.store(smoothed_rms.to_bits(), Ordering::Relaxed);
-                        peak_bits_clone.store(smoothed_peak.to_bits(), Ordering::Relaxed);
-                        bass_bits_clone.store(smoothed_bass.to_bits(), Ordering::Relaxed);
-                    }
-                },
+                move |data: &[f32], _: &cpal::InputCallbackInfo| processor.process(data),
                 |err| {
                     log::error!("Audio stream error: {}", err);
                 },
@@ -128,19 +452,111 @@ impl AudioAnalyzer {
 
         log::info!("Audio capture started");
 
-        Ok(Self {
-            _stream: stream,
+        analyzer._stream = Some(stream);
+        Ok(analyzer)
+    }
+
+    /// Analyzer without an input device, fed interleaved samples with `push_samples`.
+    /// 0 channels or a 0 Hz rate are taken as mono / 1 Hz.
+    pub fn external(channels: u16, sample_rate: u32) -> Self {
+        if channels == 0 || sample_rate == 0 {
+            log::warn!("Audio format {} channels at {} Hz is invalid, treating it as mono", channels, sample_rate);
+        }
+        let channels = (channels as usize).max(1);
+        let sample_rate = sample_rate.max(1) as f32;
+        if channels < 2 {
+            log::info!("Mono input: left and right audio bands both follow it");
+        }
+
+        let rms_bits = Arc::new(AtomicU32::new(0));
+        let peak_bits = Arc::new(AtomicU32::new(0));
+        let bass_bits = Arc::new(AtomicU32::new(0));
+        let channel_rms_bits: [Arc<AtomicU32>; 2] = Default::default();
+        let channel_bass_bits: [Arc<AtomicU32>; 2] = Default::default();
+        let agc_window_bits = Arc::new(AtomicU32::new(0));
+        let rms_gain_bits = Arc::new(AtomicU32::new(1.0f32.to_bits()));
+        let bass_gain_bits = Arc::new(AtomicU32::new(1.0f32.to_bits()));
+        let band_bits: [Arc<AtomicU32>; SPECTRUM_BANDS] = Default::default();
+
+        let processor = SampleProcessor {
+            channels,
+            sample_rate,
+            bass_alpha: one_pole_alpha(BASS_CUTOFF_HZ, sample_rate),
+            bass_filter_state: 0.0,
+            channel_filter_state: [0.0; 2],
+            rms_bits: rms_bits.clone(),
+            peak_bits: peak_bits.clone(),
+            bass_bits: bass_bits.clone(),
+            channel_rms_bits: channel_rms_bits.clone(),
+            channel_bass_bits: channel_bass_bits.clone(),
+            agc_window_bits: agc_window_bits.clone(),
+            rms_agc: AutoGain::default(),
+            bass_agc: AutoGain::default(),
+            rms_gain_bits: rms_gain_bits.clone(),
+            bass_gain_bits: bass_gain_bits.clone(),
+            spectrum: Spectrum::new(sample_rate, band_bits.clone()),
+        };
+
+        Self {
+            _stream: None,
+            external: Some(processor),
             rms_bits,
             peak_bits,
             bass_bits,
+            channel_rms_bits,
+            channel_bass_bits,
+            agc_window_bits,
+            rms_gain_bits,
+            bass_gain_bits,
+            band_bits,
             prev_bass: 0.0,
-            kick_threshold: 0.15, // Sensitivity for kick detection
-        })
+            prev_update: None,
+            kick_threshold: DEFAULT_KICK_THRESHOLD,
+            kick: 0.0,
+            reference_db: None,
+        }
+    }
+
+    /// Channels and sample rate of an analyzer made with `external`
+    pub fn external_format(&self) -> Option<(u16, u32)> {
+        self.external.as_ref().map(|p| (p.channels as u16, p.sample_rate as u32))
+    }
+
+    /// Analyze interleaved samples from the caller. Ignored by analyzers that
+    /// capture from a device.
+    pub fn push_samples(&mut self, data: &[f32]) {
+        if let Some(processor) = &mut self.external {
+            processor.process(data);
+        }
+    }
+
+    /// Normalize RMS and bass by their loudest level over the last `window`
+    /// seconds, so quiet and loud rooms both use the full range. 0 turns it off.
+    pub fn set_agc(&self, window: f32) {
+        store_level(&self.agc_window_bits, window.max(0.0));
+    }
+
+    /// Make `level` (and so every audio route) use the calibrated getters, with
+    /// `reference_db` dBFS reading 1.0. None goes back to the boosted linear levels.
+    pub fn set_reference_level(&mut self, reference_db: Option<f32>) {
+        self.reference_db = reference_db;
+    }
+
+    fn reference(&self) -> f32 {
+        self.reference_db.unwrap_or(DEFAULT_REFERENCE_DB)
+    }
+
+    fn rms_gain(&self) -> f32 {
+        f32::from_bits(self.rms_gain_bits.load(Ordering::Relaxed))
+    }
+
+    fn bass_gain(&self) -> f32 {
+        f32::from_bits(self.bass_gain_bits.load(Ordering::Relaxed))
     }
 
     /// Get current RMS value (0.0 - 1.0, typically 0.0 - 0.5 for normal audio)
     pub fn rms(&self) -> f32 {
-        f32::from_bits(self.rms_bits.load(Ordering::Relaxed)).min(1.0)
+        (f32::from_bits(self.rms_bits.load(Ordering::Relaxed)) * self.rms_gain()).min(1.0)
     }
 
     /// Get current peak value (0.0 - 1.0)
@@ -150,7 +566,128 @@ impl AudioAnalyzer {
 
     /// Get bass energy (0.0 - 1.0, boosted low frequencies)
     pub fn bass(&self) -> f32 {
-        f32::from_bits(self.bass_bits.load(Ordering::Relaxed)).min(1.0)
+        (f32::from_bits(self.bass_bits.load(Ordering::Relaxed)) * self.bass_gain()).min(1.0)
+    }
+
+    /// RMS of one channel (0 = left, 1 = right)
+    pub fn channel_rms(&self, channel: usize) -> f32 {
+        (f32::from_bits(self.channel_rms_bits[channel].load(Ordering::Relaxed)) * self.rms_gain()).min(1.0)
+    }
+
+    /// Bass energy of one channel (0 = left, 1 = right)
+    pub fn channel_bass(&self, channel: usize) -> f32 {
+        (f32::from_bits(self.channel_bass_bits[channel].load(Ordering::Relaxed)) * self.bass_gain()).min(1.0)
+    }
+
+    /// Levels of the spectrum bands (sub-bass, bass, low-mid, mid, high-mid,
+    /// treble), 0.0 - 1.0, boosted like `bass` and following the RMS automatic
+    /// gain. The bass band is the FFT's, separate from the low-pass `bass`.
+    pub fn bands(&self) -> [f32; SPECTRUM_BANDS] {
+        std::array::from_fn(|band| (self.band_raw(band) * SPECTRUM_BOOST * self.rms_gain()).min(1.0))
+    }
+
+    /// RMS amplitude of one spectrum band, on the same scale as `rms_raw`
+    pub fn band_raw(&self, band: usize) -> f32 {
+        f32::from_bits(self.band_bits[band].load(Ordering::Relaxed))
+    }
+
+    /// RMS amplitude of the input, 1.0 = a full-scale square wave. Smoothed but
+    /// not clamped, boosted or gain controlled.
+    pub fn rms_raw(&self) -> f32 {
+        f32::from_bits(self.rms_bits.load(Ordering::Relaxed))
+    }
+
+    /// Peak amplitude of the input (1.0 = full scale), smoothed
+    pub fn peak_raw(&self) -> f32 {
+        f32::from_bits(self.peak_bits.load(Ordering::Relaxed))
+    }
+
+    /// RMS amplitude below the bass cutoff, on the same scale as `rms_raw`
+    pub fn bass_raw(&self) -> f32 {
+        f32::from_bits(self.bass_bits.load(Ordering::Relaxed)) / BASS_BOOST
+    }
+
+    /// `rms_raw` of one channel (0 = left, 1 = right)
+    pub fn channel_rms_raw(&self, channel: usize) -> f32 {
+        f32::from_bits(self.channel_rms_bits[channel].load(Ordering::Relaxed))
+    }
+
+    /// `bass_raw` of one channel (0 = left, 1 = right)
+    pub fn channel_bass_raw(&self, channel: usize) -> f32 {
+        f32::from_bits(self.channel_bass_bits[channel].load(Ordering::Relaxed)) / BASS_BOOST
+    }
+
+    /// Loudness 0-1: the reference level (`set_reference_level`, default
+    /// DEFAULT_REFERENCE_DB) reads 1, each 12 dB quieter a quarter less, 48 dB
+    /// down reads 0. The same scale for every feature, so 0.5 bass is as loud
+    /// as 0.5 RMS. Ignores the automatic gain.
+    pub fn rms_calibrated(&self) -> f32 {
+        calibrate(self.rms_raw(), self.reference())
+    }
+
+    /// Peak level on the `rms_calibrated` scale; reads higher than the RMS by
+    /// the signal's crest factor (about 10 dB for music)
+    pub fn peak_calibrated(&self) -> f32 {
+        calibrate(self.peak_raw(), self.reference())
+    }
+
+    /// Bass level on the `rms_calibrated` scale
+    pub fn bass_calibrated(&self) -> f32 {
+        calibrate(self.bass_raw(), self.reference())
+    }
+
+    /// Raw level of a band, None for the derived ones (kick, motion)
+    fn raw_level(&self, band: AudioBand) -> Option<f32> {
+        match band {
+            AudioBand::Bass => Some(self.bass_raw()),
+            AudioBand::Rms => Some(self.rms_raw()),
+            AudioBand::Peak => Some(self.peak_raw()),
+            AudioBand::Left => Some(self.channel_rms_raw(0)),
+            AudioBand::Right => Some(self.channel_rms_raw(1)),
+            AudioBand::LeftBass => Some(self.channel_bass_raw(0)),
+            AudioBand::RightBass => Some(self.channel_bass_raw(1)),
+            AudioBand::SubBass | AudioBand::LowMid | AudioBand::Mid | AudioBand::HighMid | AudioBand::Treble => {
+                band.spectrum_index().map(|index| self.band_raw(index))
+            }
+            AudioBand::Kick | AudioBand::Motion => None,
+        }
+    }
+
+    /// Bass rise per second that counts as a kick; lower is more sensitive
+    pub fn set_kick_threshold(&mut self, threshold: f32) {
+        self.kick_threshold = threshold.max(0.0);
+    }
+
+    pub fn kick_threshold(&self) -> f32 {
+        self.kick_threshold
+    }
+
+    /// Run the edge-triggered detectors (call once per update, before `level`)
+    pub fn update(&mut self) {
+        self.kick = self.detect_kick();
+    }
+
+    /// Current level of a band (0.0 - 1.0): calibrated once a reference level
+    /// is set, otherwise the boosted linear getters below
+    pub fn level(&self, band: AudioBand) -> f32 {
+        if let (Some(reference), Some(raw)) = (self.reference_db, self.raw_level(band)) {
+            return calibrate(raw, reference);
+        }
+        match band {
+            AudioBand::Bass => self.bass(),
+            AudioBand::Rms => self.rms(),
+            AudioBand::Peak => self.peak(),
+            AudioBand::Kick => self.kick.min(1.0),
+            AudioBand::Left => self.channel_rms(0),
+            AudioBand::Right => self.channel_rms(1),
+            AudioBand::LeftBass => self.channel_bass(0),
+            AudioBand::RightBass => self.channel_bass(1),
+            AudioBand::SubBass | AudioBand::LowMid | AudioBand::Mid | AudioBand::HighMid | AudioBand::Treble => {
+                band.spectrum_index().map_or(0.0, |index| self.bands()[index])
+            }
+            // Not audio; resolved by the caller from the camera
+            AudioBand::Motion => 0.0,
+        }
     }
 
     /// Detect if a kick/transient occurred (call once per frame)
@@ -160,9 +697,16 @@ impl AudioAnalyzer {
         let delta = current_bass - self.prev_bass;
         self.prev_bass = current_bass;
 
+        // As a rate, so the sensitivity doesn't depend on the update rate
+        let now = Instant::now();
+        let Some(elapsed) = self.prev_update.replace(now).map(|prev| now - prev) else {
+            return 0.0;
+        };
+        let rise = delta / elapsed.as_secs_f32().max(0.001);
+
         // Kick detected if bass energy increased significantly
-        if delta > self.kick_threshold {
-            delta * 2.0 // Return intensity scaled
+        if rise > self.kick_threshold {
+            rise * KICK_GAIN_SECS
         } else {
             0.0
         }
@@ -180,3 +724,43 @@ pub fn list_audio_devices() -> Vec<String> {
         })
         .unwrap_or_default()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Magnitude of y[n] = y[n-1] + alpha * (x[n] - y[n-1]) at `freq` Hz
+    fn one_pole_gain(alpha: f64, freq: f64, sample_rate: f64) -> f64 {
+        let w = 2.0 * std::f64::consts::PI * freq / sample_rate;
+        let feedback = 1.0 - alpha;
+        alpha / (1.0 - 2.0 * feedback * w.cos() + feedback * feedback).sqrt()
+    }
+
+    #[test]
+    fn bass_filter_cutoff_is_independent_of_sample_rate() {
+        for sample_rate in [44_100.0, 48_000.0, 96_000.0] {
+            let alpha = one_pole_alpha(BASS_CUTOFF_HZ, sample_rate) as f64;
+            let sample_rate = sample_rate as f64;
+
+            // The gain falls monotonically up to Nyquist, so bisect for -3 dB
+            let half_power = std::f64::consts::FRAC_1_SQRT_2;
+            let (mut low, mut high) = (1.0, sample_rate / 2.0);
+            for _ in 0..60 {
+                let mid = 0.5 * (low + high);
+                if one_pole_gain(alpha, mid, sample_rate) > half_power {
+                    low = mid;
+                } else {
+                    high = mid;
+                }
+            }
+
+            let cutoff = 0.5 * (low + high);
+            assert!(
+                (cutoff - BASS_CUTOFF_HZ as f64).abs() < 1.5,
+                "-3 dB at {:.2} Hz for {} Hz input",
+                cutoff,
+                sample_rate
+            );
+        }
+    }
+}
@@ -0,0 +1,118 @@
+//! Startup defaults file: the current look saved as `name value` lines, in the
+//! same format `--stdin-control` reads, and applied again at the next launch
+
+use crate::control::parse_command;
+use crate::mesh::{LineRender, MeshType};
+use crate::midi::MidiCommand;
+use crate::params::PARAMS;
+use crate::state::{AppState, EXTRA_LFOS};
+use std::fmt::Write;
+use std::path::{Path, PathBuf};
+
+const FILE_NAME: &str = "defaults.txt";
+
+/// Stdin names of the stacked LFO shapes, in state.extra_lfos order
+const EXTRA_LFO_SHAPES: [&str; EXTRA_LFOS] =
+    ["x_lfo2_shape", "x_lfo3_shape", "y_lfo2_shape", "y_lfo3_shape", "z_lfo2_shape", "z_lfo3_shape"];
+
+/// Standard per-user location: $XDG_CONFIG_HOME or ~/.config on Linux/macOS,
+/// %APPDATA% on Windows
+pub fn default_path() -> Option<PathBuf> {
+    let base = std::env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))
+        .or_else(|| std::env::var_os("APPDATA").map(PathBuf::from))?;
+    Some(base.join("spectral_mesh").join(FILE_NAME))
+}
+
+/// Write the current parameters, modes and switches. Keyboard offsets are
+/// folded into the p_lock values, so the file reproduces what's on screen.
+pub fn save(state: &AppState, path: &Path) -> Result<(), String> {
+    let mut out = String::from("# Spectral Mesh defaults (F2 to overwrite)\n# Parameters are normalized 0-1, see --stdin-control in the README\n");
+
+    for spec in &PARAMS {
+        let mut value = state.param(spec.id);
+        if let Some(slot) = spec.id.p_lock_slot() {
+            value += state.keyboard_offset(slot);
+        }
+        let _ = writeln!(out, "{} {:.4}", spec.name, spec.normalize(value));
+    }
+
+    let mesh = match state.mesh_type {
+        MeshType::Triangles => "triangles",
+        MeshType::HorizontalLines => "horizontal",
+        MeshType::VerticalLines => "vertical",
+        MeshType::Grid => "grid",
+    };
+    let on_off = |v: bool| if v { "on" } else { "off" };
+    let line_render = match state.line_render {
+        LineRender::Hard => "hard",
+        LineRender::Smooth => "smooth",
+    };
+    let _ = writeln!(out, "mesh {}", mesh);
+    let _ = writeln!(out, "line_render {}", line_render);
+    let _ = writeln!(out, "z_lfo_shape {}", state.z_lfo_shape.to_i32());
+    let _ = writeln!(out, "x_lfo_shape {}", state.x_lfo_shape.to_i32());
+    let _ = writeln!(out, "y_lfo_shape {}", state.y_lfo_shape.to_i32());
+    for (name, lfo) in EXTRA_LFO_SHAPES.iter().zip(&state.extra_lfos) {
+        let _ = writeln!(out, "{} {}", name, lfo.shape.to_i32());
+    }
+    let _ = writeln!(out, "z_source {}", state.z_source as usize);
+    let _ = writeln!(out, "x_source {}", state.x_source as usize);
+    let _ = writeln!(out, "y_source {}", state.y_source as usize);
+    let _ = writeln!(out, "x_noise_kind {}", state.noise_kind[0] as usize);
+    let _ = writeln!(out, "y_noise_kind {}", state.noise_kind[1] as usize);
+    let _ = writeln!(out, "z_noise_kind {}", state.noise_kind[2] as usize);
+    let _ = writeln!(out, "ripple_shape {}", state.ripples.shape as usize);
+    let _ = writeln!(out, "rotate_x {}", state.rotate_x);
+    let _ = writeln!(out, "rotate_y {}", state.rotate_y);
+    let _ = writeln!(out, "rotate_z {}", state.rotate_z);
+    let _ = writeln!(out, "greyscale {}", on_off(state.greyscale));
+    let _ = writeln!(out, "invert {}", on_off(state.invert));
+    let _ = writeln!(out, "bright_switch {}", on_off(state.bright_switch));
+    let _ = writeln!(out, "bloom {}", on_off(state.bloom));
+    let _ = writeln!(out, "chroma_follow {}", on_off(state.chroma_follow));
+    let _ = writeln!(out, "z_ringmod {}", on_off(state.z_ringmod));
+    let _ = writeln!(out, "x_ringmod {}", on_off(state.x_ringmod));
+    let _ = writeln!(out, "y_ringmod {}", on_off(state.y_ringmod));
+    let _ = writeln!(out, "z_phasemod {}", on_off(state.z_phasemod));
+    let _ = writeln!(out, "x_phasemod {}", on_off(state.x_phasemod));
+    let _ = writeln!(out, "y_phasemod {}", on_off(state.y_phasemod));
+
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir).map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+    }
+    std::fs::write(path, out).map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+}
+
+/// Apply a defaults file. p_lock parameters are written to every step, so
+/// they become the pattern's starting point. Bad lines are logged and skipped.
+pub fn load(state: &mut AppState, path: &Path) -> Result<(), String> {
+    let text = std::fs::read_to_string(path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+
+    for (number, line) in text.lines().enumerate() {
+        match parse_command(line) {
+            Ok(Some(MidiCommand::Param(id, v))) => match id.p_lock_slot() {
+                Some(slot) => state.p_lock.set_all(slot, id.spec().scale(v)),
+                None => state.set_param_direct(id, v),
+            },
+            // Recording, clearing or quantizing the pattern has no place in a
+            // defaults file, and the camera is picked with --video
+            Ok(Some(
+                MidiCommand::RecordStart
+                | MidiCommand::RecordStop
+                | MidiCommand::Reset
+                | MidiCommand::Quantize(..)
+                | MidiCommand::SavePattern
+                | MidiCommand::LoadPattern
+                | MidiCommand::SelectCamera(_),
+            )) => {
+                log::warn!("{}:{}: ignoring '{}'", path.display(), number + 1, line.trim());
+            }
+            Ok(Some(cmd)) => state.process_midi(cmd),
+            Ok(None) => {}
+            Err(e) => log::warn!("{}:{}: {}", path.display(), number + 1, e),
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,162 @@
+//! Text control from stdin: newline-delimited `name value` commands, mapped to
+//! the same `MidiCommand`s a controller would send. Continuous parameters take
+//! a normalized 0-1 value and go through the ParamSpec table, e.g.
+//!
+//! ```text
+//! displace_x 0.7
+//! mesh triangles
+//! z_lfo_shape 2
+//! greyscale on
+//! ```
+
+use crate::mesh::LineRender;
+use crate::midi::MidiCommand;
+use crate::params::ParamId;
+use crate::state::{FlashMode, LfoShape};
+use std::io::BufRead;
+use std::sync::mpsc::{channel, Receiver};
+
+pub struct StdinControl {
+    receiver: Receiver<MidiCommand>,
+}
+
+impl StdinControl {
+    /// Start the reader thread. It exits at end of input.
+    pub fn spawn() -> Self {
+        let (sender, receiver) = channel();
+        std::thread::spawn(move || {
+            for line in std::io::stdin().lock().lines() {
+                let Ok(line) = line else { break };
+                match parse_command(&line) {
+                    Ok(Some(cmd)) => {
+                        if sender.send(cmd).is_err() {
+                            break;
+                        }
+                    }
+                    Ok(None) => {}
+                    Err(e) => log::warn!("stdin: {}", e),
+                }
+            }
+            log::info!("stdin control closed");
+        });
+        Self { receiver }
+    }
+
+    pub fn poll_all(&self) -> Vec<MidiCommand> {
+        self.receiver.try_iter().collect()
+    }
+}
+
+/// Parse one line. Blank lines and `#` comments give `Ok(None)`.
+pub fn parse_command(line: &str) -> Result<Option<MidiCommand>, String> {
+    let line = line.trim();
+    if line.is_empty() || line.starts_with('#') {
+        return Ok(None);
+    }
+
+    let mut parts = line.split_whitespace();
+    let name = parts.next().unwrap_or_default().to_ascii_lowercase();
+    let arg = parts.next();
+    let value = || arg.ok_or_else(|| format!("'{}' needs a value", name));
+    let number = || value()?.parse::<f32>().map_err(|_| format!("'{}': bad number '{}'", name, arg.unwrap_or_default()));
+    let shape = || LfoShape::from_i32(number()? as i32).ok_or_else(|| format!("'{}': expected a shape 0-3", name));
+
+    if let Some(id) = ParamId::from_name(&name) {
+        return Ok(Some(MidiCommand::Param(id, number()?)));
+    }
+
+    let cmd = match name.as_str() {
+        "mesh" => match value()? {
+            "triangles" => MidiCommand::SetTriangleMesh,
+            "grid" | "wireframe" => MidiCommand::SetGridMesh,
+            "horizontal" => MidiCommand::SetHorizontalLines,
+            "vertical" => MidiCommand::SetVerticalLines,
+            other => return Err(format!("unknown mesh '{}' (triangles, grid, horizontal, vertical)", other)),
+        },
+        "line_render" => match value()? {
+            "hard" => MidiCommand::SetLineRender(LineRender::Hard),
+            "smooth" => MidiCommand::SetLineRender(LineRender::Smooth),
+            other => return Err(format!("unknown line render '{}' (hard, smooth)", other)),
+        },
+        "camera" => match value()? {
+            "next" => MidiCommand::SelectCamera(None),
+            index => MidiCommand::SelectCamera(Some(
+                index.parse().map_err(|_| format!("'{}': expected a camera index or 'next', got '{}'", name, index))?,
+            )),
+        },
+        "flash" => match value()? {
+            "off" => MidiCommand::SetFlash(FlashMode::Off),
+            "brighten" => MidiCommand::SetFlash(FlashMode::Brighten),
+            "invert" => MidiCommand::SetFlash(FlashMode::Invert),
+            other => return Err(format!("unknown flash '{}' (off, brighten, invert)", other)),
+        },
+        "z_lfo_shape" => MidiCommand::ZLfoShape(shape()?),
+        "x_lfo_shape" => MidiCommand::XLfoShape(shape()?),
+        "y_lfo_shape" => MidiCommand::YLfoShape(shape()?),
+        "x_lfo2_shape" => MidiCommand::ExtraLfoShape(0, shape()?),
+        "x_lfo3_shape" => MidiCommand::ExtraLfoShape(1, shape()?),
+        "y_lfo2_shape" => MidiCommand::ExtraLfoShape(2, shape()?),
+        "y_lfo3_shape" => MidiCommand::ExtraLfoShape(3, shape()?),
+        "z_lfo2_shape" => MidiCommand::ExtraLfoShape(4, shape()?),
+        "z_lfo3_shape" => MidiCommand::ExtraLfoShape(5, shape()?),
+        "ripple_shape" => MidiCommand::RippleShape(number()?.max(0.0) as usize),
+        "z_source" => MidiCommand::ZSource(number()?.max(0.0) as usize),
+        "x_source" => MidiCommand::XSource(number()?.max(0.0) as usize),
+        "y_source" => MidiCommand::YSource(number()?.max(0.0) as usize),
+        "x_noise_kind" => MidiCommand::NoiseKind(0, number()?.max(0.0) as usize),
+        "y_noise_kind" => MidiCommand::NoiseKind(1, number()?.max(0.0) as usize),
+        "z_noise_kind" => MidiCommand::NoiseKind(2, number()?.max(0.0) as usize),
+        "rotate_x" => MidiCommand::RotateX(number()?),
+        "rotate_y" => MidiCommand::RotateY(number()?),
+        "rotate_z" => MidiCommand::RotateZ(number()?),
+        "greyscale" => MidiCommand::Greyscale(switch(&name, value()?)?),
+        "invert" => MidiCommand::Invert(switch(&name, value()?)?),
+        "bright_switch" => MidiCommand::BrightSwitch(switch(&name, value()?)?),
+        "freeze_noise" => MidiCommand::FreezeNoise(switch(&name, value()?)?),
+        "bloom" => MidiCommand::Bloom(switch(&name, value()?)?),
+        "chroma_follow" => MidiCommand::ChromaFollow(switch(&name, value()?)?),
+        "z_ringmod" => MidiCommand::ZRingMod(switch(&name, value()?)?),
+        "x_ringmod" => MidiCommand::XRingMod(switch(&name, value()?)?),
+        "y_ringmod" => MidiCommand::YRingMod(switch(&name, value()?)?),
+        "z_phasemod" => MidiCommand::ZPhaseMod(switch(&name, value()?)?),
+        "x_phasemod" => MidiCommand::XPhaseMod(switch(&name, value()?)?),
+        "y_phasemod" => MidiCommand::YPhaseMod(switch(&name, value()?)?),
+        "record" => {
+            if switch(&name, value()?)? {
+                MidiCommand::RecordStart
+            } else {
+                MidiCommand::RecordStop
+            }
+        }
+        "reset" => MidiCommand::Reset,
+        "save_pattern" => MidiCommand::SavePattern,
+        "load_pattern" => MidiCommand::LoadPattern,
+        // `quantize 16` for every parameter, `quantize displace_x 16` for one
+        "quantize" => match (arg, parts.next()) {
+            (Some(param), Some(divisions)) => {
+                let slot = ParamId::from_name(param)
+                    .and_then(ParamId::p_lock_slot)
+                    .ok_or_else(|| format!("'{}': '{}' is not a recordable parameter", name, param))?;
+                MidiCommand::Quantize(Some(slot), divisions_arg(&name, divisions)?)
+            }
+            _ => MidiCommand::Quantize(None, divisions_arg(&name, value()?)?),
+        },
+        _ => return Err(format!("unknown command '{}'", name)),
+    };
+    Ok(Some(cmd))
+}
+
+fn divisions_arg(name: &str, value: &str) -> Result<usize, String> {
+    match value.parse::<usize>() {
+        Ok(divisions) if divisions > 0 => Ok(divisions),
+        _ => Err(format!("'{}': expected a step count, got '{}'", name, value)),
+    }
+}
+
+fn switch(name: &str, value: &str) -> Result<bool, String> {
+    match value.to_ascii_lowercase().as_str() {
+        "on" | "1" | "true" | "start" => Ok(true),
+        "off" | "0" | "false" | "stop" => Ok(false),
+        other => Err(format!("'{}': expected on/off, got '{}'", name, other)),
+    }
+}
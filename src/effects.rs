@@ -0,0 +1,150 @@
+//! Post-effect chains: an ordered list of WGSL snippets applied to the mesh
+//! color after the built-in effects, assembled into the mesh shader at startup.
+//!
+//! A chain file has one effect per line, `name [param ...]` with up to four
+//! numeric parameters (missing ones take the effect's defaults):
+//!
+//! ```text
+//! contrast 1.3 0.8
+//! posterize 6
+//! vignette 0.7 0.4
+//! file my_effect.wgsl 0.5
+//! ```
+//!
+//! `file <path>` loads a custom snippet. Every snippet defines
+//! `fn effect(color: vec4<f32>, uv: vec2<f32>, params: vec4<f32>) -> vec4<f32>`
+//! taking and returning straight (not premultiplied) color, with `uv` the
+//! video coordinate of the fragment. Snippets may read `uniforms`.
+
+use std::fmt::Write;
+use std::path::{Path, PathBuf};
+
+/// Signature of the pass-through post-effect function in displace.wgsl,
+/// which is replaced by the chain
+const HOOK_SIGNATURE: &str = "fn post_effects(color: vec4<f32>, uv: vec2<f32>) -> vec4<f32>";
+
+/// Entry point every snippet defines, renamed per chain entry so an effect
+/// can appear more than once
+const SNIPPET_ENTRY: &str = "fn effect(";
+
+/// Built-in effects: name, snippet and default parameters
+const BUILTIN: [(&str, &str, [f32; 4]); 5] = [
+    ("vignette", include_str!("../shaders/effects/vignette.wgsl"), [0.6, 0.5, 0.0, 0.0]),
+    ("posterize", include_str!("../shaders/effects/posterize.wgsl"), [6.0, 0.0, 0.0, 0.0]),
+    ("contrast", include_str!("../shaders/effects/contrast.wgsl"), [1.2, 1.0, 0.0, 0.0]),
+    ("scanlines", include_str!("../shaders/effects/scanlines.wgsl"), [240.0, 0.3, 0.0, 0.0]),
+    ("tint", include_str!("../shaders/effects/tint.wgsl"), [1.0, 0.6, 0.3, 1.0]),
+];
+
+pub struct Effect {
+    /// Built-in name or snippet path, for logging
+    pub name: String,
+    source: String,
+    pub params: [f32; 4],
+}
+
+#[derive(Default)]
+pub struct EffectChain {
+    pub effects: Vec<Effect>,
+}
+
+impl EffectChain {
+    /// Read a chain file. Custom snippet paths are relative to the file.
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let text = std::fs::read_to_string(path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+        let dir = path.parent().map(Path::to_path_buf).unwrap_or_default();
+        Self::parse(&text, &dir).map_err(|e| format!("{}: {}", path.display(), e))
+    }
+
+    fn parse(text: &str, dir: &Path) -> Result<Self, String> {
+        let mut effects = Vec::new();
+        for (number, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let effect = Self::parse_line(line, dir).map_err(|e| format!("line {}: {}", number + 1, e))?;
+            effects.push(effect);
+        }
+        Ok(Self { effects })
+    }
+
+    fn parse_line(line: &str, dir: &Path) -> Result<Effect, String> {
+        let mut words = line.split_whitespace();
+        let name = words.next().unwrap_or_default();
+
+        let (name, source, mut params) = if name == "file" {
+            let file = words.next().ok_or("expected a snippet path after 'file'")?;
+            let file: PathBuf = dir.join(file);
+            let source =
+                std::fs::read_to_string(&file).map_err(|e| format!("failed to read {}: {}", file.display(), e))?;
+            if !source.contains(SNIPPET_ENTRY) {
+                return Err(format!("{} does not define fn effect(color, uv, params)", file.display()));
+            }
+            (file.display().to_string(), source, [0.0; 4])
+        } else {
+            let (_, source, defaults) = BUILTIN.iter().find(|(builtin, ..)| *builtin == name).ok_or_else(|| {
+                let names: Vec<&str> = BUILTIN.iter().map(|(builtin, ..)| *builtin).collect();
+                format!("unknown effect '{}' (built in: {}; or file <path>)", name, names.join(", "))
+            })?;
+            (name.to_string(), source.to_string(), *defaults)
+        };
+
+        for (i, word) in words.enumerate() {
+            let param = params.get_mut(i).ok_or("at most 4 parameters")?;
+            *param = word.parse().map_err(|_| format!("'{}' is not a number", word))?;
+        }
+        Ok(Effect { name, source, params })
+    }
+
+    /// `base` (displace.wgsl) with its pass-through post-effect function
+    /// replaced by calls to each effect of the chain in order
+    pub fn shader_source(&self, base: &str) -> Result<String, String> {
+        // From the signature to the closing brace of the one-line body, so the
+        // match doesn't depend on the file's line endings or indentation
+        let no_hook = || "mesh shader has no post_effects hook".to_string();
+        let start = base.find(HOOK_SIGNATURE).ok_or_else(no_hook)?;
+        let end = start + base[start..].find('}').ok_or_else(no_hook)? + 1;
+
+        let mut snippets = String::new();
+        let mut calls = String::new();
+        for (i, effect) in self.effects.iter().enumerate() {
+            let entry = format!("fn fx_{}(", i);
+            let _ = writeln!(snippets, "// {}\n{}", effect.name, effect.source.replacen(SNIPPET_ENTRY, &entry, 1));
+            let [a, b, c, d] = effect.params;
+            let _ = writeln!(
+                calls,
+                "    out = fx_{}(out, uv, vec4<f32>({:?}, {:?}, {:?}, {:?}));",
+                i, a, b, c, d
+            );
+        }
+        let chain = format!(
+            "{}fn post_effects(color: vec4<f32>, uv: vec2<f32>) -> vec4<f32> {{\n    var out = color;\n{}    return out;\n}}",
+            snippets, calls
+        );
+        Ok(format!("{}{}{}", &base[..start], chain, &base[end..]))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn chain_splices_into_crlf_shader() {
+        let base = "fn shade() {\r\n}\r\n\r\nfn post_effects(color: vec4<f32>, uv: vec2<f32>) -> vec4<f32> {\r\n    return color;\r\n}\r\n\r\n@fragment\r\nfn fs_main() {\r\n}\r\n";
+        let chain = EffectChain::parse("posterize 4", Path::new("")).unwrap();
+        let source = chain.shader_source(base).unwrap();
+
+        assert!(!source.contains("return color;"), "pass-through left in:\n{}", source);
+        assert!(source.contains("out = fx_0(out, uv, vec4<f32>(4.0, 0.0, 0.0, 0.0));"));
+        assert!(source.starts_with("fn shade() {\r\n}\r\n\r\n"));
+        assert!(source.ends_with("\r\n\r\n@fragment\r\nfn fs_main() {\r\n}\r\n"));
+    }
+
+    #[test]
+    fn shader_without_hook_is_an_error() {
+        let chain = EffectChain::parse("posterize", Path::new("")).unwrap();
+        assert!(chain.shader_source("fn shade() {}\n").is_err());
+    }
+}
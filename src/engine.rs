@@ -0,0 +1,381 @@
+//! The effect engine without the app around it: parameter state, modulation,
+//! noise, mesh and renderer. The binary drives it from a window, cameras, MIDI
+//! and the keyboard; embedders feed frames, audio and commands themselves.
+//!
+//! ```no_run
+//! use spectral_mesh::{midi::MidiCommand, SpectralMesh};
+//!
+//! let mut engine = pollster::block_on(SpectralMesh::headless(1280, 720, 640, 360)).unwrap();
+//! let target = engine.renderer.device().create_texture(&wgpu::TextureDescriptor {
+//!     label: None,
+//!     size: wgpu::Extent3d { width: 1280, height: 720, depth_or_array_layers: 1 },
+//!     mip_level_count: 1,
+//!     sample_count: 1,
+//!     dimension: wgpu::TextureDimension::D2,
+//!     format: engine.renderer.format(),
+//!     usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
+//!     view_formats: &[],
+//! });
+//! let view = target.create_view(&Default::default());
+//!
+//! engine.apply_command(MidiCommand::DisplaceX(0.8));
+//! let frame = vec![128u8; 640 * 360 * 4];
+//! engine.feed_frame(&frame);
+//! engine.update();
+//! engine.render_to_texture(&view);
+//! ```
+
+use crate::audio::{AudioAnalyzer, AudioBand};
+use crate::mesh::{Mesh, MeshType};
+use crate::midi::MidiCommand;
+use crate::noise::{axis_seeds, NoiseBank, DEFAULT_NOISE_HEIGHT, DEFAULT_NOISE_WIDTH, MAX_NOISE_SIZE};
+use crate::renderer::Renderer;
+use crate::state::{AppState, MAX_KICK_RIPPLE};
+use std::path::Path;
+
+/// Kick level below which no ripple is spawned
+const KICK_RIPPLE_EPSILON: f32 = 0.01;
+
+pub struct SpectralMesh {
+    pub renderer: Renderer,
+    pub state: AppState,
+    /// Analyzer behind the audio modulation, if any
+    pub audio: Option<AudioAnalyzer>,
+    noise_bank: NoiseBank,
+    /// Processing resolution, the size of every fed frame
+    video_width: u32,
+    video_height: u32,
+    last_mesh_scale: u32,
+    needs_mesh_rebuild: bool,
+    /// Noise changed since the last upload; frozen noise is uploaded once
+    noise_dirty: bool,
+}
+
+impl SpectralMesh {
+    /// Engine around an existing renderer, for frames of `video_width` x `video_height`
+    pub fn new(renderer: Renderer, state: AppState, video_width: u32, video_height: u32) -> Self {
+        Self {
+            renderer,
+            state,
+            audio: None,
+            noise_bank: NoiseBank::new(DEFAULT_NOISE_WIDTH, DEFAULT_NOISE_HEIGHT),
+            video_width,
+            video_height,
+            last_mesh_scale: 100,
+            needs_mesh_rebuild: false,
+            noise_dirty: true,
+        }
+    }
+
+    /// Engine without a window, drawing `width` x `height` output with `render_to_texture`
+    pub async fn headless(width: u32, height: u32, video_width: u32, video_height: u32) -> Result<Self, String> {
+        let renderer = Renderer::new_headless(width, height).await?;
+        Ok(Self::new(renderer, AppState::new(video_width, video_height), video_width, video_height))
+    }
+
+    /// Apply a control change, exactly as if it came from MIDI
+    pub fn apply_command(&mut self, cmd: MidiCommand) {
+        self.state.process_midi(cmd);
+    }
+
+    /// Upload the next RGBA video frame (processing resolution, bottom row first)
+    /// and run the video analysis on it. Alpha is straight; transparent pixels
+    /// let the clear color (or a transparent window) through.
+    pub fn feed_frame(&mut self, rgba: &[u8]) {
+        self.renderer.update_video_texture(rgba, self.video_width, self.video_height);
+        self.state.auto_center.analyze(rgba, self.video_width, self.video_height);
+        self.state.motion.analyze(rgba, self.video_width, self.video_height);
+    }
+
+    /// Upload a frame of the second source, crossfaded by video_mix
+    pub fn feed_frame2(&mut self, rgba: &[u8]) {
+        self.renderer.update_video_texture2(rgba, self.video_width, self.video_height);
+    }
+
+    /// Analyze interleaved audio samples from the caller. Replaces a device-backed
+    /// analyzer, or one with another format, on first use.
+    pub fn feed_audio(&mut self, samples: &[f32], channels: u16, sample_rate: u32) {
+        let format = (channels, sample_rate);
+        if self.audio.as_ref().and_then(AudioAnalyzer::external_format) != Some(format) {
+            self.audio = Some(AudioAnalyzer::external(channels, sample_rate));
+        }
+        if let Some(audio) = &mut self.audio {
+            audio.push_samples(samples);
+        }
+    }
+
+    /// Export the current noise fields as greyscale PNGs into `dir`
+    pub fn save_noise(&self, dir: &Path) -> Result<(), String> {
+        self.noise_bank.save_png(dir)
+    }
+
+    /// Load noise fields exported by `save_noise` and freeze them in place
+    pub fn load_noise(&mut self, dir: &Path) -> Result<(), String> {
+        self.noise_bank.load_png(dir)?;
+        self.state.noise_frozen = true;
+        self.noise_dirty = true;
+        Ok(())
+    }
+
+    /// Reseed the noise fields (see `NoiseBank::with_seed`); the same seed and
+    /// controls always give the same noise. Seed 0 is the default.
+    pub fn set_noise_seed(&mut self, seed: u32) {
+        self.noise_bank.reseed(axis_seeds(seed));
+        self.noise_dirty = true;
+    }
+
+    /// Noise texture resolution, clamped to 1 - `MAX_NOISE_SIZE` per side. The
+    /// GPU textures follow on the next upload.
+    pub fn set_noise_size(&mut self, width: u32, height: u32) {
+        let width = width.clamp(1, MAX_NOISE_SIZE);
+        let height = height.clamp(1, MAX_NOISE_SIZE);
+        if (width, height) != self.noise_bank.size() {
+            self.noise_bank.resize(width, height);
+            self.noise_dirty = true;
+        }
+    }
+
+    /// Rebuild the mesh on the next update even if the grid density didn't change
+    pub fn request_mesh_rebuild(&mut self) {
+        self.needs_mesh_rebuild = true;
+    }
+
+    /// Advance one logic step: p_lock playback, ripples, audio and motion
+    /// modulation, LFO phases and noise
+    pub fn update(&mut self) {
+        // Update p_lock system
+        self.state.p_lock.update();
+        if self.state.p_lock.take_loop_start() && self.state.loop_flash > 0.0 {
+            self.state.strobe.pulse(self.state.loop_flash);
+        }
+        self.state.ripples.update();
+        self.state.auto_center_offset = self.state.auto_center.update();
+
+        // Audio modulation - aesthetic effect: bass modulates displacement and LFO
+        if let Some(ref mut audio) = self.audio {
+            audio.update();
+            let sensitivity = self.state.audio_sensitivity;
+            let bass = audio.bass() * sensitivity;
+            let rms = audio.rms() * sensitivity;
+
+            // Reduced amplitude for subtle global effect
+            self.state.audio_mod_displacement = bass * 2.0;
+            self.state.audio_mod_lfo = rms * 1.0;
+            self.state.audio_mod_z = bass * 0.02;
+            self.state.audio_envelope = bass;
+
+            // Audio vibration effect - lines tremble with the music
+            // Phase advances fast for vibration effect
+            let phase_speed = 0.5 + bass * 1.5; // Faster base speed, accelerates with bass
+            self.state.audio_wave_phase += phase_speed;
+
+            // Amplitude pulses with bass - fast attack, slower decay
+            let target_amp = bass * 0.08; // Vibration amplitude
+            // Fast attack (0.4), slower decay (0.9) for punchy response
+            if target_amp > self.state.audio_wave_amp {
+                self.state.audio_wave_amp = self.state.audio_wave_amp * 0.6 + target_amp * 0.4;
+            } else {
+                self.state.audio_wave_amp = self.state.audio_wave_amp * 0.92 + target_amp * 0.08;
+            }
+
+            // Frequency not used for vibration but keep for potential future use
+            self.state.audio_wave_freq = 10.0 + rms * 20.0;
+        }
+
+        // Camera motion, smoothed like the audio envelopes
+        self.state.motion_energy = follow_envelope(self.state.motion_energy, self.state.motion.energy());
+
+        // Lines thicken on the beat - same fast attack, slower decay
+        let target_stroke = self.band_level(self.state.audio_stroke_band) * self.state.audio_stroke_gain;
+        self.state.audio_stroke_mod = follow_envelope(self.state.audio_stroke_mod, target_stroke);
+
+        // RGB split widens with the music
+        let target_chroma = self.band_level(self.state.audio_chroma_band) * self.state.audio_chroma_gain;
+        self.state.audio_chroma_mod = follow_envelope(self.state.audio_chroma_mod, target_chroma);
+
+        // Rotation nudged on the beat, easing back to the base rotation
+        let rotate_level = self.band_level(self.state.audio_rotate_band);
+        for (offset, gain) in self.state.audio_rotate_mod.iter_mut().zip(self.state.audio_rotate_gain) {
+            *offset = follow_envelope(*offset, rotate_level * gain);
+        }
+
+        // Breathing zoom: the whole mesh swells with the beat, with its own envelope
+        let zoom_target = self.band_level(self.state.audio_zoom_band) * self.state.audio_zoom_amount;
+        self.state.audio_zoom_mod = follow_envelope_with(
+            self.state.audio_zoom_mod,
+            zoom_target,
+            self.state.audio_zoom_attack,
+            self.state.audio_zoom_release,
+        );
+
+        // Strobe on big hits
+        let onset = self.band_level(AudioBand::Kick);
+        self.state.strobe.onset(onset);
+
+        // Ripples on kicks, capped so a loud transient stays a ripple
+        if self.state.kick_ripples && onset > KICK_RIPPLE_EPSILON {
+            self.state.ripples.kick(onset.min(MAX_KICK_RIPPLE));
+        }
+
+        // Per-axis displacement, e.g. left channel on X and right on Y
+        for axis in 0..2 {
+            let target = self.band_level(self.state.audio_displace_band[axis]) * self.state.audio_displace_gain[axis];
+            self.state.audio_displace_mod[axis] = follow_envelope(self.state.audio_displace_mod[axis], target);
+        }
+
+        // Calculate render params
+        let params = self.state.calculate_render_params();
+
+        // Update LFO phases, wrapped to one cycle so f32 keeps its precision
+        // over long runs. Every shape repeats every 2π, so the wrap is seamless.
+        self.state.z_lfo_arg = wrap_phase(self.state.z_lfo_arg + params.z_lfo_arg);
+        self.state.x_lfo_arg = wrap_phase(self.state.x_lfo_arg + params.x_lfo_arg);
+        self.state.y_lfo_arg = wrap_phase(self.state.y_lfo_arg + params.y_lfo_arg);
+        for lfo in &mut self.state.extra_lfos {
+            lfo.phase = wrap_phase(lfo.phase + lfo.rate);
+        }
+        let lfo_rates = [params.x_lfo_arg, params.y_lfo_arg, params.z_lfo_arg];
+        for ((theta, rate), scale) in self.state.noise_theta.iter_mut().zip(lfo_rates).zip(self.state.noise_time_scale) {
+            *theta += rate * scale;
+        }
+
+        // Update noise textures, unless frozen on a snapshot
+        if !self.state.noise_frozen {
+            self.noise_bank.set_kinds(self.state.noise_kind);
+            self.noise_bank.update(
+                self.state.noise_theta[0],
+                self.state.noise_resolution[0],
+                self.state.noise_theta[1],
+                self.state.noise_resolution[1],
+                self.state.noise_theta[2],
+                self.state.noise_resolution[2],
+            );
+            self.noise_dirty = true;
+        }
+
+        // Check if mesh needs rebuild
+        let new_scale = params.scale.clamp(1, 127);
+        if new_scale != self.last_mesh_scale || self.needs_mesh_rebuild {
+            self.last_mesh_scale = new_scale;
+            self.needs_mesh_rebuild = false;
+            self.state.scale = new_scale;
+        }
+    }
+
+    /// Draw a frame to the window
+    pub fn render(&mut self) -> Result<(), wgpu::SurfaceError> {
+        self.prepare_frame();
+        self.renderer.render()
+    }
+
+    /// Draw a frame into `view`, a texture of the renderer's device with its
+    /// output size and `format()`
+    pub fn render_to_texture(&mut self, view: &wgpu::TextureView) {
+        self.prepare_frame();
+        self.renderer.render_to(view);
+    }
+
+    /// Draw a frame offscreen at the output size and read it back as tightly
+    /// packed RGBA8, top row first. Blocks until the GPU is done.
+    pub fn render_to_rgba(&mut self) -> Vec<u8> {
+        self.prepare_frame();
+        self.renderer.render_to_rgba()
+    }
+
+    /// Upload noise, mesh and uniforms for the next draw
+    fn prepare_frame(&mut self) {
+        // Update noise textures
+        if self.noise_dirty {
+            let (width, height) = self.noise_bank.size();
+            self.renderer.update_noise_texture(0, self.noise_bank.x_noise.pixels(), width, height);
+            self.renderer.update_noise_texture(1, self.noise_bank.y_noise.pixels(), width, height);
+            self.renderer.update_noise_texture(2, self.noise_bank.z_noise.pixels(), width, height);
+            self.noise_dirty = false;
+        }
+
+        // Rebuild mesh if needed
+        let mesh = match self.state.mesh_type {
+            MeshType::Triangles => {
+                Mesh::triangle_mesh(self.state.scale, self.video_width as f32, self.video_height as f32)
+            }
+            MeshType::HorizontalLines => {
+                Mesh::horizontal_line_mesh(self.state.scale, self.video_width as f32, self.video_height as f32)
+            }
+            MeshType::VerticalLines => {
+                Mesh::vertical_line_mesh(self.state.scale, self.video_width as f32, self.video_height as f32)
+            }
+            MeshType::Grid => {
+                Mesh::grid_mesh(self.state.scale, self.video_width as f32, self.video_height as f32)
+            }
+        }
+        .with_stroke(self.state.effective_stroke_weight(), self.state.line_render);
+        self.renderer.update_mesh(&mesh);
+
+        // Update uniforms
+        let [r, g, b, a] = self.state.strobe.apply(self.state.clear_color);
+        let level = self.state.output_level.clamp(0.0, 1.0);
+        self.renderer.set_clear_color([r * level, g * level, b * level, a]);
+        self.renderer.update_uniforms(&self.state);
+    }
+
+    /// Level of a modulation source (0 - 1): audio bands scaled by the audio
+    /// sensitivity, or the camera's motion energy
+    fn band_level(&self, band: AudioBand) -> f32 {
+        match band {
+            AudioBand::Motion => self.state.motion_energy,
+            _ => self.audio.as_ref().map_or(0.0, |audio| audio.level(band) * self.state.audio_sensitivity),
+        }
+    }
+}
+
+/// LFO phase brought back into 0 - 2π, the period of every shape in
+/// displace.wgsl's `oscillate` (sine, square, triangle) and of the chroma
+/// split direction
+fn wrap_phase(phase: f32) -> f32 {
+    phase.rem_euclid(std::f32::consts::TAU)
+}
+
+/// Fast attack, slower decay toward `target`, for punchy audio-driven values
+fn follow_envelope(current: f32, target: f32) -> f32 {
+    follow_envelope_with(current, target, 0.4, 0.08)
+}
+
+/// Move toward `target` by the `attack` fraction per update when it grows,
+/// by `release` when it falls
+fn follow_envelope_with(current: f32, target: f32, attack: f32, release: f32) -> f32 {
+    let k = if target.abs() > current.abs() { attack } else { release };
+    current + (target - current) * k.clamp(0.0, 1.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::f32::consts::TAU;
+
+    #[test]
+    fn wrap_phase_stays_bounded_and_continuous() {
+        // A rate that doesn't divide 2π, so every frame lands somewhere new
+        let rate = 0.0137_f32;
+        let mut phase = 0.0_f32;
+        let mut wraps = 0;
+        for frame in 1..=1_000_000_u32 {
+            let unwrapped = phase + rate;
+            phase = wrap_phase(unwrapped);
+            assert!((0.0..TAU).contains(&phase), "phase {} out of range at frame {}", phase, frame);
+            if phase < unwrapped {
+                wraps += 1;
+            }
+
+            // The wrap must not show up in what the shader sees
+            assert!((phase.sin() - unwrapped.sin()).abs() < 1e-5, "sin jumps at frame {}", frame);
+            assert!((phase.cos() - unwrapped.cos()).abs() < 1e-5, "cos jumps at frame {}", frame);
+        }
+        assert!(wraps > 0);
+
+        // Still on time after a million frames, where an unwrapped f32
+        // would have lost most of its precision
+        let exact = 1_000_000.0 * rate as f64;
+        let drift = (phase as f64 - exact.rem_euclid(std::f64::consts::TAU)).sin().abs();
+        assert!(drift < 0.02, "phase drifted by {} rad", drift);
+    }
+}
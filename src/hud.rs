@@ -0,0 +1,154 @@
+//! Parameter overlay: live LFO, mesh, audio and p_lock values as a few lines
+//! of text, rasterized on the CPU into a small premultiplied RGBA image over a
+//! dimmed backing so it stays legible on any feed. The renderer draws it in
+//! the top left corner of the window.
+
+use crate::audio::AudioAnalyzer;
+use crate::state::{AppState, RenderParams};
+use embedded_graphics::mono_font::ascii::FONT_6X10;
+use embedded_graphics::mono_font::MonoTextStyle;
+use embedded_graphics::pixelcolor::BinaryColor;
+use embedded_graphics::prelude::*;
+use embedded_graphics::text::{Baseline, Text};
+use std::fmt::Write;
+
+/// Text area in characters of the 6x10 font
+const COLUMNS: u32 = 40;
+const LINES: u32 = 7;
+const GLYPH_WIDTH: u32 = 6;
+const GLYPH_HEIGHT: u32 = 10;
+/// Backing margin around the text, in pixels
+const PADDING: u32 = 4;
+/// Opacity of the black backing behind the text
+const BACKING_ALPHA: u8 = 170;
+
+pub struct Hud {
+    /// Text of this update, and the text the pixels show; only redrawn on change
+    text: String,
+    drawn: String,
+    /// Premultiplied RGBA, top row first
+    pixels: Vec<u8>,
+}
+
+impl Default for Hud {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Hud {
+    pub fn new() -> Self {
+        Self {
+            text: String::with_capacity((COLUMNS * LINES) as usize),
+            drawn: String::with_capacity((COLUMNS * LINES) as usize),
+            pixels: vec![0u8; (Self::width() * Self::height() * 4) as usize],
+        }
+    }
+
+    /// Image size in pixels
+    pub fn width() -> u32 {
+        COLUMNS * GLYPH_WIDTH + 2 * PADDING
+    }
+
+    pub fn height() -> u32 {
+        LINES * GLYPH_HEIGHT + 2 * PADDING
+    }
+
+    /// Premultiplied RGBA of the last `update`, top row first
+    pub fn pixels(&self) -> &[u8] {
+        &self.pixels
+    }
+
+    /// Lay out the current values and redraw the image if any of them changed
+    /// at the shown precision. Returns whether the pixels changed.
+    pub fn update(&mut self, state: &AppState, audio: Option<&AudioAnalyzer>, fps: f32) -> bool {
+        let params = state.calculate_render_params();
+        self.text.clear();
+        let _ = self.write_text(state, &params, audio, fps);
+        if self.text == self.drawn {
+            return false;
+        }
+        std::mem::swap(&mut self.text, &mut self.drawn);
+        self.draw();
+        true
+    }
+
+    fn write_text(
+        &mut self,
+        state: &AppState,
+        params: &RenderParams,
+        audio: Option<&AudioAnalyzer>,
+        fps: f32,
+    ) -> std::fmt::Result {
+        let text = &mut self.text;
+        writeln!(text, "{:5.1} fps  grid {:3}  {:?}", fps, params.scale, state.mesh_type)?;
+        writeln!(text, "{:<4}{:>6}  {:>6}  {:>6}", "LFO", "freq", "amp", "rate")?;
+        let lfos = [
+            ("X", params.x_frequency, params.x_lfo_amp, params.x_lfo_arg),
+            ("Y", params.y_frequency, params.y_lfo_amp, params.y_lfo_arg),
+            ("Z", params.z_frequency, params.z_lfo_amp, params.z_lfo_arg),
+        ];
+        for (axis, frequency, amp, rate) in lfos {
+            writeln!(text, "{:<4}{:6.2}  {:6.3}  {:6.3}", axis, frequency, amp, rate)?;
+        }
+        match audio {
+            Some(audio) => writeln!(
+                text,
+                "audio bass {:.2}  rms {:.2}  peak {:.2}",
+                audio.bass(),
+                audio.rms(),
+                audio.peak()
+            )?,
+            None => writeln!(text, "audio off")?,
+        }
+        let p_lock = &state.p_lock;
+        write!(text, "p_lock step {:3}/{}", p_lock.current_step() + 1, p_lock.length())?;
+        if p_lock.recording {
+            write!(text, "  REC")?;
+        }
+        Ok(())
+    }
+
+    /// Rasterize `drawn` over the backing
+    fn draw(&mut self) {
+        for pixel in self.pixels.chunks_exact_mut(4) {
+            pixel.copy_from_slice(&[0, 0, 0, BACKING_ALPHA]);
+        }
+        let mut canvas = Canvas { pixels: &mut self.pixels };
+        let style = MonoTextStyle::new(&FONT_6X10, BinaryColor::On);
+        for (row, line) in self.drawn.lines().take(LINES as usize).enumerate() {
+            let position = Point::new(PADDING as i32, (PADDING + row as u32 * GLYPH_HEIGHT) as i32);
+            let _ = Text::with_baseline(line, position, style, Baseline::Top).draw(&mut canvas);
+        }
+    }
+}
+
+/// The overlay image as an embedded-graphics target, white where text is set
+struct Canvas<'a> {
+    pixels: &'a mut [u8],
+}
+
+impl OriginDimensions for Canvas<'_> {
+    fn size(&self) -> Size {
+        Size::new(Hud::width(), Hud::height())
+    }
+}
+
+impl DrawTarget for Canvas<'_> {
+    type Color = BinaryColor;
+    type Error = std::convert::Infallible;
+
+    fn draw_iter<I>(&mut self, pixels: I) -> Result<(), Self::Error>
+    where
+        I: IntoIterator<Item = Pixel<Self::Color>>,
+    {
+        let (width, height) = (Hud::width() as i32, Hud::height() as i32);
+        for Pixel(point, color) in pixels {
+            if color.is_on() && (0..width).contains(&point.x) && (0..height).contains(&point.y) {
+                let index = ((point.y * width + point.x) * 4) as usize;
+                self.pixels[index..index + 4].copy_from_slice(&[255; 4]);
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,25 @@
+//! Spectral Mesh effect engine. The `spectral_mesh` binary is a thin app over
+//! this library; [`SpectralMesh`] is the entry point for embedding the effect
+//! in other programs.
+
+pub mod audio;
+pub mod config;
+pub mod control;
+pub mod effects;
+pub mod engine;
+pub mod hud;
+pub mod mesh;
+pub mod midi;
+pub mod ndi;
+pub mod noise;
+pub mod p_lock;
+pub mod params;
+pub mod renderer;
+pub mod schedule;
+pub mod session;
+pub mod state;
+pub mod terminal;
+pub mod tracking;
+pub mod video;
+
+pub use engine::SpectralMesh;
@@ -1,25 +1,33 @@
-mod audio;
-mod mesh;
-mod midi;
-mod noise;
-mod p_lock;
-mod renderer;
-mod state;
-mod video;
-
-use audio::AudioAnalyzer;
 use clap::Parser;
-use mesh::Mesh;
-use midi::MidiHandler;
-use noise::NoiseBank;
-use renderer::Renderer;
-use state::AppState;
-use video::{DummyVideoSource, VideoCapture};
+use spectral_mesh::audio::{self, AudioAnalyzer, AudioBand, DEFAULT_KICK_THRESHOLD};
+use spectral_mesh::control::StdinControl;
+use spectral_mesh::effects::EffectChain;
+use spectral_mesh::hud::Hud;
+use spectral_mesh::mesh::LineRender;
+use spectral_mesh::midi::{default_midi_map, load_midi_map, MidiCommand, MidiHandler, MidiMap};
+use spectral_mesh::ndi::NdiSender;
+use spectral_mesh::noise::{self, NoiseKind};
+use spectral_mesh::p_lock::PLockSystem;
+use spectral_mesh::params::ParamId;
+use spectral_mesh::renderer::{PresentMode, Renderer};
+use spectral_mesh::schedule::Schedule;
+use spectral_mesh::session::{CommandRecorder, CommandReplay};
+use spectral_mesh::state::{
+    AppState, FlashMode, ProjectionMode, RippleShape, Solo, DEFAULT_RIPPLES, MAX_FLASH_INTENSITY, NOISE_AMP, NOISE_RESOLUTION,
+};
+use spectral_mesh::tracking::AutoCenterMode;
+use spectral_mesh::video::{
+    self, DummyVideoSource, FileVideoSource, ImageVideoSource, ResizeFilter, TestPattern, VideoCapture,
+};
+use spectral_mesh::{config, mesh, p_lock, terminal, SpectralMesh};
+use std::fmt::Write;
+use std::time::Instant;
 use winit::{
-    event::{ElementState, Event, KeyEvent, WindowEvent},
+    event::{ElementState, Event, KeyEvent, MouseButton, WindowEvent},
     event_loop::{ControlFlow, EventLoop},
-    keyboard::{KeyCode, PhysicalKey},
-    window::WindowBuilder,
+    keyboard::{KeyCode, ModifiersState, PhysicalKey},
+    monitor::MonitorHandle,
+    window::{Fullscreen, Window, WindowBuilder},
 };
 
 /// Spectral Mesh - Real-time video mesh distortion
@@ -32,10 +40,33 @@ struct Args {
     #[arg(short, long, default_value_t = 1)]
     midi: usize,
 
+    /// Create a virtual MIDI input with this name for other software to send
+    /// to, instead of opening the --midi port (not available on Windows)
+    #[arg(long, value_name = "NAME")]
+    midi_virtual: Option<String>,
+
     /// Video input device index
     #[arg(short, long, default_value_t = 0)]
     video: u32,
 
+    /// Play a video file instead of the camera (decoded by ffmpeg, which must
+    /// be on the PATH); falls back to the camera if it can't be started
+    #[arg(long, value_name = "PATH")]
+    file: Option<std::path::PathBuf>,
+
+    /// Hold the last frame at the end of --file instead of looping
+    #[arg(long, requires = "file")]
+    no_loop: bool,
+
+    /// Show a still image (PNG, JPEG, ...) instead of the camera, so the effects
+    /// animate over a fixed picture; also the input of --render-frames
+    #[arg(long, value_name = "PATH", conflicts_with = "file")]
+    image: Option<std::path::PathBuf>,
+
+    /// Second video input device index, crossfaded with the first (optional)
+    #[arg(long)]
+    video2: Option<u32>,
+
     /// Video processing width (lower = faster, use 16:9 for modern cameras)
     #[arg(long, default_value_t = 960)]
     width: u32,
@@ -44,14 +75,49 @@ struct Args {
     #[arg(long, default_value_t = 540)]
     height: u32,
 
-    /// Audio input device index (optional, omit to disable)
+    /// Audio input device index (omit to use the default input device)
     #[arg(short, long)]
     audio: Option<usize>,
 
+    /// Don't open any audio input, not even the default device
+    #[arg(long, conflicts_with = "audio")]
+    no_audio: bool,
+
+    /// Automatic gain: normalize the audio levels to the loudest of the last
+    /// --agc-window seconds, so quiet and loud rooms both drive the effects
+    #[arg(long)]
+    agc: bool,
+
+    /// Seconds of audio the automatic gain looks back over
+    #[arg(long, default_value_t = 10.0, requires = "agc")]
+    agc_window: f32,
+
+    /// Read every audio route on a calibrated dB scale: this level in dBFS
+    /// reads 1.0 and 48 dB below reads 0, alike for all bands (e.g. -12)
+    #[arg(long, value_name = "DBFS", allow_negative_numbers = true)]
+    audio_reference: Option<f32>,
+
+    /// Don't spawn ripples on audio kicks (on whenever audio is enabled)
+    #[arg(long)]
+    no_kick_ripples: bool,
+
+    /// Bass rise per second that counts as a kick, for kick ripples and the
+    /// strobe; lower is more sensitive (Numpad 9/3 at runtime)
+    #[arg(long, default_value_t = DEFAULT_KICK_THRESHOLD)]
+    kick_threshold: f32,
+
     /// List available devices and exit
     #[arg(long)]
     list_devices: bool,
 
+    /// Only log warnings and errors (overrides RUST_LOG)
+    #[arg(short, long, conflicts_with = "verbose")]
+    quiet: bool,
+
+    /// Log debug details too (overrides RUST_LOG; -v is --video)
+    #[arg(long)]
+    verbose: bool,
+
     /// Window width
     #[arg(long, default_value_t = 1280)]
     window_width: u32,
@@ -59,108 +125,741 @@ struct Args {
     /// Window height
     #[arg(long, default_value_t = 720)]
     window_height: u32,
+
+    /// Display the camera at its native aspect ratio instead of --width/--height
+    #[arg(long)]
+    native_aspect: bool,
+
+    /// Milliseconds without camera frames before fading to the test pattern (0 = never)
+    #[arg(long, default_value_t = 500)]
+    stall_timeout: u64,
+
+    /// Filter used to resize camera frames to the processing resolution
+    #[arg(long, value_enum, default_value_t = ResizeFilter::Bilinear)]
+    resize_filter: ResizeFilter,
+
+    /// Average camera frames over time against sensor noise, at the cost of
+    /// motion blur (F12 toggles at runtime)
+    #[arg(long)]
+    denoise: bool,
+
+    /// Share of the previous camera frames kept each frame with --denoise
+    /// (0 - 0.95); higher is cleaner but smears motion
+    #[arg(long, default_value_t = 0.6)]
+    denoise_amount: f32,
+
+    /// Render the scene at this fraction of the window size (0.25 - 1) and
+    /// upscale it, for fill-rate-bound GPUs at high resolutions
+    #[arg(long, default_value_t = 1.0)]
+    render_scale: f32,
+
+    /// Filter used to upscale a --render-scale below 1 to the window
+    #[arg(long, value_enum, default_value_t = ResizeFilter::Bilinear)]
+    render_filter: ResizeFilter,
+
+    /// Surface present mode (vsync / uncapped)
+    #[arg(long, value_enum, default_value_t = PresentMode::AutoVsync)]
+    present_mode: PresentMode,
+
+    /// Mesh projection (F8 toggles at runtime)
+    #[arg(long, value_enum, default_value_t = ProjectionMode::Orthographic)]
+    projection: ProjectionMode,
+
+    /// Line drawing: hard (aliased) or smooth (anti-aliased quads, cheaper than MSAA)
+    #[arg(long, value_enum, default_value_t = LineRender::Hard)]
+    line_render: LineRender,
+
+    /// Filter out LFO detail finer than the mesh grid so sparse meshes don't
+    /// tear (0 = off, 1 = full; MIDI CC 74)
+    #[arg(long, default_value_t = 0.0)]
+    mesh_smoothing: f32,
+
+    /// Glow around bright areas, 0 - 2 (0 = off; Alt+B bypasses, MIDI CC 91)
+    #[arg(long, default_value_t = 0.0)]
+    bloom: f32,
+
+    /// Brightness the glow starts at, 0 - 1 (MIDI CC 92)
+    #[arg(long, default_value_t = 0.6)]
+    bloom_threshold: f32,
+
+    /// Extra glow at full audio level, 0 - 2 (MIDI CC 93)
+    #[arg(long, default_value_t = 0.0)]
+    audio_bloom_gain: f32,
+
+    /// Perspective field of view in degrees (MIDI CC 48)
+    #[arg(long, default_value_t = 60.0)]
+    fov: f32,
+
+    /// Perspective near / far planes, in video pixels from the camera
+    #[arg(long, default_value_t = 1.0)]
+    near: f32,
+    #[arg(long, default_value_t = 10000.0)]
+    far: f32,
+
+    /// Simulation rate in Hz (LFOs and p_lock steps advance at this rate regardless of FPS)
+    #[arg(long, default_value_t = 60.0)]
+    logic_hz: f32,
+
+    /// Bars of MIDI clock spanning one p_lock pattern (0 = ignore MIDI clock)
+    #[arg(long, default_value_t = 4)]
+    clock_bars: u32,
+
+    /// Steps F4 quantizes the recorded p_lock pattern to (240 steps per pattern)
+    #[arg(long, default_value_t = 16)]
+    quantize_steps: usize,
+
+    /// Flash this bright (0 - 0.8) each time the p_lock loop starts over while
+    /// recording, to feel the loop point (0 = off)
+    #[arg(long, default_value_t = 0.0)]
+    loop_flash: f32,
+
+    /// Background color as R,G,B or R,G,B,A (0-255)
+    #[arg(long, value_parser = parse_color, default_value = "0,0,0")]
+    clear_color: [f32; 4],
+
+    /// Reverse the Scale knob so turning it up increases grid density (Shift+[ or ] toggles)
+    #[arg(long)]
+    invert_scale: bool,
+
+    /// MIDI CC numbers whose values are reversed, comma separated (e.g. 17,22)
+    #[arg(long, value_delimiter = ',')]
+    invert_cc: Vec<u8>,
+
+    /// JSON file assigning MIDI CCs and notes to commands, for controllers other
+    /// than the default layout; unlisted CCs keep their default (see --dump-midi-map)
+    #[arg(long, value_name = "PATH")]
+    midi_map: Option<std::path::PathBuf>,
+
+    /// MIDI output device index to echo parameter values to, for controllers
+    /// with LED rings or motor faders (see --list-devices)
+    #[arg(long, value_name = "PORT")]
+    midi_out: Option<usize>,
+
+    /// Print the default MIDI CC assignments in --midi-map format and exit
+    #[arg(long)]
+    dump_midi_map: bool,
+
+    /// Audio feature (or camera motion) that thickens line meshes (see --audio-stroke-gain)
+    #[arg(long, value_enum, default_value_t = AudioBand::Bass)]
+    audio_stroke: AudioBand,
+
+    /// Extra line thickness at full audio level, in video pixels (0 = off)
+    #[arg(long, default_value_t = 0.0)]
+    audio_stroke_gain: f32,
+
+    /// Audio feature (or camera motion) that widens the RGB split (see --audio-chroma-gain)
+    #[arg(long, value_enum, default_value_t = AudioBand::Rms)]
+    audio_chroma: AudioBand,
+
+    /// Extra RGB split at full audio level, 0 - 1 like chroma_split (MIDI CC 95; 0 = off)
+    #[arg(long, default_value_t = 0.0)]
+    audio_chroma_gain: f32,
+
+    /// Camera motion adds displacement: amount at full motion, like the audio displacement (0 = off)
+    #[arg(long, default_value_t = 0.0)]
+    motion_displace: f32,
+
+    /// Audio feature (or camera motion) that displaces along X and Y, as X,Y
+    /// (e.g. left,right for stereo; see --audio-displace-gain)
+    #[arg(long, value_enum, value_delimiter = ',', default_value = "left,right")]
+    audio_displace: Vec<AudioBand>,
+
+    /// Displacement at full audio level per axis as X,Y, like the audio displacement (0 = off)
+    #[arg(long, value_delimiter = ',', default_value = "0,0")]
+    audio_displace_gain: Vec<f32>,
+
+    /// Audio feature (or camera motion) that nudges the rotation (see --audio-rotate-gain)
+    #[arg(long, value_enum, default_value_t = AudioBand::Kick)]
+    audio_rotate: AudioBand,
+
+    /// Rotation nudge at full audio level in radians, per axis as X,Y,Z (e.g. 0,0,0.3; 0 = off)
+    #[arg(long, value_delimiter = ',', default_value = "0,0,0")]
+    audio_rotate_gain: Vec<f32>,
+
+    /// Audio feature (or camera motion) that scales the whole mesh, a breathing
+    /// zoom (see --audio-zoom-amount)
+    #[arg(long, value_enum, default_value_t = AudioBand::Bass)]
+    audio_zoom: AudioBand,
+
+    /// Extra mesh scale at full audio level (0.3 = 30% larger; 0 = off)
+    #[arg(long, default_value_t = 0.0)]
+    audio_zoom_amount: f32,
+
+    /// How far the zoom moves toward a louder level each update (0-1, higher = punchier)
+    #[arg(long, default_value_t = 0.4)]
+    audio_zoom_attack: f32,
+
+    /// How far the zoom eases back each update as the level falls (0-1, lower = slower)
+    #[arg(long, default_value_t = 0.08)]
+    audio_zoom_release: f32,
+
+    /// Strobe on strong kicks (Space cycles); at most 3 flashes a second
+    #[arg(long, value_enum, default_value_t = FlashMode::Off)]
+    flash: FlashMode,
+
+    /// Kick level that fires a flash (higher = only the biggest hits)
+    #[arg(long, default_value_t = 0.6)]
+    flash_threshold: f32,
+
+    /// Flash strength (0 - 0.8, capped for photosensitivity)
+    #[arg(long, default_value_t = 0.6)]
+    flash_intensity: f32,
+
+    /// Noise evolution speed relative to the LFO rate, per axis as X,Y,Z (F9/F10, CC 49 set all)
+    #[arg(long, value_delimiter = ',', default_value = "1,1,1")]
+    noise_time_scale: Vec<f32>,
+
+    /// Noise function, one for all axes or per axis as X,Y,Z (Alt+6/7/8 cycle Z/X/Y, CC 88-90)
+    #[arg(long, value_enum, value_delimiter = ',', default_value = "perlin")]
+    noise_kind: Vec<NoiseKind>,
+
+    /// Image shown when there is no camera (F3 cycles at runtime)
+    #[arg(long, value_enum, default_value_t = TestPattern::Plasma)]
+    test_pattern: TestPattern,
+
+    /// Ripple ring profile (` cycles at runtime, CC 56)
+    #[arg(long, value_enum, default_value_t = RippleShape::Gaussian)]
+    ripple_shape: RippleShape,
+
+    /// Ripples running at once (up to 16); older ones are replaced past this
+    #[arg(long, default_value_t = DEFAULT_RIPPLES)]
+    max_ripples: usize,
+
+    /// Center offset follows the brightest or most-moving part of the video
+    #[arg(long, value_enum, default_value_t = AutoCenterMode::Off)]
+    auto_center: AutoCenterMode,
+
+    /// How quickly auto-center follows (0-1, fraction of the distance per update)
+    #[arg(long, default_value_t = 0.05)]
+    auto_center_speed: f32,
+
+    /// Post-effect chain file: one effect per line, e.g. "vignette 0.7 0.4"
+    /// (see the README for the built-in effects and custom WGSL snippets)
+    #[arg(long, value_name = "FILE")]
+    effects: Option<std::path::PathBuf>,
+
+    /// Skip drawing once with every pipeline at startup (faster start, may hitch on first mode switch)
+    #[arg(long)]
+    no_prewarm: bool,
+
+    /// Seconds without keyboard/mouse/MIDI input before the mesh starts drifting on its own (0 = off)
+    #[arg(long, default_value_t = 0.0)]
+    idle_timeout: f32,
+
+    /// Daily opening hours, local time, e.g. "09:00-17:00" or "10:00-13:00,14:00-18:00".
+    /// Outside them the output fades to black and rendering pauses until the next opening
+    #[arg(long)]
+    schedule: Option<Schedule>,
+
+    /// Start in borderless fullscreen (F11 toggles at runtime)
+    #[arg(long)]
+    fullscreen: bool,
+
+    /// Monitor index for the window / fullscreen (see --list-devices; default: primary)
+    #[arg(long)]
+    monitor: Option<usize>,
+
+    /// Key that quits the app, e.g. esc, q, f12 (default: none, close the window or Ctrl+C)
+    #[arg(long, value_parser = parse_key)]
+    quit_key: Option<KeyCode>,
+
+    /// Transparent, undecorated window for overlay compositing (luma-keyed areas show through)
+    #[arg(long)]
+    transparent: bool,
+
+    /// Startup defaults file, loaded if it exists and overwritten by F2
+    /// (default: spectral_mesh/defaults.txt in the user config dir)
+    #[arg(long)]
+    config: Option<std::path::PathBuf>,
+
+    /// Folder for captured noise maps (noise_x/y/z.png): loaded and frozen at
+    /// startup if present, written by Shift+4 (default: next to the defaults file)
+    #[arg(long)]
+    noise_maps: Option<std::path::PathBuf>,
+
+    /// Seed of the X/Y/Z noise fields and of random ripple positions; the same
+    /// seed and controls give the same output on every run (Alt+4 picks a
+    /// random noise seed at runtime)
+    #[arg(long, visible_alias = "noise-seed", default_value_t = 0)]
+    seed: u32,
+
+    /// Noise texture width, up to 2048 (larger = more, smaller noise features; slower)
+    #[arg(long, default_value_t = noise::DEFAULT_NOISE_WIDTH)]
+    noise_width: u32,
+
+    /// Noise texture height, up to 2048
+    #[arg(long, default_value_t = noise::DEFAULT_NOISE_HEIGHT)]
+    noise_height: u32,
+
+    /// p_lock pattern file: recalled at startup if present, written by Numpad 7
+    /// and read back by Numpad 1 (default: pattern.json next to the defaults file)
+    #[arg(long)]
+    pattern: Option<std::path::PathBuf>,
+
+    /// Read `name value` control lines from stdin, e.g. `displace_x 0.7` or `mesh triangles`
+    #[arg(long)]
+    stdin_control: bool,
+
+    /// Log every control command with its time to this file (JSON lines), to
+    /// replay later with --replay-commands
+    #[arg(long)]
+    record_commands: Option<std::path::PathBuf>,
+
+    /// Feed the commands recorded with --record-commands back at their timing
+    #[arg(long)]
+    replay_commands: Option<std::path::PathBuf>,
+
+    /// Publish the output as an NDI source with this name (needs the ndi
+    /// feature and the NDI runtime)
+    #[arg(long)]
+    ndi_name: Option<String>,
+
+    /// Run without a window and print a low-rate preview of the output to the
+    /// terminal instead (for checking a headless Pi over SSH). Camera, audio,
+    /// MIDI and --stdin-control work as usual; Ctrl+C quits.
+    #[arg(long)]
+    ascii_preview: bool,
+
+    /// Terminal preview width in characters
+    #[arg(long, default_value_t = 64)]
+    ascii_width: u32,
+
+    /// Render this many frames offscreen to PNGs in --output-dir and exit: no
+    /// window, camera, audio or MIDI; one logic step per frame at --logic-hz
+    #[arg(long, value_name = "N")]
+    render_frames: Option<u32>,
+
+    /// Folder for the --render-frames PNGs (frame_00000.png, ...)
+    #[arg(long, value_name = "DIR", default_value = "frames")]
+    output_dir: std::path::PathBuf,
 }
 
-const NOISE_WIDTH: u32 = 180;
-const NOISE_HEIGHT: u32 = 120;
+/// Parse a key name (esc, space, enter, tab, backspace, a-z, 0-9, f1-f12)
+fn parse_key(name: &str) -> Result<KeyCode, String> {
+    const LETTERS: [KeyCode; 26] = [
+        KeyCode::KeyA, KeyCode::KeyB, KeyCode::KeyC, KeyCode::KeyD, KeyCode::KeyE, KeyCode::KeyF, KeyCode::KeyG,
+        KeyCode::KeyH, KeyCode::KeyI, KeyCode::KeyJ, KeyCode::KeyK, KeyCode::KeyL, KeyCode::KeyM, KeyCode::KeyN,
+        KeyCode::KeyO, KeyCode::KeyP, KeyCode::KeyQ, KeyCode::KeyR, KeyCode::KeyS, KeyCode::KeyT, KeyCode::KeyU,
+        KeyCode::KeyV, KeyCode::KeyW, KeyCode::KeyX, KeyCode::KeyY, KeyCode::KeyZ,
+    ];
+    const DIGITS: [KeyCode; 10] = [
+        KeyCode::Digit0, KeyCode::Digit1, KeyCode::Digit2, KeyCode::Digit3, KeyCode::Digit4,
+        KeyCode::Digit5, KeyCode::Digit6, KeyCode::Digit7, KeyCode::Digit8, KeyCode::Digit9,
+    ];
+    const FUNCTION: [KeyCode; 12] = [
+        KeyCode::F1, KeyCode::F2, KeyCode::F3, KeyCode::F4, KeyCode::F5, KeyCode::F6,
+        KeyCode::F7, KeyCode::F8, KeyCode::F9, KeyCode::F10, KeyCode::F11, KeyCode::F12,
+    ];
+
+    let lower = name.trim().to_ascii_lowercase();
+    let key = match lower.as_str() {
+        "esc" | "escape" => Some(KeyCode::Escape),
+        "space" => Some(KeyCode::Space),
+        "enter" | "return" => Some(KeyCode::Enter),
+        "tab" => Some(KeyCode::Tab),
+        "backspace" => Some(KeyCode::Backspace),
+        s if s.len() == 1 => {
+            let c = s.as_bytes()[0];
+            match c {
+                b'a'..=b'z' => Some(LETTERS[(c - b'a') as usize]),
+                b'0'..=b'9' => Some(DIGITS[(c - b'0') as usize]),
+                _ => None,
+            }
+        }
+        s => s
+            .strip_prefix('f')
+            .and_then(|n| n.parse::<usize>().ok())
+            .and_then(|n| FUNCTION.get(n.wrapping_sub(1)).copied()),
+    };
+    key.ok_or_else(|| format!("unknown key name: {}", name))
+}
+
+/// Parse "R,G,B" or "R,G,B,A" with 0-255 components into normalized RGBA
+fn parse_color(s: &str) -> Result<[f32; 4], String> {
+    let parts = s
+        .split(',')
+        .map(|p| p.trim().parse::<u8>().map(|v| v as f32 / 255.0))
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| format!("invalid color component: {}", e))?;
+    match parts.as_slice() {
+        [r, g, b] => Ok([*r, *g, *b, 1.0]),
+        [r, g, b, a] => Ok([*r, *g, *b, *a]),
+        _ => Err("expected R,G,B or R,G,B,A".to_string()),
+    }
+}
+
+/// Window title; the p_lock loop position is appended while recording
+const WINDOW_TITLE: &str = "Spectral Mesh v5.0 (Rust/wgpu)";
+/// Cap on catch-up updates per frame so a long stall doesn't snowball
+const MAX_UPDATES_PER_FRAME: u32 = 8;
+/// Rotation per key press, in radians
+const ROTATE_STEP: f32 = 0.05;
+/// Idle drift: seconds to ease in, and peak rotation (radians) / center offset (clip space)
+const IDLE_RAMP_SECS: f32 = 10.0;
+const IDLE_ROTATE_AMP: f32 = 0.25;
+const IDLE_CENTER_AMP: f32 = 0.15;
+/// Fade to / from black at the edges of the --schedule hours, in seconds
+const STANDBY_FADE_SECS: f32 = 3.0;
+/// How often a paused installation checks whether it's time to open
+const STANDBY_POLL: std::time::Duration = std::time::Duration::from_secs(1);
+/// Seconds between terminal preview frames
+const ASCII_PREVIEW_INTERVAL: f32 = 0.5;
+/// Consecutive surface timeouts before the surface is reconfigured from scratch
+const SURFACE_TIMEOUT_LIMIT: u32 = 10;
+/// Seconds to crossfade between a stalled camera and the test pattern
+const STALL_FADE_SECS: f32 = 1.0;
 
 enum VideoSource {
-    Camera(VideoCapture),
+    Camera(CameraFeed),
     Dummy(DummyVideoSource),
+    File(FileVideoSource),
+    Image(ImageVideoSource),
+}
+
+impl VideoSource {
+    /// Latest frame from the source
+    fn next_frame(&mut self) -> &[u8] {
+        match self {
+            VideoSource::Camera(feed) => feed.next_frame(),
+            VideoSource::Dummy(dummy) => dummy.update(),
+            VideoSource::File(file) => file.next_frame(),
+            VideoSource::Image(image) => image.update(),
+        }
+    }
+
+    /// Capture behind this source, if it's a camera
+    fn camera(&self) -> Option<&VideoCapture> {
+        match self {
+            VideoSource::Camera(feed) => Some(&feed.cam),
+            VideoSource::Dummy(_) | VideoSource::File(_) | VideoSource::Image(_) => None,
+        }
+    }
+
+    /// Test pattern shown by this source, either all the time or while its
+    /// camera stalls; video files and images have none
+    fn test_pattern(&mut self) -> Option<&mut DummyVideoSource> {
+        match self {
+            VideoSource::Camera(feed) => Some(&mut feed.fallback),
+            VideoSource::Dummy(dummy) => Some(dummy),
+            VideoSource::File(_) | VideoSource::Image(_) => None,
+        }
+    }
+}
+
+/// Seconds a camera being switched to gets to deliver its first frame
+const CAMERA_SWITCH_TIMEOUT_SECS: f32 = 5.0;
+
+/// Camera that fades to a test pattern when it stops delivering frames (e.g. a
+/// USB camera dropping out), and back once frames resume
+struct CameraFeed {
+    cam: VideoCapture,
+    fallback: DummyVideoSource,
+    /// No frames for this long counts as a stall (None = never)
+    stall_timeout: Option<std::time::Duration>,
+    last_frame: Instant,
+    last_update: Instant,
+    /// Crossfade position, 0 = camera, 1 = test pattern
+    fade: f32,
+    blended: Vec<u8>,
+}
+
+impl CameraFeed {
+    fn new(cam: VideoCapture, fallback: DummyVideoSource, stall_timeout_ms: u64) -> Self {
+        Self {
+            cam,
+            fallback,
+            stall_timeout: (stall_timeout_ms > 0).then(|| std::time::Duration::from_millis(stall_timeout_ms)),
+            last_frame: Instant::now(),
+            last_update: Instant::now(),
+            fade: 0.0,
+            blended: Vec::new(),
+        }
+    }
+
+    /// Continue from another camera; the old capture thread stops on drop
+    fn switch_to(&mut self, cam: VideoCapture) {
+        self.cam = cam;
+        self.last_frame = Instant::now();
+    }
+
+    fn next_frame(&mut self) -> &[u8] {
+        let now = Instant::now();
+        if self.cam.get_frame().is_some() {
+            self.last_frame = now;
+        }
+        let stalled = self.stall_timeout.is_some_and(|timeout| now - self.last_frame > timeout);
+
+        let step = (now - self.last_update).as_secs_f32() / STALL_FADE_SECS;
+        self.last_update = now;
+        let target = if stalled { 1.0 } else { 0.0 };
+        let fade = if stalled { (self.fade + step).min(target) } else { (self.fade - step).max(target) };
+        if fade > 0.0 && self.fade == 0.0 {
+            log::warn!("Camera stalled, fading to the test pattern");
+        } else if fade < 1.0 && self.fade == 1.0 {
+            log::info!("Camera frames resumed");
+        }
+        self.fade = fade;
+
+        if fade == 0.0 {
+            return self.cam.current_frame();
+        }
+        let pattern = self.fallback.update();
+        if fade == 1.0 {
+            return pattern;
+        }
+        let weight = (fade * 256.0) as u32;
+        self.blended.clear();
+        self.blended.extend(
+            self.cam
+                .current_frame()
+                .iter()
+                .zip(pattern)
+                .map(|(&c, &p)| ((c as u32 * (256 - weight) + p as u32 * weight) >> 8) as u8),
+        );
+        &self.blended
+    }
 }
 
 struct App {
-    renderer: Renderer,
-    state: AppState,
+    window: std::sync::Arc<Window>,
+    engine: SpectralMesh,
     midi: Option<MidiHandler>,
-    noise_bank: NoiseBank,
+    stdin: Option<StdinControl>,
+    recorder: Option<CommandRecorder>,
+    replay: Option<CommandReplay>,
+    ndi: Option<NdiSender>,
+    config_path: Option<std::path::PathBuf>,
     video_source: VideoSource,
-    audio: Option<AudioAnalyzer>,
-    last_mesh_scale: u32,
-    needs_mesh_rebuild: bool,
+    video_source2: Option<VideoSource>,
+    // Main camera device, and one being opened to replace it (kept off screen
+    // until it delivers a frame, so a failed open leaves the current source)
+    camera_index: u32,
+    pending_camera: Option<(VideoCapture, Instant)>,
+    resize_filter: ResizeFilter,
+    // Initial test pattern, for a camera replacing a video file
+    test_pattern: TestPattern,
+    // Camera temporal blend: on/off and amount
+    denoise: bool,
+    denoise_amount: f32,
+    stall_timeout: u64,
     show_help: bool,
-    video_width: u32,
-    video_height: u32,
+    // Parameter overlay in the window, rebuilt each frame while shown
+    show_hud: bool,
+    hud: Hud,
+    native_aspect: bool,
+    present_mode: PresentMode,
+    modifiers: ModifiersState,
+    // Key holding the current solo
+    solo_key: Option<KeyCode>,
+    quit_key: Option<KeyCode>,
+    quit_requested: bool,
+    // Steps F4 quantizes the p_lock pattern to
+    quantize_steps: usize,
+    // Window title as last set, so the loop progress only touches it on change
+    title: String,
+    // Where Shift+4 exports the noise maps
+    noise_maps: Option<std::path::PathBuf>,
+    // Where the p_lock pattern is saved and recalled
+    pattern_path: Option<std::path::PathBuf>,
+    monitor: Option<MonitorHandle>,
+    // Mouse: last cursor position (physical pixels) and right-drag anchor (video coords)
+    cursor: (f64, f64),
+    drag_last: Option<(f32, f32)>,
+    // Frame timing
+    fps_frames: u32,
+    fps_timer: Instant,
+    fps: f32,
+    // Frames in a row where the surface timed out
+    surface_timeouts: u32,
+    // Idle drift after a period without input
+    idle_timeout: f32,
+    last_input: Instant,
+    idle_time: f32,
+    // Opening hours; outside them the output fades out and the loop pauses
+    schedule: Option<Schedule>,
+    standby: bool,
+    // Fixed timestep simulation
+    logic_step: f32,
+    logic_accumulator: f32,
+    last_tick: Instant,
 }
 
 impl App {
-    fn new(renderer: Renderer, args: &Args) -> Self {
-        // Initialize MIDI
-        let midi = match MidiHandler::new(args.midi) {
-            Ok(midi) => {
-                log::info!("MIDI initialized on port {}", args.midi);
-                Some(midi)
+    fn new(window: std::sync::Arc<Window>, mut renderer: Renderer, args: &Args) -> Self {
+        renderer.set_present_mode(args.present_mode);
+        renderer.set_render_scale(args.render_scale, args.render_filter);
+        if let Some(path) = &args.effects {
+            load_effect_chain(&mut renderer, path);
+        }
+        if !args.no_prewarm {
+            renderer.prewarm();
+        }
+
+        // Move to the requested monitor, then go fullscreen there if asked
+        let monitor = args.monitor.and_then(|index| select_monitor(&window, index));
+        if let Some(ref monitor) = monitor {
+            window.set_outer_position(monitor.position());
+        }
+        if args.fullscreen {
+            window.set_fullscreen(Some(Fullscreen::Borderless(monitor.clone())));
+        }
+
+        let midi = open_midi(args);
+
+        let recorder = args.record_commands.as_deref().and_then(|path| match CommandRecorder::create(path) {
+            Ok(recorder) => {
+                log::info!("Recording commands to {}", path.display());
+                Some(recorder)
             }
             Err(e) => {
-                log::warn!("MIDI initialization failed: {}", e);
+                log::warn!("Command recording disabled: {}", e);
                 None
             }
-        };
-
-        // Try to initialize camera, fall back to dummy if it fails
-        let video_source = match VideoCapture::new(args.width, args.height, args.video) {
-            Ok(cam) => {
-                log::info!("Camera {} initialized ({}x{})", args.video, args.width, args.height);
-                VideoSource::Camera(cam)
+        });
+        let replay = args.replay_commands.as_deref().and_then(|path| match CommandReplay::load(path) {
+            Ok(replay) => {
+                log::info!("Replaying commands from {}", path.display());
+                Some(replay)
             }
             Err(e) => {
-                log::warn!("Camera failed: {}. Using test pattern.", e);
-                VideoSource::Dummy(DummyVideoSource::new(args.width, args.height))
+                log::warn!("Command replay disabled: {}", e);
+                None
             }
-        };
+        });
 
-        // Initialize audio if requested
-        let audio = if let Some(audio_idx) = args.audio {
-            match AudioAnalyzer::new(Some(audio_idx)) {
-                Ok(analyzer) => {
-                    log::info!("Audio analyzer initialized");
-                    Some(analyzer)
-                }
+        let ndi = args.ndi_name.as_deref().and_then(|name| match NdiSender::new(name) {
+            Ok(ndi) => match renderer.set_frame_capture(true) {
+                Ok(()) => Some(ndi),
                 Err(e) => {
-                    log::warn!("Audio initialization failed: {}", e);
+                    log::warn!("NDI output disabled: {}", e);
                     None
                 }
+            },
+            Err(e) => {
+                log::warn!("NDI output disabled: {}", e);
+                None
             }
-        } else {
-            // Try default audio device
-            match AudioAnalyzer::new(None) {
-                Ok(analyzer) => {
-                    log::info!("Audio analyzer initialized (default device)");
-                    Some(analyzer)
+        });
+
+        let video_source = open_video(args);
+        let denoise = if args.denoise { args.denoise_amount } else { 0.0 };
+
+        // Optional second source for crossfading
+        let video_source2 = args.video2.map(|index| {
+            match VideoCapture::new(args.width, args.height, index, args.resize_filter) {
+                Ok(cam) => {
+                    log::info!("Second camera {} initialized", index);
+                    cam.set_denoise(denoise);
+                    let fallback = DummyVideoSource::new(args.width, args.height, args.test_pattern);
+                    VideoSource::Camera(CameraFeed::new(cam, fallback, args.stall_timeout))
                 }
                 Err(e) => {
-                    log::info!("No audio input: {}", e);
-                    None
+                    log::warn!("Second camera failed: {}. Using test pattern.", e);
+                    VideoSource::Dummy(DummyVideoSource::new(args.width, args.height, args.test_pattern))
                 }
             }
-        };
+        });
+
+        let audio = open_audio(args);
 
         log::info!("Spectral Mesh initialized");
         log::info!("Press H for help");
 
+        let config_path = args.config.clone().or_else(config::default_path);
+        let mut state = initial_state(args, config_path.as_deref());
+
+        // Closed at launch: start dark instead of fading out
+        let standby = args.schedule.as_ref().is_some_and(|schedule| !schedule.is_open());
+        if standby {
+            log::info!("Outside the scheduled hours, standing by");
+            state.output_level = 0.0;
+        }
+
+        let mut engine = SpectralMesh::new(renderer, state, args.width, args.height);
+        if audio.is_some() {
+            engine.state.kick_ripples = !args.no_kick_ripples;
+        }
+        engine.audio = audio;
+
+        let pattern_path = recall_saved(&mut engine, args, config_path.as_deref());
+        let noise_maps = args.noise_maps.clone().or_else(|| Some(config_path.as_deref()?.parent()?.to_path_buf()));
+
         Self {
-            renderer,
-            state: AppState::new(args.width, args.height),
+            window,
+            engine,
             midi,
-            noise_bank: NoiseBank::new(NOISE_WIDTH, NOISE_HEIGHT),
+            stdin: args.stdin_control.then(StdinControl::spawn),
+            recorder,
+            replay,
+            ndi,
+            config_path,
             video_source,
-            audio,
-            last_mesh_scale: 100,
-            needs_mesh_rebuild: false,
+            camera_index: args.video,
+            pending_camera: None,
+            resize_filter: args.resize_filter,
+            test_pattern: args.test_pattern,
+            denoise: args.denoise,
+            denoise_amount: args.denoise_amount,
+            stall_timeout: args.stall_timeout,
+            video_source2,
             show_help: false,
-            video_width: args.width,
-            video_height: args.height,
+            show_hud: false,
+            hud: Hud::new(),
+            native_aspect: args.native_aspect,
+            present_mode: args.present_mode,
+            modifiers: ModifiersState::empty(),
+            solo_key: None,
+            quit_key: args.quit_key,
+            quit_requested: false,
+            quantize_steps: args.quantize_steps.max(1),
+            title: String::new(),
+            noise_maps,
+            pattern_path,
+            monitor,
+            cursor: (0.0, 0.0),
+            drag_last: None,
+            fps_frames: 0,
+            fps_timer: Instant::now(),
+            fps: 0.0,
+            surface_timeouts: 0,
+            idle_timeout: args.idle_timeout,
+            last_input: Instant::now(),
+            idle_time: 0.0,
+            standby,
+            schedule: args.schedule.clone(),
+            logic_step: 1.0 / args.logic_hz.max(1.0),
+            logic_accumulator: 0.0,
+            last_tick: Instant::now(),
         }
     }
 
     fn handle_keyboard(&mut self, key: KeyCode, pressed: bool) {
         if !pressed {
+            if self.solo_key == Some(key) {
+                self.end_solo();
+            }
+            return;
+        }
+        self.last_input = Instant::now();
+
+        // Ctrl + an effect's key solos that effect while held
+        if let Some(solo) = solo_group(key).filter(|_| self.modifiers.control_key()) {
+            if self.engine.state.solo != Some(solo) {
+                log::info!("Solo: {:?}", solo);
+            }
+            self.engine.state.solo = Some(solo);
+            self.solo_key = Some(key);
             return;
         }
 
         // Debug: log all key presses
         log::info!("Key pressed: {:?}", key);
 
+        if Some(key) == self.quit_key {
+            self.quit_requested = true;
+            return;
+        }
+
         // Help toggle
         if key == KeyCode::KeyH {
             self.show_help = !self.show_help;
@@ -170,13 +869,40 @@ impl App {
             return;
         }
 
-        let ko = &mut self.state.keyboard_offsets;
+        let ko = &mut self.engine.state.keyboard_offsets;
 
         match key {
             // Luma key
             KeyCode::KeyA => ko.az += 0.01,
             KeyCode::KeyZ => ko.az -= 0.01,
 
+            // Noise scale (Shift) and amount (Alt) on the LFO bias keys, per axis
+            KeyCode::Numpad6
+            | KeyCode::Numpad4
+            | KeyCode::Numpad8
+            | KeyCode::Numpad2
+            | KeyCode::NumpadAdd
+            | KeyCode::NumpadSubtract
+                if self.modifiers.shift_key() || self.modifiers.alt_key() =>
+            {
+                let (axis, sign) = match key {
+                    KeyCode::Numpad6 => (0, 1.0),
+                    KeyCode::Numpad4 => (0, -1.0),
+                    KeyCode::Numpad8 => (1, 1.0),
+                    KeyCode::Numpad2 => (1, -1.0),
+                    KeyCode::NumpadAdd => (2, 1.0),
+                    _ => (2, -1.0),
+                };
+                let state = &mut self.engine.state;
+                if self.modifiers.shift_key() {
+                    state.nudge_param(NOISE_RESOLUTION[axis], 0.01 * sign);
+                    log::info!("{} noise resolution: {:.2}", ["X", "Y", "Z"][axis], state.noise_resolution[axis]);
+                } else {
+                    state.nudge_param(NOISE_AMP[axis], 0.1 * sign);
+                    log::info!("{} noise amount: {:.1}", ["X", "Y", "Z"][axis], state.noise_amp[axis]);
+                }
+            }
+
             // Z LFO
             KeyCode::KeyS => ko.sx += 0.0001,
             KeyCode::KeyX => ko.sx -= 0.0001,
@@ -184,6 +910,15 @@ impl App {
             KeyCode::KeyC => ko.dc -= 0.001,
             KeyCode::KeyF => ko.fv += 0.001,
             KeyCode::KeyV => ko.fv -= 0.001,
+            KeyCode::NumpadAdd => ko.z_bias += 0.01,
+            KeyCode::NumpadSubtract => ko.z_bias -= 0.01,
+
+            // Bloom bypass, e.g. to spare a Pi's GPU
+            KeyCode::KeyB if self.modifiers.alt_key() => {
+                let bloom = !self.engine.state.bloom;
+                self.engine.apply_command(MidiCommand::Bloom(bloom));
+                log::info!("Bloom {}", if bloom { "on" } else { "bypassed" });
+            }
 
             // X LFO
             KeyCode::KeyG => ko.gb += 0.001,
@@ -192,6 +927,8 @@ impl App {
             KeyCode::KeyN => ko.hn -= 0.001,
             KeyCode::KeyJ => ko.jm += 0.1,
             KeyCode::KeyM => ko.jm -= 0.1,
+            KeyCode::Numpad6 => ko.x_bias += 0.01,
+            KeyCode::Numpad4 => ko.x_bias -= 0.01,
 
             // Y LFO
             KeyCode::KeyK => ko.kk += 0.001,
@@ -200,8 +937,14 @@ impl App {
             KeyCode::Period => ko.ll -= 0.001,
             KeyCode::Semicolon => ko.ylfo_amp += 0.1,
             KeyCode::Slash => ko.ylfo_amp -= 0.1,
+            KeyCode::Numpad8 => ko.y_bias += 0.01,
+            KeyCode::Numpad2 => ko.y_bias -= 0.01,
 
-            // Center offset
+            // Center offset (Shift+T cycles auto-center)
+            KeyCode::KeyT if self.modifiers.shift_key() => {
+                self.engine.state.auto_center.mode = self.engine.state.auto_center.mode.next();
+                log::info!("Auto-center: {:?}", self.engine.state.auto_center.mode);
+            }
             KeyCode::KeyT => ko.ty += 5.0,
             KeyCode::KeyY => ko.ty -= 5.0,
             KeyCode::KeyU => ko.ui += 5.0,
@@ -218,100 +961,437 @@ impl App {
             KeyCode::KeyW => ko.qw -= 0.01,
 
             // Scale
+            KeyCode::BracketLeft | KeyCode::BracketRight if self.modifiers.shift_key() => {
+                self.engine.state.invert_scale = !self.engine.state.invert_scale;
+                log::info!("Scale knob inverted: {}", self.engine.state.invert_scale);
+            }
             KeyCode::BracketRight => {
-                ko.scale_key += 1;
-                self.needs_mesh_rebuild = true;
+                self.engine.state.nudge_scale_key(1);
+                self.engine.request_mesh_rebuild();
             }
             KeyCode::BracketLeft => {
-                ko.scale_key -= 1;
-                self.needs_mesh_rebuild = true;
+                self.engine.state.nudge_scale_key(-1);
+                self.engine.request_mesh_rebuild();
             }
 
             // Toggles
-            KeyCode::Digit1 => self.state.luma_switch = !self.state.luma_switch,
-            KeyCode::Digit2 => self.state.bright_switch = !self.state.bright_switch,
-            KeyCode::Digit3 => self.state.invert = !self.state.invert,
-            KeyCode::Digit5 => self.state.greyscale = !self.state.greyscale,
+            KeyCode::Digit1 => self.engine.state.luma_switch = !self.engine.state.luma_switch,
+            KeyCode::Digit2 => self.engine.state.bright_switch = !self.engine.state.bright_switch,
+            KeyCode::Digit3 => self.engine.state.invert = !self.engine.state.invert,
+            KeyCode::Digit5 => self.engine.state.greyscale = !self.engine.state.greyscale,
+
+            // Noise snapshot - 4 freezes the current fields, Shift+4 exports them,
+            // Alt+4 reseeds them
+            KeyCode::Digit4 if self.modifiers.alt_key() => {
+                let seed = std::time::SystemTime::now()
+                    .duration_since(std::time::UNIX_EPOCH)
+                    .unwrap_or_default()
+                    .as_nanos() as u32;
+                self.engine.set_noise_seed(seed);
+                log::info!("Noise seed: {} (--seed {} to get it back)", seed, seed);
+            }
+            KeyCode::Digit4 if self.modifiers.shift_key() => match &self.noise_maps {
+                Some(dir) => match self.engine.save_noise(dir) {
+                    Ok(()) => log::info!("Saved noise maps to {}", dir.display()),
+                    Err(e) => log::error!("{}", e),
+                },
+                None => log::error!("No config directory found, pass --noise-maps <dir> to export noise"),
+            },
+            KeyCode::Digit4 => {
+                let frozen = !self.engine.state.noise_frozen;
+                self.engine.apply_command(MidiCommand::FreezeNoise(frozen));
+                log::info!("Noise {}", if frozen { "frozen" } else { "animating" });
+            }
+
+            // Chromatic aberration (Shift+' splits along the displacement)
+            KeyCode::Quote if self.modifiers.shift_key() => {
+                let follow = !self.engine.state.chroma_follow;
+                self.engine.apply_command(MidiCommand::ChromaFollow(follow));
+                log::info!("Chroma split {}", if follow { "follows the displacement" } else { "follows the LFOs" });
+            }
+            KeyCode::Quote => {
+                self.engine.state.chroma_split = (self.engine.state.chroma_split + 0.05).min(1.0);
+                log::info!("Chroma split: {:.2}", self.engine.state.chroma_split);
+            }
+            KeyCode::Backslash => {
+                self.engine.state.chroma_split = (self.engine.state.chroma_split - 0.05).max(0.0);
+                log::info!("Chroma split: {:.2}", self.engine.state.chroma_split);
+            }
+
+            // Feedback trails
+            KeyCode::PageUp => {
+                self.engine.state.feedback_decay = (self.engine.state.feedback_decay + 0.05).min(0.99);
+                log::info!("Feedback decay: {:.2}", self.engine.state.feedback_decay);
+            }
+            KeyCode::PageDown => {
+                self.engine.state.feedback_decay = (self.engine.state.feedback_decay - 0.05).max(0.0);
+                log::info!("Feedback decay: {:.2}", self.engine.state.feedback_decay);
+            }
 
             // LFO shapes
-            KeyCode::Digit6 => self.state.z_lfo_shape = (self.state.z_lfo_shape + 1) % 4,
-            KeyCode::Digit7 => self.state.x_lfo_shape = (self.state.x_lfo_shape + 1) % 4,
-            KeyCode::Digit8 => self.state.y_lfo_shape = (self.state.y_lfo_shape + 1) % 4,
+            // Shift + LFO shape key cycles that axis' displacement source, Alt its noise
+            KeyCode::Digit6 | KeyCode::Digit7 | KeyCode::Digit8 if self.modifiers.alt_key() => {
+                let axis = match key {
+                    KeyCode::Digit7 => 0,
+                    KeyCode::Digit8 => 1,
+                    _ => 2,
+                };
+                let kind = &mut self.engine.state.noise_kind[axis];
+                *kind = kind.next();
+                log::info!("{} noise: {:?}", ["X", "Y", "Z"][axis], kind);
+            }
+            KeyCode::Digit6 if self.modifiers.shift_key() => {
+                self.engine.state.z_source = self.engine.state.z_source.next();
+                log::info!("Z source: {:?}", self.engine.state.z_source);
+            }
+            KeyCode::Digit7 if self.modifiers.shift_key() => {
+                self.engine.state.x_source = self.engine.state.x_source.next();
+                log::info!("X source: {:?}", self.engine.state.x_source);
+            }
+            KeyCode::Digit8 if self.modifiers.shift_key() => {
+                self.engine.state.y_source = self.engine.state.y_source.next();
+                log::info!("Y source: {:?}", self.engine.state.y_source);
+            }
+            KeyCode::Digit6 => self.engine.state.z_lfo_shape = self.engine.state.z_lfo_shape.next(),
+            KeyCode::Digit7 => self.engine.state.x_lfo_shape = self.engine.state.x_lfo_shape.next(),
+            KeyCode::Digit8 => self.engine.state.y_lfo_shape = self.engine.state.y_lfo_shape.next(),
 
             // Mesh types
             KeyCode::Digit9 => {
-                self.state.mesh_type = mesh::MeshType::VerticalLines;
-                self.needs_mesh_rebuild = true;
+                self.engine.state.mesh_type = mesh::MeshType::VerticalLines;
+                self.engine.request_mesh_rebuild();
             }
             KeyCode::Digit0 => {
-                self.state.mesh_type = mesh::MeshType::HorizontalLines;
-                self.needs_mesh_rebuild = true;
+                self.engine.state.mesh_type = mesh::MeshType::HorizontalLines;
+                self.engine.request_mesh_rebuild();
             }
             KeyCode::Minus => {
                 log::info!("Minus pressed - Triangles filled");
-                self.state.mesh_type = mesh::MeshType::Triangles;
-                self.needs_mesh_rebuild = true;
+                self.engine.state.mesh_type = mesh::MeshType::Triangles;
+                self.engine.request_mesh_rebuild();
             }
             KeyCode::Equal => {
                 log::info!("Equal pressed - Grid (wireframe)");
-                self.state.mesh_type = mesh::MeshType::Grid;
-                self.needs_mesh_rebuild = true;
+                self.engine.state.mesh_type = mesh::MeshType::Grid;
+                self.engine.request_mesh_rebuild();
+            }
+            // One-button cycling through all of the above (Shift: backwards)
+            KeyCode::Tab => {
+                let mesh_type = self.engine.state.mesh_type;
+                self.engine.state.mesh_type =
+                    if self.modifiers.shift_key() { mesh_type.previous() } else { mesh_type.next() };
+                log::info!("Mesh: {:?}", self.engine.state.mesh_type);
+                self.engine.request_mesh_rebuild();
+            }
+
+            // Source exposure / gamma - Shift + arrows
+            KeyCode::ArrowUp | KeyCode::ArrowDown if self.modifiers.shift_key() => {
+                let step = if key == KeyCode::ArrowUp { 0.1 } else { -0.1 };
+                self.engine.state.nudge_param(ParamId::InputExposure, step);
+                log::info!("Input exposure: {:+.1} stops", self.engine.state.input_exposure);
+            }
+            KeyCode::ArrowLeft | KeyCode::ArrowRight if self.modifiers.shift_key() => {
+                let step = if key == KeyCode::ArrowRight { 0.05 } else { -0.05 };
+                self.engine.state.nudge_param(ParamId::InputGamma, step);
+                log::info!("Input gamma: {:.2}", self.engine.state.input_gamma);
             }
 
             // Audio sensitivity controls
             KeyCode::ArrowUp => {
-                self.state.audio_sensitivity = (self.state.audio_sensitivity + 0.1).min(5.0);
-                log::info!("Audio sensitivity: {:.1}", self.state.audio_sensitivity);
+                self.engine.state.nudge_param(ParamId::AudioSensitivity, 0.1);
+                log::info!("Audio sensitivity: {:.1}", self.engine.state.audio_sensitivity);
             }
             KeyCode::ArrowDown => {
-                self.state.audio_sensitivity = (self.state.audio_sensitivity - 0.1).max(0.0);
-                log::info!("Audio sensitivity: {:.1}", self.state.audio_sensitivity);
+                self.engine.state.nudge_param(ParamId::AudioSensitivity, -0.1);
+                log::info!("Audio sensitivity: {:.1}", self.engine.state.audio_sensitivity);
+            }
+
+            // Video crossfade
+            KeyCode::ArrowRight => {
+                self.engine.state.video_mix = (self.engine.state.video_mix + 0.05).min(1.0);
+                log::info!("Video mix: {:.2}", self.engine.state.video_mix);
+            }
+            KeyCode::ArrowLeft => {
+                self.engine.state.video_mix = (self.engine.state.video_mix - 0.05).max(0.0);
+                log::info!("Video mix: {:.2}", self.engine.state.video_mix);
+            }
+
+            // Rotation - Shift with any rotation key resets all axes
+            KeyCode::Insert | KeyCode::Delete | KeyCode::Home | KeyCode::End | KeyCode::F6 | KeyCode::F7
+                if self.modifiers.shift_key() =>
+            {
+                self.engine.state.reset_rotation();
+                log::info!("Rotation reset");
+            }
+            KeyCode::Insert => self.rotate(ROTATE_STEP, 0.0, 0.0),
+            KeyCode::Delete => self.rotate(-ROTATE_STEP, 0.0, 0.0),
+            KeyCode::Home => self.rotate(0.0, ROTATE_STEP, 0.0),
+            KeyCode::End => self.rotate(0.0, -ROTATE_STEP, 0.0),
+            KeyCode::F7 => self.rotate(0.0, 0.0, ROTATE_STEP),
+            KeyCode::F6 => self.rotate(0.0, 0.0, -ROTATE_STEP),
+
+            // Borderless fullscreen on the current monitor
+            KeyCode::F11 => {
+                let fullscreen = match self.window.fullscreen() {
+                    Some(_) => None,
+                    None => Some(Fullscreen::Borderless(self.monitor.clone())),
+                };
+                self.window.set_fullscreen(fullscreen);
+            }
+
+            // Live parameter overlay
+            KeyCode::F1 => {
+                self.show_hud = !self.show_hud;
+                self.engine.renderer.set_hud_visible(self.show_hud);
+            }
+
+            // Save the current look as the startup defaults
+            KeyCode::F2 => match &self.config_path {
+                Some(path) => match config::save(&self.engine.state, path) {
+                    Ok(()) => log::info!("Saved defaults to {}", path.display()),
+                    Err(e) => log::error!("{}", e),
+                },
+                None => log::error!("No config directory found, pass --config <file> to save defaults"),
+            },
+
+            // Keep a loop built at soundcheck for the set
+            KeyCode::Numpad7 => self.save_pattern(),
+            KeyCode::Numpad1 => self.load_pattern(),
+
+            // Tighten the recorded automation into a stepped sequence
+            KeyCode::F4 => self.engine.apply_command(MidiCommand::Quantize(None, self.quantize_steps)),
+
+            // Test pattern shown without a camera or while one stalls
+            KeyCode::F3 => {
+                let sources = std::iter::once(&mut self.video_source).chain(self.video_source2.as_mut());
+                for dummy in sources.filter_map(VideoSource::test_pattern) {
+                    dummy.pattern = dummy.pattern.next();
+                    log::info!("Test pattern: {:?}", dummy.pattern);
+                }
+            }
+
+            // Temporal blend of the camera input
+            KeyCode::F12 => {
+                self.denoise = !self.denoise;
+                let level = self.denoise_level();
+                let cameras = std::iter::once(&self.video_source).chain(self.video_source2.as_ref());
+                for cam in cameras.filter_map(VideoSource::camera) {
+                    cam.set_denoise(level);
+                }
+                log::info!("Camera denoise: {}", if self.denoise { "on" } else { "off" });
+            }
+
+            // Kick sensitivity, for kick ripples and the strobe
+            KeyCode::Numpad9 | KeyCode::Numpad3 => {
+                if let Some(audio) = &mut self.engine.audio {
+                    let step = if key == KeyCode::Numpad9 { 0.6 } else { -0.6 };
+                    audio.set_kick_threshold((audio.kick_threshold() + step).clamp(0.6, 60.0));
+                    log::info!("Kick threshold: {:.1}/s", audio.kick_threshold());
+                }
+            }
+
+            // Ripple from the middle of the window, for playing without a mouse
+            KeyCode::Numpad5 => {
+                let size = self.engine.renderer.size;
+                let (x, y) = (size.width as f64 / 2.0, size.height as f64 / 2.0);
+                let (u, v) = self.engine.renderer.window_to_video(&self.engine.state, x, y);
+                self.engine.state.ripples.spawn(u, v, 1.0);
+            }
+
+            // Ripple ring profile
+            KeyCode::Backquote => {
+                self.engine.state.ripples.shape = self.engine.state.ripples.shape.next();
+                log::info!("Ripple shape: {:?}", self.engine.state.ripples.shape);
+            }
+
+            // One fader for all distortion, e.g. clean for a breakdown
+            KeyCode::NumpadMultiply | KeyCode::NumpadDivide => {
+                let step = if key == KeyCode::NumpadMultiply { 0.1 } else { -0.1 };
+                self.engine.state.nudge_param(ParamId::MasterIntensity, step);
+                log::info!("Master intensity: {:.1}", self.engine.state.master_intensity);
+            }
+
+            // Noise evolution speed, all axes
+            KeyCode::F9 | KeyCode::F10 => {
+                let step = if key == KeyCode::F10 { 0.1 } else { -0.1 };
+                self.engine.state.nudge_param(ParamId::NoiseTimeScale, step);
+                log::info!("Noise time scale: {:.1}", self.engine.state.noise_time_scale[0]);
+            }
+
+            // Next connected camera, without a restart
+            KeyCode::Enter => self.select_camera(None),
+
+            // Beat-synced strobe
+            KeyCode::Space => {
+                let mode = self.engine.state.strobe.mode.next();
+                self.engine.apply_command(MidiCommand::SetFlash(mode));
+                log::info!("Flash: {:?}", mode);
+            }
+
+            // Orthographic / perspective
+            KeyCode::F8 => {
+                self.engine.state.projection = self.engine.state.projection.next();
+                log::info!("Projection: {:?}", self.engine.state.projection);
+            }
+
+            // Present mode
+            KeyCode::F5 => {
+                self.present_mode = self.present_mode.next();
+                self.engine.renderer.set_present_mode(self.present_mode);
             }
 
             _ => {}
         }
     }
 
+    fn set_modifiers(&mut self, modifiers: ModifiersState) {
+        self.modifiers = modifiers;
+        if !modifiers.control_key() && self.solo_key.is_some() {
+            self.end_solo();
+        }
+    }
+
+    fn end_solo(&mut self) {
+        self.engine.state.solo = None;
+        self.solo_key = None;
+        log::info!("Solo off");
+    }
+
+    /// Enter or leave standby at the edges of the scheduled hours
+    fn update_schedule(&mut self) {
+        let Some(schedule) = &self.schedule else { return };
+        let standby = !schedule.is_open();
+        if standby == self.standby {
+            return;
+        }
+        if standby {
+            log::info!("Outside the scheduled hours, fading out");
+        } else {
+            log::info!("Scheduled hours started, resuming");
+            // Don't count the paused time as simulation backlog
+            self.last_tick = Instant::now();
+            self.logic_accumulator = 0.0;
+        }
+        self.standby = standby;
+    }
+
+    /// In standby and fully faded out: nothing to draw until the schedule opens
+    fn paused(&self) -> bool {
+        self.standby && self.engine.state.output_level <= 0.0
+    }
+
+    /// After `idle_timeout` seconds without input, ease rotation and center offset
+    /// along a slow Lissajous path. Any input drops straight back to the controls.
+    fn update_idle(&mut self) {
+        if self.idle_timeout <= 0.0 || self.last_input.elapsed().as_secs_f32() < self.idle_timeout {
+            if self.idle_time > 0.0 {
+                log::info!("Idle mode off");
+            }
+            self.idle_time = 0.0;
+            self.engine.state.idle_rotate = [0.0; 3];
+            self.engine.state.idle_center = [0.0; 2];
+            return;
+        }
+
+        if self.idle_time == 0.0 {
+            log::info!("Idle mode on");
+        }
+        self.idle_time += self.logic_step;
+        let t = self.idle_time;
+        let ramp = (t / IDLE_RAMP_SECS).min(1.0);
+        let ramp = ramp * ramp * (3.0 - 2.0 * ramp);
+
+        // Incommensurate rates so the path takes a long time to repeat
+        self.engine.state.idle_rotate = [
+            ramp * IDLE_ROTATE_AMP * (0.13 * t).sin(),
+            ramp * IDLE_ROTATE_AMP * (0.17 * t).sin(),
+            ramp * IDLE_ROTATE_AMP * (0.07 * t).sin(),
+        ];
+        self.engine.state.idle_center = [
+            ramp * IDLE_CENTER_AMP * (0.11 * t).sin(),
+            ramp * IDLE_CENTER_AMP * (0.19 * t).sin(),
+        ];
+    }
+
+    fn rotate(&mut self, x: f32, y: f32, z: f32) {
+        self.engine.state.rotate_x += x;
+        self.engine.state.rotate_y += y;
+        self.engine.state.rotate_z += z;
+        log::info!(
+            "Rotation: x {:.2} y {:.2} z {:.2}",
+            self.engine.state.rotate_x,
+            self.engine.state.rotate_y,
+            self.engine.state.rotate_z
+        );
+    }
+
     fn print_help(&self) {
         println!("\n╔════════════════════════════════════════════════════════════════╗");
         println!("║              SPECTRAL MESH v5.0 - CONTROLS                     ║");
         println!("╠════════════════════════════════════════════════════════════════╣");
         println!("║ H        : Toggle this help                                    ║");
-        println!("║ ESC      : Quit                                                ║");
+        println!("║ F1       : Toggle the parameter overlay                        ║");
+        println!("║ F2       : Save current settings as the startup defaults       ║");
+        println!("║ F4       : Quantize recorded automation (--quantize-steps)     ║");
+        println!("║ Num 7/1  : Save / recall the recorded automation (--pattern)   ║");
+        match self.quit_key {
+            Some(key) => println!("║ {:<9}: Quit{:<48}║", format!("{:?}", key), ""),
+            None => println!("║ Quit     : Close the window (or set --quit-key esc)            ║"),
+        }
         println!("╠════════════════════════════════════════════════════════════════╣");
         println!("║ MESH TYPE                                                      ║");
         println!("║ 9        : Vertical lines                                      ║");
         println!("║ 0        : Horizontal lines                                    ║");
         println!("║ -        : Triangles (filled)                                  ║");
         println!("║ =        : Triangles (wireframe)                               ║");
+        println!("║ Tab      : Next mesh type (Shift+Tab: previous)                ║");
         println!("║ [ / ]    : Decrease / Increase grid density                    ║");
+        println!("║ Shift+[ / ]: Invert the MIDI scale knob direction              ║");
         println!("╠════════════════════════════════════════════════════════════════╣");
         println!("║ EFFECTS                                                        ║");
         println!("║ 1        : Toggle luma key mode                                ║");
         println!("║ 2        : Toggle brightness mode                              ║");
         println!("║ 3        : Toggle color inversion                              ║");
+        println!("║ 4        : Freeze / unfreeze the noise (Shift+4: save as PNG)  ║");
+        println!("║ Alt+4    : New random noise seed (logged for --seed)           ║");
         println!("║ 5        : Toggle greyscale                                    ║");
         println!("║ A / Z    : Luma key level +/-                                  ║");
+        println!("║ ' / \\    : Chromatic aberration +/-                            ║");
+        println!("║ Shift+'  : Chromatic aberration along the displacement         ║");
+        println!("║ PgUp/PgDn: Feedback trails +/-                                 ║");
+        println!("║ Num * / /: Master intensity +/- (all distortion, 0 = clean)    ║");
+        println!("║ Ctrl+key : Hold to solo that key's effect (LFO, displacement,  ║");
+        println!("║            color, chroma, feedback)                            ║");
         println!("╠════════════════════════════════════════════════════════════════╣");
-        println!("║ LFO SHAPES (cycle: sine -> square -> saw -> triangle)          ║");
+        println!("║ LFO SHAPES (cycle: sine -> square -> triangle -> noise)        ║");
         println!("║ 6        : Z LFO shape                                         ║");
         println!("║ 7        : X LFO shape                                         ║");
         println!("║ 8        : Y LFO shape                                         ║");
+        println!("║ Shift+6/7/8: Z/X/Y displacement source                         ║");
+        println!("║   (classic -> noise -> LFO -> audio -> luma)                   ║");
+        println!("║ Alt+6/7/8: Z/X/Y noise (perlin -> simplex -> worley -> billow  ║");
+        println!("║   -> fbm)                                                      ║");
+        println!("║ F9 / F10 : Noise evolution speed -/+                           ║");
+        println!("║ Shift+Num6/4, 8/2, +/-: X, Y, Z noise resolution +/-           ║");
+        println!("║ Alt+Num6/4, 8/2, +/-  : X, Y, Z noise amount +/-               ║");
         println!("╠════════════════════════════════════════════════════════════════╣");
         println!("║ Z LFO (zoom/scale)                                             ║");
         println!("║ S / X    : Frequency +/-                                       ║");
         println!("║ D / C    : Phase +/-                                           ║");
         println!("║ F / V    : Amplitude +/-                                       ║");
+        println!("║ Num+/Num-: Bias (center it oscillates around) +/-              ║");
         println!("╠════════════════════════════════════════════════════════════════╣");
         println!("║ X LFO (horizontal waves)                                       ║");
         println!("║ G / B    : Frequency +/-                                       ║");
         println!("║ H / N    : Phase +/-                                           ║");
         println!("║ J / M    : Amplitude +/-                                       ║");
+        println!("║ Num6/Num4: Bias +/- (right / left)                             ║");
         println!("╠════════════════════════════════════════════════════════════════╣");
         println!("║ Y LFO (vertical waves)                                         ║");
         println!("║ K / ,    : Frequency +/-                                       ║");
         println!("║ L / .    : Phase +/-                                           ║");
         println!("║ ; / /    : Amplitude +/-                                       ║");
+        println!("║ Num8/Num2: Bias +/- (up / down)                                ║");
         println!("╠════════════════════════════════════════════════════════════════╣");
         println!("║ DISPLACEMENT                                                   ║");
         println!("║ Q / W    : X displacement +/-                                  ║");
@@ -320,9 +1400,36 @@ impl App {
         println!("║ POSITION                                                       ║");
         println!("║ T / Y    : Center X +/-                                        ║");
         println!("║ U / I    : Center Y +/-                                        ║");
+        println!("║ Shift+T  : Auto-center (off -> brightness -> motion)           ║");
         println!("║ O / P    : Zoom +/-                                            ║");
+        println!("╠════════════════════════════════════════════════════════════════╣");
+        println!("║ ROTATION (Shift + any rotation key: reset all)                 ║");
+        println!("║ Ins / Del  : Rotate X +/-                                      ║");
+        println!("║ Home / End : Rotate Y +/-                                      ║");
+        println!("║ F7 / F6    : Rotate Z +/-                                      ║");
+        println!("╠════════════════════════════════════════════════════════════════╣");
+        println!("║ DISPLAY                                                        ║");
+        println!("║ Left/Right: Crossfade to second video source (--video2)        ║");
+        println!("║ Shift+Up/Down    : Source exposure +/-                         ║");
+        println!("║ Shift+Left/Right : Source gamma -/+                            ║");
+        println!("║ Enter    : Switch to the next camera                           ║");
+        println!("║ F3       : Cycle test pattern (no camera or camera stalled)    ║");
+        println!("║ F5       : Cycle present mode (vsync / uncapped)               ║");
+        println!("║ F8       : Toggle orthographic / perspective projection        ║");
+        println!("║ F11      : Toggle borderless fullscreen                        ║");
+        println!("║ F12      : Toggle camera denoise (--denoise-amount)            ║");
+        println!("║ Alt+B    : Toggle bloom (--bloom sets its strength)            ║");
+        println!("║ Space    : Kick strobe (off -> brighten -> invert)             ║");
+        println!("╠════════════════════════════════════════════════════════════════╣");
+        println!("║ MOUSE                                                          ║");
+        println!("║ Left click : Spawn a ripple                                    ║");
+        println!("║ Numpad 5   : Spawn a ripple at the window center               ║");
+        println!("║ Numpad 9/3 : Kick threshold +/- (kick ripples, strobe)         ║");
+        println!("║ `          : Ripple shape (gaussian -> sine -> saw -> square)  ║");
+        println!("║ Right drag : Move the pivot (center of the distortion)         ║");
+        println!("║ Middle click : Put the pivot under the cursor                  ║");
         println!("╚════════════════════════════════════════════════════════════════╝");
-        if self.audio.is_some() {
+        if self.engine.audio.is_some() {
             println!("║ AUDIO    : Active (modulating displacement & LFO)             ║");
         } else {
             println!("║ AUDIO    : Disabled (use --audio to enable)                   ║");
@@ -330,127 +1437,724 @@ impl App {
         println!();
     }
 
-    fn update(&mut self) {
-        // Process MIDI
-        if let Some(ref midi) = self.midi {
-            for cmd in midi.poll_all() {
-                self.state.process_midi(cmd);
-            }
-        }
-
-        // Update p_lock system
-        self.state.p_lock.update();
-
-        // Audio modulation - aesthetic effect: bass modulates displacement and LFO
-        if let Some(ref mut audio) = self.audio {
-            let sensitivity = self.state.audio_sensitivity;
-            let bass = audio.bass() * sensitivity;
-            let rms = audio.rms() * sensitivity;
-
-            // Reduced amplitude for subtle global effect
-            self.state.audio_mod_displacement = bass * 2.0;
-            self.state.audio_mod_lfo = rms * 1.0;
-            self.state.audio_mod_z = bass * 0.02;
-
-            // Audio vibration effect - lines tremble with the music
-            // Phase advances fast for vibration effect
-            let phase_speed = 0.5 + bass * 1.5; // Faster base speed, accelerates with bass
-            self.state.audio_wave_phase += phase_speed;
-
-            // Amplitude pulses with bass - fast attack, slower decay
-            let target_amp = bass * 0.08; // Vibration amplitude
-            // Fast attack (0.4), slower decay (0.9) for punchy response
-            if target_amp > self.state.audio_wave_amp {
-                self.state.audio_wave_amp = self.state.audio_wave_amp * 0.6 + target_amp * 0.4;
-            } else {
-                self.state.audio_wave_amp = self.state.audio_wave_amp * 0.92 + target_amp * 0.08;
-            }
-
-            // Frequency not used for vibration but keep for potential future use
-            self.state.audio_wave_freq = 10.0 + rms * 20.0;
-        }
-
-        // Calculate render params
-        let params = self.state.calculate_render_params();
-
-        // Update LFO phases - no wrapping to avoid discontinuities
-        // Precision issues won't occur for hours of continuous use
-        self.state.z_lfo_arg += params.z_lfo_arg;
-        self.state.x_lfo_arg += params.x_lfo_arg;
-        self.state.y_lfo_arg += params.y_lfo_arg;
-
-        // Update noise textures
-        self.noise_bank.update(
-            self.state.x_lfo_arg,
-            self.state.p_lock.get(4),
-            self.state.y_lfo_arg,
-            self.state.p_lock.get(5),
-            self.state.z_lfo_arg,
-            self.state.p_lock.get(3),
+    /// Run as many fixed-rate updates as the elapsed wall-clock time calls for
+    fn advance(&mut self) {
+        let now = Instant::now();
+        self.logic_accumulator += (now - self.last_tick).as_secs_f32();
+        self.last_tick = now;
+
+        let mut updates = 0;
+        while self.logic_accumulator >= self.logic_step {
+            self.update();
+            self.logic_accumulator -= self.logic_step;
+            updates += 1;
+            if updates >= MAX_UPDATES_PER_FRAME {
+                // Drop the backlog rather than fast-forwarding the animation
+                self.logic_accumulator = 0.0;
+                break;
+            }
+        }
+        self.update_title();
+    }
+
+    /// Show the FPS (refreshed once a second), mesh, grid scale and live inputs
+    /// in the window title, and while recording where in the p_lock loop it
+    /// is by quantize step (e.g. "REC 5/16 [#####-----------]")
+    fn update_title(&mut self) {
+        let state = &self.engine.state;
+        let on_off = |active: bool| if active { "on" } else { "off" };
+        let mut title = format!(
+            "{} - {:.1} fps | {:?} | grid {} | audio {} | MIDI {}",
+            WINDOW_TITLE,
+            self.fps,
+            state.mesh_type,
+            state.scale,
+            on_off(self.engine.audio.is_some()),
+            on_off(self.midi.is_some())
         );
+        let p_lock = &state.p_lock;
+        if p_lock.recording {
+            let divisions = self.quantize_steps.min(p_lock.length());
+            let done = (p_lock.current_step() * divisions / p_lock.length()) + 1;
+            let _ = write!(
+                title,
+                " - REC {}/{} [{}{}]",
+                done,
+                divisions,
+                "#".repeat(done),
+                "-".repeat(divisions - done)
+            );
+        }
+        if title != self.title {
+            self.window.set_title(&title);
+            self.title = title;
+        }
+    }
 
-        // Check if mesh needs rebuild
-        let new_scale = params.scale.clamp(1, 127);
-        if new_scale != self.last_mesh_scale || self.needs_mesh_rebuild {
-            self.last_mesh_scale = new_scale;
-            self.needs_mesh_rebuild = false;
-            self.state.scale = new_scale;
+    fn update(&mut self) {
+        // Process MIDI and stdin control
+        let mut commands = self.midi.as_ref().map(MidiHandler::poll_all).unwrap_or_default();
+        if let Some(ref stdin) = self.stdin {
+            commands.extend(stdin.poll_all());
+        }
+        if let Some(ref mut replay) = self.replay {
+            commands.extend(replay.poll_all());
+            if replay.is_finished() {
+                log::info!("Command replay finished");
+                self.replay = None;
+            }
+        }
+        for cmd in commands {
+            if let Some(ref mut recorder) = self.recorder {
+                recorder.record(&cmd);
+            }
+            // Clock keeps running unattended, so it doesn't count as input
+            let realtime = matches!(
+                cmd,
+                MidiCommand::Clock | MidiCommand::ClockStart | MidiCommand::ClockContinue | MidiCommand::ClockStop
+            );
+            if !realtime {
+                self.last_input = Instant::now();
+            }
+            // The camera and pattern file belong to the app, not the engine
+            match cmd {
+                MidiCommand::SelectCamera(index) => self.select_camera(index),
+                MidiCommand::SavePattern => self.save_pattern(),
+                MidiCommand::LoadPattern => self.load_pattern(),
+                cmd => self.engine.apply_command(cmd),
+            }
+        }
+        self.update_idle();
+        self.update_schedule();
+
+        // Fade toward black in standby, back up when open
+        let target = if self.standby { 0.0 } else { 1.0 };
+        let step = self.logic_step / STANDBY_FADE_SECS;
+        let level = &mut self.engine.state.output_level;
+        *level = if *level < target { (*level + step).min(target) } else { (*level - step).max(target) };
+
+        self.engine.update();
+        if let Some(ref mut midi) = self.midi {
+            midi.send_feedback(&self.engine.state);
         }
     }
 
-    fn render(&mut self) {
-        // Update video texture
-        let frame = match &mut self.video_source {
-            VideoSource::Camera(cam) => {
-                cam.get_frame();
-                cam.current_frame()
+    fn save_pattern(&self) {
+        save_pattern(&self.engine.state.p_lock, self.pattern_path.as_deref());
+    }
+
+    fn load_pattern(&mut self) {
+        load_pattern(&mut self.engine.state.p_lock, self.pattern_path.as_deref());
+    }
+
+    /// Open camera `index`, or the next connected one after the current
+    /// camera for None. It replaces the main source once it delivers a frame.
+    fn select_camera(&mut self, index: Option<u32>) {
+        let current = self.pending_camera.as_ref().map_or(self.camera_index, |(cam, _)| cam.device_index);
+        let index = match index {
+            Some(index) => index,
+            None => {
+                #[cfg(feature = "camera")]
+                let indices: Vec<u32> = video::list_cameras().into_iter().map(|(index, _)| index).collect();
+                #[cfg(not(feature = "camera"))]
+                let indices: Vec<u32> = Vec::new();
+                match indices.iter().find(|&&i| i > current).or(indices.first()) {
+                    Some(&next) if next != current => next,
+                    _ => {
+                        log::warn!("No other camera to switch to");
+                        return;
+                    }
+                }
             }
-            VideoSource::Dummy(dummy) => dummy.update(),
         };
-        self.renderer.update_video_texture(frame, self.video_width, self.video_height);
-
-        // Update noise textures
-        self.renderer.update_noise_texture(0, self.noise_bank.x_noise.pixels(), NOISE_WIDTH, NOISE_HEIGHT);
-        self.renderer.update_noise_texture(1, self.noise_bank.y_noise.pixels(), NOISE_WIDTH, NOISE_HEIGHT);
-        self.renderer.update_noise_texture(2, self.noise_bank.z_noise.pixels(), NOISE_WIDTH, NOISE_HEIGHT);
 
-        // Rebuild mesh if needed
-        let mesh = match self.state.mesh_type {
-            mesh::MeshType::Triangles => {
-                Mesh::triangle_mesh(self.state.scale, self.video_width as f32, self.video_height as f32)
+        let state = &self.engine.state;
+        match VideoCapture::new(state.width, state.height, index, self.resize_filter) {
+            Ok(cam) => {
+                log::info!("Switching to camera {}...", index);
+                cam.set_denoise(self.denoise_level());
+                self.pending_camera = Some((cam, Instant::now()));
             }
-            mesh::MeshType::HorizontalLines => {
-                Mesh::horizontal_line_mesh(self.state.scale, self.video_width as f32, self.video_height as f32)
+            Err(e) => log::warn!("Camera {} failed: {}. Staying on the current source.", index, e),
+        }
+    }
+
+    /// Camera temporal blend amount in effect, 0 when off
+    fn denoise_level(&self) -> f32 {
+        if self.denoise {
+            self.denoise_amount
+        } else {
+            0.0
+        }
+    }
+
+    /// Swap in a camera opened by `select_camera` once it's streaming, or give
+    /// up on it if it failed
+    fn poll_pending_camera(&mut self) {
+        let Some((cam, started)) = &mut self.pending_camera else { return };
+        if cam.get_frame().is_none() {
+            if cam.is_closed() || started.elapsed().as_secs_f32() > CAMERA_SWITCH_TIMEOUT_SECS {
+                log::warn!("Camera {} did not start. Staying on the current source.", cam.device_index);
+                self.pending_camera = None;
             }
-            mesh::MeshType::VerticalLines => {
-                Mesh::vertical_line_mesh(self.state.scale, self.video_width as f32, self.video_height as f32)
+            return;
+        }
+
+        let Some((cam, _)) = self.pending_camera.take() else { return };
+        log::info!("Switched to camera {}", cam.device_index);
+        self.camera_index = cam.device_index;
+        match &mut self.video_source {
+            VideoSource::Camera(feed) => feed.switch_to(cam),
+            source => {
+                let state = &self.engine.state;
+                let pattern = source.test_pattern().map_or(self.test_pattern, |dummy| dummy.pattern);
+                let fallback = DummyVideoSource::new(state.width, state.height, pattern);
+                self.video_source = VideoSource::Camera(CameraFeed::new(cam, fallback, self.stall_timeout));
             }
-            mesh::MeshType::Grid => {
-                Mesh::grid_mesh(self.state.scale, self.video_width as f32, self.video_height as f32)
+        }
+    }
+
+    fn render(&mut self) {
+        self.poll_pending_camera();
+
+        // Update video texture
+        if self.native_aspect {
+            if let VideoSource::Camera(feed) = &self.video_source {
+                if let Some((w, h)) = feed.cam.source_resolution() {
+                    self.engine.renderer.set_source_aspect(Some(w as f32 / h as f32));
+                }
             }
-        };
-        self.renderer.update_mesh(&mesh);
+        }
+        let frame = self.video_source.next_frame();
+        self.engine.feed_frame(frame);
 
-        // Update uniforms
-        self.renderer.update_uniforms(&self.state);
+        if let Some(source) = &mut self.video_source2 {
+            let frame = source.next_frame();
+            self.engine.feed_frame2(frame);
+        }
+
+        if self.show_hud && self.hud.update(&self.engine.state, self.engine.audio.as_ref(), self.fps) {
+            self.engine.renderer.update_hud(self.hud.pixels(), Hud::width(), Hud::height());
+        }
 
         // Render
-        match self.renderer.render() {
-            Ok(_) => {}
-            Err(wgpu::SurfaceError::Lost) => self.renderer.resize(self.renderer.size),
+        match self.engine.render() {
+            Ok(_) => self.surface_timeouts = 0,
+            Err(e @ (wgpu::SurfaceError::Lost | wgpu::SurfaceError::Outdated)) => {
+                log::debug!("Surface {:?}, reconfiguring", e);
+                self.engine.renderer.resize(self.engine.renderer.size);
+            }
             Err(wgpu::SurfaceError::OutOfMemory) => {
                 log::error!("Out of memory");
                 std::process::exit(1);
             }
-            Err(e) => log::warn!("Render error: {:?}", e),
+            Err(wgpu::SurfaceError::Timeout) => {
+                // Single timeouts happen (e.g. window hidden); a run of them means the
+                // swapchain is stuck, which a fresh configure often recovers
+                self.surface_timeouts += 1;
+                if self.surface_timeouts >= SURFACE_TIMEOUT_LIMIT {
+                    log::error!(
+                        "Surface timed out {} frames in a row, reconfiguring. If the window stays frozen, \
+                         the GPU driver may be hung; try --present-mode fifo or a different backend (WGPU_BACKEND)",
+                        self.surface_timeouts
+                    );
+                    self.surface_timeouts = 0;
+                    self.engine.renderer.resize(self.engine.renderer.size);
+                } else {
+                    log::warn!("Surface timeout ({} in a row)", self.surface_timeouts);
+                }
+            }
+        }
+
+        // Frames read back a frame or two behind, converted and sent off-thread
+        if let Some(ndi) = &self.ndi {
+            if let Some(frame) = self.engine.renderer.take_captured_frame() {
+                ndi.send(frame);
+            }
+        }
+
+        // FPS averaged over one second
+        self.fps_frames += 1;
+        let elapsed = self.fps_timer.elapsed().as_secs_f32();
+        if elapsed >= 1.0 {
+            self.fps = self.fps_frames as f32 / elapsed;
+            self.fps_frames = 0;
+            self.fps_timer = Instant::now();
+            log::info!("FPS: {:.1} (grid {})", self.fps, self.engine.state.scale);
+        }
+    }
+
+    fn cursor_moved(&mut self, x: f64, y: f64) {
+        self.cursor = (x, y);
+        if self.drag_last.is_some() {
+            self.last_input = Instant::now();
+        }
+
+        // Right-drag moves the pivot by the distance dragged, in video coords
+        if let Some((last_u, last_v)) = self.drag_last {
+            let (u, v) = self.engine.renderer.window_to_video(&self.engine.state, x, y);
+            for (index, delta) in [(8, u - last_u), (9, v - last_v)] {
+                let value = (self.engine.state.p_lock.get(index) + delta).clamp(0.0, 1.0);
+                self.engine.state.p_lock.set(index, value);
+            }
+            self.drag_last = Some((u, v));
+        }
+    }
+
+    fn handle_mouse(&mut self, button: MouseButton, pressed: bool) {
+        self.last_input = Instant::now();
+        let (u, v) = self.engine.renderer.window_to_video(&self.engine.state, self.cursor.0, self.cursor.1);
+        match button {
+            // Clicks in the letterbox are ignored
+            MouseButton::Left if pressed && (0.0..=1.0).contains(&u) && (0.0..=1.0).contains(&v) => {
+                self.engine.state.ripples.spawn(u, v, 1.0);
+            }
+            MouseButton::Right => {
+                self.drag_last = if pressed { Some((u, v)) } else { None };
+            }
+            // Middle click drops the pivot under the cursor
+            MouseButton::Middle if pressed => {
+                let (x, y) = self.engine.renderer.window_to_clip(self.cursor.0, self.cursor.1);
+                self.engine.state.p_lock.set(8, (0.5 + x / 2.0).clamp(0.0, 1.0));
+                self.engine.state.p_lock.set(9, (0.5 + y / 2.0).clamp(0.0, 1.0));
+            }
+            _ => {}
         }
     }
 
     fn resize(&mut self, size: winit::dpi::PhysicalSize<u32>) {
-        self.renderer.resize(size);
+        self.engine.renderer.resize(size);
+    }
+
+    /// Resize the surface if the window's physical size has moved on without a
+    /// `Resized` event, as after a scale factor change on some platforms
+    fn sync_size(&mut self) {
+        let size = self.window.inner_size();
+        if size != self.engine.renderer.size {
+            self.resize(size);
+        }
+    }
+}
+
+/// Effect group controlled by a key, for Ctrl+key solo
+/// Assemble `path` into the mesh shader, keeping the built-in effects alone if it fails
+fn load_effect_chain(renderer: &mut Renderer, path: &std::path::Path) {
+    match EffectChain::load(path).and_then(|chain| {
+        renderer.set_effect_chain(&chain)?;
+        Ok(chain)
+    }) {
+        Ok(chain) => {
+            let names: Vec<&str> = chain.effects.iter().map(|effect| effect.name.as_str()).collect();
+            log::info!("Effect chain: {}", names.join(" -> "));
+        }
+        Err(e) => log::warn!("Effect chain not loaded: {}", e),
+    }
+}
+
+/// --image at the processing resolution, or None (logged) if it can't be read
+fn load_image(path: &std::path::Path, args: &Args) -> Option<ImageVideoSource> {
+    match ImageVideoSource::new(path, args.width, args.height, args.resize_filter) {
+        Ok(image) => {
+            log::info!("Showing {}", path.display());
+            Some(image)
+        }
+        Err(e) => {
+            log::warn!("Image not shown: {}", e);
+            None
+        }
+    }
+}
+
+/// MIDI input from --midi-virtual or --midi, with --midi-map bindings and
+/// --midi-out feedback, or None if no port opens
+fn open_midi(args: &Args) -> Option<MidiHandler> {
+    let midi_map = match args.midi_map.as_deref().map(load_midi_map) {
+        Some(Ok(map)) => {
+            log::info!("Loaded {} CC and {} note MIDI bindings", map.cc.len(), map.note.len());
+            map
+        }
+        Some(Err(e)) => {
+            log::warn!("MIDI map not loaded, using the default CCs: {}", e);
+            MidiMap::default()
+        }
+        None => MidiMap::default(),
+    };
+    let virtual_midi = args.midi_virtual.as_deref().and_then(|name| {
+        match MidiHandler::new_virtual(name, &args.invert_cc, &midi_map) {
+            Ok(midi) => {
+                log::info!("Virtual MIDI input '{}' created", name);
+                Some(midi)
+            }
+            Err(e) => {
+                log::warn!("Virtual MIDI unavailable, using port {}: {}", args.midi, e);
+                None
+            }
+        }
+    });
+    let mut midi = virtual_midi.or_else(|| match MidiHandler::new(args.midi, &args.invert_cc, &midi_map) {
+        Ok(midi) => {
+            log::info!("MIDI initialized on port {}", args.midi);
+            Some(midi)
+        }
+        Err(e) => {
+            log::warn!("MIDI initialization failed: {}", e);
+            None
+        }
+    });
+    if let (Some(midi), Some(port)) = (midi.as_mut(), args.midi_out) {
+        if let Err(e) = midi.open_output(port, &args.invert_cc, &midi_map) {
+            log::warn!("MIDI feedback disabled: {}", e);
+        }
+    }
+    midi
+}
+
+/// Main video source: a still image or video file if given, else the camera,
+/// falling back to the test pattern
+fn open_video(args: &Args) -> VideoSource {
+    let denoise = if args.denoise { args.denoise_amount } else { 0.0 };
+    let image = args.image.as_deref().and_then(|path| load_image(path, args).map(VideoSource::Image));
+    let file = args.file.as_deref().and_then(|path| {
+        match FileVideoSource::new(path, args.width, args.height, args.resize_filter, !args.no_loop) {
+            Ok(file) => {
+                log::info!("Playing {}", path.display());
+                Some(VideoSource::File(file))
+            }
+            Err(e) => {
+                log::warn!("Video file failed: {}. Using the camera.", e);
+                None
+            }
+        }
+    });
+    match image.or(file) {
+        Some(file) => file,
+        None => match VideoCapture::new(args.width, args.height, args.video, args.resize_filter) {
+            Ok(cam) => {
+                log::info!("Camera {} initialized ({}x{})", args.video, args.width, args.height);
+                cam.set_denoise(denoise);
+                let fallback = DummyVideoSource::new(args.width, args.height, args.test_pattern);
+                VideoSource::Camera(CameraFeed::new(cam, fallback, args.stall_timeout))
+            }
+            Err(e) => {
+                log::warn!("Camera failed: {}. Using test pattern.", e);
+                VideoSource::Dummy(DummyVideoSource::new(args.width, args.height, args.test_pattern))
+            }
+        },
+    }
+}
+
+/// Audio analyzer on --audio or the default input, with the gain and kick
+/// settings applied, or None if disabled or unavailable
+fn open_audio(args: &Args) -> Option<AudioAnalyzer> {
+    let audio = if args.no_audio {
+        log::info!("Audio disabled");
+        None
+    } else if let Some(audio_idx) = args.audio {
+        match AudioAnalyzer::new(Some(audio_idx)) {
+            Ok(analyzer) => {
+                log::info!("Audio analyzer initialized");
+                Some(analyzer)
+            }
+            Err(e) => {
+                log::warn!("Audio initialization failed: {}", e);
+                None
+            }
+        }
+    } else {
+        // Try default audio device
+        match AudioAnalyzer::new(None) {
+            Ok(analyzer) => {
+                log::info!("Audio analyzer initialized (default device)");
+                Some(analyzer)
+            }
+            Err(e) => {
+                log::info!("No audio input: {}", e);
+                None
+            }
+        }
+    };
+    let mut audio = audio?;
+    if args.agc {
+        log::info!("Automatic gain over {:.0} s of audio", args.agc_window);
+        audio.set_agc(args.agc_window.max(0.1));
+    }
+    if let Some(reference) = args.audio_reference {
+        log::info!("Audio levels calibrated to {:.1} dBFS", reference);
+        audio.set_reference_level(Some(reference));
+    }
+    audio.set_kick_threshold(args.kick_threshold);
+    Some(audio)
+}
+
+/// Write the p_lock pattern to --pattern (or next to the defaults)
+fn save_pattern(p_lock: &PLockSystem, path: Option<&std::path::Path>) {
+    let Some(path) = path else {
+        log::error!("No config directory found, pass --pattern <file> to save the pattern");
+        return;
+    };
+    match p_lock.save_to_file(path) {
+        Ok(()) => log::info!("Saved the p_lock pattern to {}", path.display()),
+        Err(e) => log::error!("Failed to save pattern {}: {}", path.display(), e),
     }
 }
 
+/// Replace the p_lock pattern with the saved one
+fn load_pattern(p_lock: &mut PLockSystem, path: Option<&std::path::Path>) {
+    let Some(path) = path else {
+        log::error!("No config directory found, pass --pattern <file> to load a pattern");
+        return;
+    };
+    match p_lock.load_from_file(path) {
+        Ok(()) => log::info!("Recalled the p_lock pattern from {}", path.display()),
+        Err(e) => log::error!("Failed to load pattern {}: {}", path.display(), e),
+    }
+}
+
+/// Parameter state from the command line, then the saved defaults at `config_path`
+fn initial_state(args: &Args, config_path: Option<&std::path::Path>) -> AppState {
+    let mut state = AppState::new(args.width, args.height);
+    state.p_lock.clock_pulses_per_pattern = args.clock_bars * p_lock::CLOCK_PULSES_PER_BAR;
+    state.clear_color = args.clear_color;
+    state.invert_scale = args.invert_scale;
+    state.audio_stroke_band = args.audio_stroke;
+    state.audio_stroke_gain = args.audio_stroke_gain;
+    state.audio_chroma_band = args.audio_chroma;
+    state.audio_chroma_gain = args.audio_chroma_gain.clamp(0.0, 1.0);
+    state.audio_rotate_band = args.audio_rotate;
+    state.audio_zoom_band = args.audio_zoom;
+    state.audio_zoom_amount = args.audio_zoom_amount.clamp(0.0, 1.0);
+    state.audio_zoom_attack = args.audio_zoom_attack.clamp(0.0, 1.0);
+    state.audio_zoom_release = args.audio_zoom_release.clamp(0.0, 1.0);
+    state.motion_displace = args.motion_displace;
+    state.strobe.mode = args.flash;
+    state.strobe.threshold = args.flash_threshold.max(0.0);
+    state.strobe.intensity = args.flash_intensity.clamp(0.0, MAX_FLASH_INTENSITY);
+    state.loop_flash = args.loop_flash.clamp(0.0, MAX_FLASH_INTENSITY);
+    state.ripples.shape = args.ripple_shape;
+    state.ripples.set_count(args.max_ripples);
+    for (scale, &arg) in state.noise_time_scale.iter_mut().zip(&args.noise_time_scale) {
+        *scale = arg.max(0.0);
+    }
+    match args.noise_kind.as_slice() {
+        &[kind] => state.noise_kind = [kind; 3],
+        kinds => {
+            for (kind, &arg) in state.noise_kind.iter_mut().zip(kinds) {
+                *kind = arg;
+            }
+        }
+    }
+    for (gain, &arg) in state.audio_rotate_gain.iter_mut().zip(&args.audio_rotate_gain) {
+        *gain = arg;
+    }
+    for (band, &arg) in state.audio_displace_band.iter_mut().zip(&args.audio_displace) {
+        *band = arg;
+    }
+    for (gain, &arg) in state.audio_displace_gain.iter_mut().zip(&args.audio_displace_gain) {
+        *gain = arg;
+    }
+    state.auto_center.mode = args.auto_center;
+    state.auto_center.responsiveness = args.auto_center_speed;
+    state.projection = args.projection;
+    state.line_render = args.line_render;
+    state.mesh_smoothing = args.mesh_smoothing.clamp(0.0, 1.0);
+    state.bloom_intensity = args.bloom.clamp(0.0, 2.0);
+    state.bloom_threshold = args.bloom_threshold.clamp(0.0, 1.0);
+    state.audio_bloom_gain = args.audio_bloom_gain.clamp(0.0, 2.0);
+    state.fov = args.fov.clamp(1.0, 179.0);
+    state.near = args.near.max(0.001);
+    state.far = args.far.max(state.near + 1.0);
+    if args.transparent {
+        state.clear_color[3] = 0.0;
+    }
+
+    // Saved defaults win over the command line, so a launch picks up where F2 left off
+    if let Some(path) = config_path.filter(|path| path.exists()) {
+        match config::load(&mut state, path) {
+            Ok(()) => log::info!("Loaded defaults from {}", path.display()),
+            Err(e) => log::warn!("{}", e),
+        }
+    }
+    state
+}
+
+/// Size and reseed the noise, reseed the ripples, recall the p_lock pattern and load frozen noise maps.
+/// Returns where the pattern is saved: --pattern, or next to the defaults.
+fn recall_saved(
+    engine: &mut SpectralMesh,
+    args: &Args,
+    config_path: Option<&std::path::Path>,
+) -> Option<std::path::PathBuf> {
+    engine.set_noise_size(args.noise_width, args.noise_height);
+    engine.set_noise_seed(args.seed);
+    engine.state.ripples.reseed(args.seed);
+
+    let pattern_path =
+        args.pattern.clone().or_else(|| Some(config_path?.parent()?.join(p_lock::PATTERN_FILE_NAME)));
+    if let Some(path) = pattern_path.as_deref().filter(|path| path.exists()) {
+        match engine.state.p_lock.load_from_file(path) {
+            Ok(()) => log::info!("Recalled the p_lock pattern from {}", path.display()),
+            Err(e) => log::warn!("Failed to load pattern {}: {}", path.display(), e),
+        }
+    }
+
+    if let Some(dir) = args.noise_maps.as_deref() {
+        match engine.load_noise(dir) {
+            Ok(()) => log::info!("Loaded frozen noise maps from {}", dir.display()),
+            Err(e) => log::warn!("{}", e),
+        }
+    }
+    pattern_path
+}
+
+/// Render `frames` frames of --window-width x --window-height to PNGs, driven
+/// only by the command line, the saved defaults and pattern, and a command
+/// replay on the frame clock, so the same inputs always give the same clip
+fn render_offline(args: &Args, frames: u32) -> Result<(), String> {
+    let mut renderer = pollster::block_on(Renderer::new_headless(args.window_width, args.window_height))?;
+    renderer.set_render_scale(args.render_scale, args.render_filter);
+    if let Some(path) = &args.effects {
+        load_effect_chain(&mut renderer, path);
+    }
+
+    let config_path = args.config.clone().or_else(config::default_path);
+    let state = initial_state(args, config_path.as_deref());
+    let mut engine = SpectralMesh::new(renderer, state, args.width, args.height);
+    recall_saved(&mut engine, args, config_path.as_deref());
+
+    let mut replay = args.replay_commands.as_deref().map(CommandReplay::load).transpose()?;
+    // The camera can't be replayed, so the --image or test pattern stands in for it
+    let mut video = match args.image.as_deref().and_then(|path| load_image(path, args)) {
+        Some(image) => VideoSource::Image(image),
+        None => VideoSource::Dummy(DummyVideoSource::new(args.width, args.height, args.test_pattern)),
+    };
+
+    std::fs::create_dir_all(&args.output_dir)
+        .map_err(|e| format!("Failed to create {}: {}", args.output_dir.display(), e))?;
+    let logic_hz = args.logic_hz.max(1.0);
+    let (width, height) = engine.renderer.output_size();
+    log::info!(
+        "Rendering {} frames ({}x{}, {:.1} s at {} Hz) to {}",
+        frames,
+        width,
+        height,
+        frames as f32 / logic_hz,
+        logic_hz,
+        args.output_dir.display()
+    );
+
+    for frame in 0..frames {
+        if let Some(replay) = &mut replay {
+            for cmd in replay.poll_until(frame as f64 / logic_hz as f64) {
+                match cmd {
+                    MidiCommand::SelectCamera(_) | MidiCommand::SavePattern | MidiCommand::LoadPattern => {}
+                    cmd => engine.apply_command(cmd),
+                }
+            }
+        }
+        engine.update();
+        engine.feed_frame(video.next_frame());
+
+        let rgba = engine.render_to_rgba();
+        let path = args.output_dir.join(format!("frame_{:05}.png", frame));
+        image::save_buffer(&path, &rgba, width, height, image::ExtendedColorType::Rgba8)
+            .map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+        if (frame + 1) % logic_hz as u32 == 0 {
+            log::info!("{}/{} frames", frame + 1, frames);
+        }
+    }
+    log::info!("Wrote {} frames to {}", frames, args.output_dir.display());
+    Ok(())
+}
+
+/// Run live without a window or event loop, printing the output to the terminal
+/// every ASCII_PREVIEW_INTERVAL. The scene is rendered at the preview size, so
+/// each frame is a tiny draw and readback even on a Pi.
+fn run_ascii_preview(args: &Args) -> Result<(), String> {
+    let width = args.ascii_width.max(1);
+    let height = terminal::preview_height(width, args.window_width, args.window_height);
+    let mut renderer = pollster::block_on(Renderer::new_headless(width, height))?;
+    if let Some(path) = &args.effects {
+        load_effect_chain(&mut renderer, path);
+    }
+
+    let mut midi = open_midi(args);
+    let stdin = args.stdin_control.then(StdinControl::spawn);
+    let mut video = open_video(args);
+    let audio = open_audio(args);
+
+    let config_path = args.config.clone().or_else(config::default_path);
+    let state = initial_state(args, config_path.as_deref());
+    let mut engine = SpectralMesh::new(renderer, state, args.width, args.height);
+    if audio.is_some() {
+        engine.state.kick_ripples = !args.no_kick_ripples;
+    }
+    engine.audio = audio;
+    let pattern_path = recall_saved(&mut engine, args, config_path.as_deref());
+
+    log::info!("Terminal preview at {}x{}, Ctrl+C to quit", width, height);
+    terminal::clear();
+    let step = std::time::Duration::from_secs_f32(1.0 / args.logic_hz.max(1.0));
+    let mut next_tick = Instant::now();
+    let mut last_print: Option<Instant> = None;
+    loop {
+        let mut commands = midi.as_ref().map(MidiHandler::poll_all).unwrap_or_default();
+        if let Some(stdin) = &stdin {
+            commands.extend(stdin.poll_all());
+        }
+        for cmd in commands {
+            match cmd {
+                MidiCommand::SavePattern => save_pattern(&engine.state.p_lock, pattern_path.as_deref()),
+                MidiCommand::LoadPattern => load_pattern(&mut engine.state.p_lock, pattern_path.as_deref()),
+                MidiCommand::SelectCamera(_) => log::warn!("Camera switching needs the window, ignored"),
+                cmd => engine.apply_command(cmd),
+            }
+        }
+        engine.update();
+        if let Some(midi) = &mut midi {
+            midi.send_feedback(&engine.state);
+        }
+        engine.feed_frame(video.next_frame());
+
+        // Every frame is drawn so trails and bloom evolve as in the window
+        let pixels = engine.render_to_rgba();
+        if !last_print.is_some_and(|t| t.elapsed().as_secs_f32() < ASCII_PREVIEW_INTERVAL) {
+            last_print = Some(Instant::now());
+            terminal::print_frame(&pixels, width, height);
+        }
+
+        next_tick += step;
+        match next_tick.checked_duration_since(Instant::now()) {
+            Some(wait) => std::thread::sleep(wait),
+            // Running behind: drop the backlog rather than fast-forwarding
+            None => next_tick = Instant::now(),
+        }
+    }
+}
+
+fn solo_group(key: KeyCode) -> Option<Solo> {
+    match key {
+        KeyCode::KeyQ | KeyCode::KeyW | KeyCode::KeyE | KeyCode::KeyR => Some(Solo::Displacement),
+        KeyCode::KeyS | KeyCode::KeyX | KeyCode::KeyD | KeyCode::KeyC | KeyCode::KeyF | KeyCode::KeyV
+        | KeyCode::Digit6 | KeyCode::NumpadAdd | KeyCode::NumpadSubtract => Some(Solo::ZLfo),
+        KeyCode::KeyG | KeyCode::KeyB | KeyCode::KeyH | KeyCode::KeyN | KeyCode::KeyJ | KeyCode::KeyM
+        | KeyCode::Digit7 | KeyCode::Numpad4 | KeyCode::Numpad6 => Some(Solo::XLfo),
+        KeyCode::KeyK | KeyCode::Comma | KeyCode::KeyL | KeyCode::Period | KeyCode::Semicolon | KeyCode::Slash
+        | KeyCode::Digit8 | KeyCode::Numpad2 | KeyCode::Numpad8 => Some(Solo::YLfo),
+        KeyCode::KeyA | KeyCode::KeyZ | KeyCode::Digit1 | KeyCode::Digit3 | KeyCode::Digit5 => Some(Solo::Color),
+        KeyCode::Quote | KeyCode::Backslash => Some(Solo::Chroma),
+        KeyCode::PageUp | KeyCode::PageDown => Some(Solo::Feedback),
+        _ => None,
+    }
+}
+
+/// Monitor by index, falling back to the primary monitor if out of range
+fn select_monitor(window: &Window, index: usize) -> Option<MonitorHandle> {
+    window.available_monitors().nth(index).or_else(|| {
+        log::warn!("Monitor {} not found, using primary monitor", index);
+        window.primary_monitor()
+    })
+}
+
 fn list_all_devices() {
     println!("\n=== MIDI INPUT DEVICES ===");
     if let Ok(midi_in) = midir::MidiInput::new("list") {
@@ -465,18 +2169,61 @@ fn list_all_devices() {
         }
     }
 
+    println!("\n=== MIDI OUTPUT DEVICES ===");
+    if let Ok(midi_out) = midir::MidiOutput::new("list") {
+        let ports = midi_out.ports();
+        if ports.is_empty() {
+            println!("  No MIDI devices found");
+        } else {
+            for (i, port) in ports.iter().enumerate() {
+                let name = midi_out.port_name(port).unwrap_or_else(|_| "Unknown".to_string());
+                println!("  {}: {}", i, name);
+            }
+        }
+    }
+
     println!("\n=== VIDEO INPUT DEVICES ===");
     #[cfg(feature = "camera")]
     {
-        println!("  Available camera indices: 0-5");
-        println!("  Use --video <index> to select");
-        println!("  (Camera enumeration requires device access)");
+        let cameras = video::list_cameras();
+        if cameras.is_empty() {
+            println!("  No cameras found");
+        } else {
+            for (i, name) in cameras {
+                println!("  {}: {}", i, name);
+                match video::camera_resolutions(i) {
+                    Ok(resolutions) => {
+                        let modes: Vec<String> =
+                            resolutions.iter().map(|(w, h, fps)| format!("{}x{}@{}", w, h, fps)).collect();
+                        println!("     {}", modes.join(", "));
+                    }
+                    Err(e) => println!("     (formats unavailable: {})", e),
+                }
+            }
+        }
     }
     #[cfg(not(feature = "camera"))]
     {
         println!("  Camera support not compiled");
     }
 
+    println!("\n=== MONITORS ===");
+    match EventLoop::new() {
+        Ok(event_loop) => {
+            for (i, monitor) in event_loop.available_monitors().enumerate() {
+                let size = monitor.size();
+                println!(
+                    "  {}: {} ({}x{})",
+                    i,
+                    monitor.name().unwrap_or_else(|| "Unknown".to_string()),
+                    size.width,
+                    size.height
+                );
+            }
+        }
+        Err(e) => println!("  Could not query monitors: {}", e),
+    }
+
     println!("\n=== AUDIO INPUT DEVICES ===");
     let audio_devices = audio::list_audio_devices();
     if audio_devices.is_empty() {
@@ -491,17 +2238,51 @@ fn list_all_devices() {
 }
 
 fn main() {
-    env_logger::init();
-
     let args = Args::parse();
 
+    // Info by default so the console isn't silent (wgpu's own info is noisy, so only
+    // its warnings); RUST_LOG still works unless a flag is given
+    let mut logger = env_logger::Builder::from_env(
+        env_logger::Env::default().default_filter_or("info,wgpu_core=warn,wgpu_hal=warn,naga=warn"),
+    );
+    if args.quiet {
+        logger.filter_level(log::LevelFilter::Warn);
+    } else if args.verbose {
+        logger
+            .filter_level(log::LevelFilter::Info)
+            .filter_module("spectral_mesh", log::LevelFilter::Debug);
+    }
+    logger.init();
+
     if args.list_devices {
         list_all_devices();
         return;
     }
+    if args.dump_midi_map {
+        match serde_json::to_string_pretty(&default_midi_map()) {
+            Ok(json) => println!("{}", json),
+            Err(e) => log::error!("{}", e),
+        }
+        return;
+    }
 
     log::info!("Starting Spectral Mesh v5.0");
     log::info!("Rust/wgpu port - Cross-platform (macOS/Linux/Raspberry Pi)");
+    if let Some(frames) = args.render_frames {
+        if let Err(e) = render_offline(&args, frames) {
+            log::error!("{}", e);
+            std::process::exit(1);
+        }
+        return;
+    }
+    if args.ascii_preview {
+        if let Err(e) = run_ascii_preview(&args) {
+            log::error!("{}", e);
+            std::process::exit(1);
+        }
+        return;
+    }
+
     log::info!("Video: {}x{}, MIDI port: {}", args.width, args.height, args.midi);
 
     let event_loop = EventLoop::new().unwrap();
@@ -509,14 +2290,22 @@ fn main() {
 
     let window = std::sync::Arc::new(
         WindowBuilder::new()
-            .with_title("Spectral Mesh v5.0 (Rust/wgpu)")
+            .with_title(WINDOW_TITLE)
             .with_inner_size(winit::dpi::LogicalSize::new(args.window_width, args.window_height))
+            .with_transparent(args.transparent)
+            .with_decorations(!args.transparent)
             .build(&event_loop)
             .unwrap(),
     );
 
-    let renderer = pollster::block_on(Renderer::new(window.clone()));
-    let mut app = App::new(renderer, &args);
+    let renderer = match pollster::block_on(Renderer::new(window.clone())) {
+        Ok(renderer) => renderer,
+        Err(e) => {
+            log::error!("{}", e);
+            std::process::exit(1);
+        }
+    };
+    let mut app = App::new(window.clone(), renderer, &args);
 
     event_loop
         .run(move |event, elwt| {
@@ -528,6 +2317,13 @@ fn main() {
                     WindowEvent::Resized(physical_size) => {
                         app.resize(physical_size);
                     }
+                    WindowEvent::ScaleFactorChanged { scale_factor, .. } => {
+                        // Moved to a monitor with another DPI: the window keeps its
+                        // logical size, so the surface needs the new physical one
+                        log::info!("Scale factor changed to {:.2}", scale_factor);
+                        app.sync_size();
+                        window.request_redraw();
+                    }
                     WindowEvent::KeyboardInput {
                         event:
                             KeyEvent {
@@ -537,17 +2333,37 @@ fn main() {
                             },
                         ..
                     } => {
-                        // ESC disabled - use Ctrl+C or close window to quit
+                        // No quit key unless --quit-key is given - use Ctrl+C or close window
                         app.handle_keyboard(key, state == ElementState::Pressed);
+                        if app.quit_requested {
+                            elwt.exit();
+                        }
+                    }
+                    WindowEvent::ModifiersChanged(modifiers) => {
+                        app.set_modifiers(modifiers.state());
+                    }
+                    WindowEvent::CursorMoved { position, .. } => {
+                        app.cursor_moved(position.x, position.y);
+                    }
+                    WindowEvent::MouseInput { state, button, .. } => {
+                        app.handle_mouse(button, state == ElementState::Pressed);
                     }
                     WindowEvent::RedrawRequested => {
-                        app.update();
+                        app.sync_size();
+                        app.advance();
                         app.render();
                     }
                     _ => {}
                 },
                 Event::AboutToWait => {
-                    window.request_redraw();
+                    app.update_schedule();
+                    if app.paused() {
+                        // The last frame drawn was black; sleep instead of redrawing it
+                        elwt.set_control_flow(ControlFlow::WaitUntil(Instant::now() + STANDBY_POLL));
+                    } else {
+                        elwt.set_control_flow(ControlFlow::Poll);
+                        window.request_redraw();
+                    }
                 }
                 _ => {}
             }
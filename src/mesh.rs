@@ -1,10 +1,16 @@
 use bytemuck::{Pod, Zeroable};
 
+/// Widest stroke the line meshes expand to, in video pixels
+pub const MAX_STROKE_WEIGHT: f32 = 8.0;
+
 #[repr(C)]
 #[derive(Copy, Clone, Debug, Pod, Zeroable)]
 pub struct Vertex {
     pub position: [f32; 3],
     pub tex_coord: [f32; 2],
+    /// Across a smooth line: 0 on the center line, +-1 on the stroke's edges.
+    /// 0 everywhere else, which draws fully covered
+    pub edge: f32,
 }
 
 impl Vertex {
@@ -23,6 +29,11 @@ impl Vertex {
                     shader_location: 1,
                     format: wgpu::VertexFormat::Float32x2,
                 },
+                wgpu::VertexAttribute {
+                    offset: std::mem::size_of::<[f32; 5]>() as wgpu::BufferAddress,
+                    shader_location: 2,
+                    format: wgpu::VertexFormat::Float32,
+                },
             ],
         }
     }
@@ -36,9 +47,55 @@ pub enum MeshType {
     Grid,
 }
 
+impl MeshType {
+    const ALL: [MeshType; 4] = [
+        MeshType::Triangles,
+        MeshType::HorizontalLines,
+        MeshType::VerticalLines,
+        MeshType::Grid,
+    ];
+
+    /// Next type in declaration order, wrapping around
+    pub fn next(self) -> Self {
+        Self::ALL[(self as usize + 1) % Self::ALL.len()]
+    }
+
+    /// Previous type, wrapping around
+    pub fn previous(self) -> Self {
+        Self::ALL[(self as usize + Self::ALL.len() - 1) % Self::ALL.len()]
+    }
+
+    /// Cells along each side of the mesh for a grid density (line meshes
+    /// double it, see `horizontal_line_mesh`)
+    pub fn cells_per_side(self, grid_size: u32) -> u32 {
+        match self {
+            MeshType::Triangles => grid_size,
+            MeshType::HorizontalLines | MeshType::VerticalLines | MeshType::Grid => grid_size * 2,
+        }
+    }
+}
+
+/// How line meshes are drawn
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, clap::ValueEnum, serde::Serialize, serde::Deserialize)]
+pub enum LineRender {
+    /// Aliased: 1px lines, or hard-edged quads for thicker strokes
+    #[default]
+    Hard,
+    /// Quads with a soft edge falloff in the fragment shader, anti-aliased at
+    /// any width without multisampling
+    Smooth,
+}
+
+/// Extra width around a smooth stroke for its falloff, in video pixels
+const SMOOTH_PADDING: f32 = 1.0;
+
 pub struct Mesh {
     pub vertices: Vec<Vertex>,
     pub mesh_type: MeshType,
+    /// Lines expanded into triangle quads by `with_stroke_weight`
+    pub thick_lines: bool,
+    /// Quads carry an anti-aliasing `edge` (`with_smooth_stroke`)
+    pub smooth_edges: bool,
 }
 
 impl Mesh {
@@ -62,28 +119,34 @@ impl Mesh {
                 vertices.push(Vertex {
                     position: [x0, y0, 0.0],
                     tex_coord: [tex_x0, tex_y0],
+                    edge: 0.0,
                 });
                 vertices.push(Vertex {
                     position: [x1, y0, 0.0],
                     tex_coord: [tex_x1, tex_y0],
+                    edge: 0.0,
                 });
                 vertices.push(Vertex {
                     position: [x1, y1, 0.0],
                     tex_coord: [tex_x1, tex_y1],
+                    edge: 0.0,
                 });
 
                 // Second triangle
                 vertices.push(Vertex {
                     position: [x1, y1, 0.0],
                     tex_coord: [tex_x1, tex_y1],
+                    edge: 0.0,
                 });
                 vertices.push(Vertex {
                     position: [x0, y1, 0.0],
                     tex_coord: [tex_x0, tex_y1],
+                    edge: 0.0,
                 });
                 vertices.push(Vertex {
                     position: [x0, y0, 0.0],
                     tex_coord: [tex_x0, tex_y0],
+                    edge: 0.0,
                 });
             }
         }
@@ -91,6 +154,8 @@ impl Mesh {
         Self {
             vertices,
             mesh_type: MeshType::Triangles,
+            thick_lines: false,
+            smooth_edges: false,
         }
     }
 
@@ -112,10 +177,12 @@ impl Mesh {
                 vertices.push(Vertex {
                     position: [x0, y0, 0.0],
                     tex_coord: [tex_x0, tex_y0],
+                    edge: 0.0,
                 });
                 vertices.push(Vertex {
                     position: [x1, y0, 0.0],
                     tex_coord: [tex_x1, tex_y0],
+                    edge: 0.0,
                 });
             }
         }
@@ -123,6 +190,8 @@ impl Mesh {
         Self {
             vertices,
             mesh_type: MeshType::HorizontalLines,
+            thick_lines: false,
+            smooth_edges: false,
         }
     }
 
@@ -144,10 +213,12 @@ impl Mesh {
                 vertices.push(Vertex {
                     position: [x0, y0, 0.0],
                     tex_coord: [tex_x0, tex_y0],
+                    edge: 0.0,
                 });
                 vertices.push(Vertex {
                     position: [x0, y1, 0.0],
                     tex_coord: [tex_x0, tex_y1],
+                    edge: 0.0,
                 });
             }
         }
@@ -155,6 +226,8 @@ impl Mesh {
         Self {
             vertices,
             mesh_type: MeshType::VerticalLines,
+            thick_lines: false,
+            smooth_edges: false,
         }
     }
 
@@ -178,10 +251,12 @@ impl Mesh {
                 vertices.push(Vertex {
                     position: [x0, y0, 0.0],
                     tex_coord: [tex_x0, tex_y0],
+                    edge: 0.0,
                 });
                 vertices.push(Vertex {
                     position: [x1, y0, 0.0],
                     tex_coord: [tex_x1, tex_y0],
+                    edge: 0.0,
                 });
             }
         }
@@ -200,10 +275,12 @@ impl Mesh {
                 vertices.push(Vertex {
                     position: [x0, y0, 0.0],
                     tex_coord: [tex_x0, tex_y0],
+                    edge: 0.0,
                 });
                 vertices.push(Vertex {
                     position: [x0, y1, 0.0],
                     tex_coord: [tex_x0, tex_y1],
+                    edge: 0.0,
                 });
             }
         }
@@ -211,12 +288,82 @@ impl Mesh {
         Self {
             vertices,
             mesh_type: MeshType::Grid,
+            thick_lines: false,
+            smooth_edges: false,
+        }
+    }
+
+    /// Expand a line mesh into quads `weight` video pixels wide, since LineList is
+    /// always drawn 1px. Both edges of a quad keep the tex coords of their line
+    /// endpoint, so the video maps and displaces exactly as on the thin line.
+    /// Triangle meshes and weights of 1 or less are returned unchanged.
+    pub fn with_stroke_weight(self, weight: f32) -> Self {
+        if self.mesh_type == MeshType::Triangles || weight <= 1.0 {
+            return self;
+        }
+        self.expand_lines(weight.min(MAX_STROKE_WEIGHT) / 2.0, 0.0)
+    }
+
+    /// Expand lines into quads padded for an anti-aliased edge, whatever the
+    /// weight. `edge` runs across each quad so the fragment shader can fade
+    /// coverage by the distance from the stroke.
+    pub fn with_smooth_stroke(self, weight: f32) -> Self {
+        if self.mesh_type == MeshType::Triangles {
+            return self;
+        }
+        self.expand_lines(weight.clamp(1.0, MAX_STROKE_WEIGHT) / 2.0, SMOOTH_PADDING)
+    }
+
+    /// Lines and a stroke weight as the line render mode draws them
+    pub fn with_stroke(self, weight: f32, render: LineRender) -> Self {
+        match render {
+            LineRender::Hard => self.with_stroke_weight(weight),
+            LineRender::Smooth => self.with_smooth_stroke(weight),
+        }
+    }
+
+    /// Quads reaching `half + padding` to each side of every line; `edge` is
+    /// +-1 at `half`
+    fn expand_lines(self, half: f32, padding: f32) -> Self {
+        let reach = half + padding;
+        let edge = if padding > 0.0 { reach / half } else { 0.0 };
+
+        let mut vertices = Vec::with_capacity(self.vertices.len() * 3);
+        for line in self.vertices.chunks_exact(2) {
+            let (a, b) = (line[0], line[1]);
+            let dx = b.position[0] - a.position[0];
+            let dy = b.position[1] - a.position[1];
+            let len = (dx * dx + dy * dy).sqrt().max(f32::EPSILON);
+            // Normal to the segment, half a stroke (plus padding) long
+            let (nx, ny) = (-dy / len * reach, dx / len * reach);
+            let offset = |v: Vertex, side: f32| Vertex {
+                position: [v.position[0] + nx * side, v.position[1] + ny * side, v.position[2]],
+                tex_coord: v.tex_coord,
+                edge: edge * side,
+            };
+
+            vertices.extend_from_slice(&[
+                offset(a, 1.0),
+                offset(a, -1.0),
+                offset(b, -1.0),
+                offset(b, -1.0),
+                offset(b, 1.0),
+                offset(a, 1.0),
+            ]);
+        }
+
+        Self {
+            vertices,
+            mesh_type: self.mesh_type,
+            thick_lines: true,
+            smooth_edges: padding > 0.0,
         }
     }
 
     pub fn primitive_topology(&self) -> wgpu::PrimitiveTopology {
         match self.mesh_type {
             MeshType::Triangles => wgpu::PrimitiveTopology::TriangleList,
+            _ if self.thick_lines => wgpu::PrimitiveTopology::TriangleList,
             MeshType::HorizontalLines | MeshType::VerticalLines | MeshType::Grid => wgpu::PrimitiveTopology::LineList,
         }
     }
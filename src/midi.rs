@@ -1,12 +1,15 @@
-use midir::{Ignore, MidiInput, MidiInputConnection};
+use crate::mesh::LineRender;
+use crate::params::ParamId;
+use crate::state::{AppState, FlashMode, LfoShape};
+use midir::{Ignore, MidiInput, MidiInputConnection, MidiOutput, MidiOutputConnection};
+use std::collections::BTreeMap;
+use std::f32::consts::PI;
+use std::path::Path;
 use std::sync::mpsc::{channel, Receiver, Sender};
 
-const MIDI_MAGIC: f32 = 63.50;
-const CONTROL_THRESHOLD: f32 = 0.04;
-
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub enum MidiCommand {
-    // Continuous controls (knobs/faders)
+    // Continuous controls (knobs/faders), normalized 0-1 and scaled by the ParamSpec table
     LumaKeyLevel(f32),        // CC 16
     DisplaceX(f32),           // CC 17
     DisplaceY(f32),           // CC 18
@@ -15,6 +18,37 @@ pub enum MidiCommand {
     YFrequency(f32),          // CC 21
     Zoom(f32),                // CC 22
     Scale(f32),               // CC 23
+    VideoMix(f32),            // CC 24
+    ChromaSplit(f32),         // CC 25
+    FeedbackDecay(f32),       // CC 26
+    ClearColor(usize, f32),   // CC 27-29 (red, green, blue)
+    ZSource(usize),           // CC 30 - displacement source, knob split into 5 ranges
+    XSource(usize),           // CC 31
+    YSource(usize),           // CC 32
+    AutoCenterSpeed(f32),     // CC 33
+    AudioStrokeGain(f32),     // CC 47
+    Fov(f32),                 // CC 48 - perspective field of view
+    NoiseTimeScale(f32),      // CC 49 - noise evolution speed, all axes
+    RippleShape(usize),       // CC 56 - knob split into 4 ranges
+    InputExposure(f32),       // CC 72 - source exposure, before the effects
+    InputGamma(f32),          // CC 73 - source gamma
+    MeshSmoothing(f32),       // CC 74 - filter LFO detail finer than the mesh
+    XLfoBias(f32),            // CC 75 - center the X LFO oscillates around
+    YLfoBias(f32),            // CC 76
+    ZLfoBias(f32),            // CC 77
+    AudioZoomAmount(f32),     // CC 78 - breathing zoom depth
+    MasterIntensity(f32),     // CC 79 - all distortion at once
+    NoiseResolution(usize, f32), // CC 82-84 (X, Y, Z) - noise feature size
+    NoiseAmp(usize, f32),     // CC 85-87 (X, Y, Z) - noise displacement gain
+    NoiseKind(usize, usize),  // CC 88-90 (X, Y, Z) - noise function, knob split into 5 ranges
+    BloomIntensity(f32),      // CC 91 - glow around bright areas (0 = off)
+    BloomThreshold(f32),      // CC 92 - brightness the glow starts at
+    AudioBloomGain(f32),      // CC 93 - extra glow at full audio level
+    AudioChromaGain(f32),     // CC 95 - extra RGB split at full audio level
+    Param(ParamId, f32),      // Any parameter by id (stdin control)
+    Control(ParamId, f32),    // Any parameter by id from a --midi-map CC, picked up like the default CCs
+    SpawnRipple(f32),         // Ripple at a random spot with this intensity (--midi-map, e.g. a pad's velocity)
+    ExtraLfoShape(usize, LfoShape), // Stacked LFO index (state::EXTRA_LFOS order), shape
 
     CenterX(f32),             // CC 120
     CenterY(f32),             // CC 121
@@ -29,11 +63,14 @@ pub enum MidiCommand {
     RecordStart,              // CC 60 value 127
     RecordStop,               // CC 60 value 0
     Reset,                    // CC 58 value 127
+    Quantize(Option<usize>, usize), // p_lock slot (None = all), divisions (keyboard F4, text control)
+    SavePattern,              // CC 80 value 127 - write the p_lock pattern (handled by the app)
+    LoadPattern,              // CC 81 value 127 - recall it (handled by the app)
 
-    // LFO shapes (0=sine, 1=square, 2=saw, 3=noise)
-    ZLfoShape(i32),
-    XLfoShape(i32),
-    YLfoShape(i32),
+    // LFO shapes
+    ZLfoShape(LfoShape),
+    XLfoShape(LfoShape),
+    YLfoShape(LfoShape),
 
     // Modulation switches
     ZRingMod(bool),
@@ -48,12 +85,19 @@ pub enum MidiCommand {
     SetHorizontalLines,
     SetVerticalLines,
     SetWireframe,
+    SetGridMesh,              // Horizontal + vertical lines (keyboard =, text control)
+    SetLineRender(LineRender), // Hard or anti-aliased lines (text control)
+    SetFlash(FlashMode),       // Beat-synced strobe (keyboard Space, text control)
+    SelectCamera(Option<u32>), // Main camera device, None = next (keyboard Enter, text control; handled by the app)
 
     // Visual effects
     Greyscale(bool),
     Invert(bool),
     BrightSwitch(bool),
     StrokeWeight(f32),
+    FreezeNoise(bool),        // Keep the current noise as static maps (keyboard 4, text control)
+    Bloom(bool),              // CC 94 - bypass switch for the glow (keyboard Alt+B)
+    ChromaFollow(bool),       // CC 96 - RGB split along the mesh displacement (keyboard Shift+')
 
     // Mode switches
     GlobalXDisplace(bool),
@@ -68,16 +112,354 @@ pub enum MidiCommand {
     ZFreqZero(bool),
     XFreqZero(bool),
     YFreqZero(bool),
+
+    // MIDI clock (system real-time)
+    Clock,                    // 0xF8, 24 per quarter note
+    ClockStart,               // 0xFA
+    ClockContinue,            // 0xFB
+    ClockStop,                // 0xFC
+}
+
+/// How a CC's 0-127 value reaches a mapped command
+#[derive(Clone, Copy, Debug, Default, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MidiScale {
+    /// 0 to 1
+    #[default]
+    Normalized,
+    /// -1 to 1, centered on value 64
+    Bipolar,
+}
+
+impl MidiScale {
+    fn apply(self, value: u8) -> f32 {
+        let normalized = value as f32 / 127.0;
+        match self {
+            MidiScale::Normalized => normalized,
+            MidiScale::Bipolar => normalized * 2.0 - 1.0,
+        }
+    }
+
+    /// CC value that `apply` maps to `v`
+    fn value(self, v: f32) -> u8 {
+        let normalized = match self {
+            MidiScale::Normalized => v,
+            MidiScale::Bipolar => (v + 1.0) * 0.5,
+        };
+        (normalized.clamp(0.0, 1.0) * 127.0).round() as u8
+    }
+}
+
+/// What a CC controls: a parameter name (as for `--stdin-control`) or one of
+/// the switch and button commands of `binding_command`
+#[derive(Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct MidiBinding {
+    pub command: String,
+    #[serde(default)]
+    pub scale: MidiScale,
+}
+
+/// Built-in CC assignments (Faderfox MX12 layout), all normalized
+const DEFAULT_MAP: [(u8, &str); 83] = [
+    (16, "luma_key_level"),
+    (17, "displace_x"),
+    (18, "displace_y"),
+    (19, "z_frequency"),
+    (20, "x_frequency"),
+    (21, "y_frequency"),
+    (22, "zoom"),
+    (23, "scale"),
+    (24, "video_mix"),
+    (25, "chroma_split"),
+    (26, "feedback_decay"),
+    (27, "background_red"),
+    (28, "background_green"),
+    (29, "background_blue"),
+    (30, "z_source"),
+    (31, "x_source"),
+    (32, "y_source"),
+    (33, "auto_center_speed"),
+    (34, "z_freq_zero"),
+    (35, "z_lfo_square"),
+    (36, "x_freq_zero"),
+    (37, "x_lfo_square"),
+    (38, "y_freq_zero"),
+    (39, "y_lfo_square"),
+    (41, "mesh_wireframe"),
+    (42, "mesh_vertical"),
+    (43, "mesh_triangles"),
+    (44, "mesh_horizontal"),
+    (45, "stroke_weight"),
+    (46, "greyscale"),
+    (47, "audio_stroke_gain"),
+    (48, "fov"),
+    (49, "noise_time_scale"),
+    (50, "z_ringmod"),
+    (51, "z_lfo_saw"),
+    (52, "x_ringmod"),
+    (53, "x_lfo_saw"),
+    (54, "y_ringmod"),
+    (55, "y_lfo_saw"),
+    (56, "ripple_shape"),
+    (58, "reset"),
+    (59, "invert"),
+    (60, "record"),
+    (61, "bright_switch"),
+    (66, "z_phasemod"),
+    (67, "z_lfo_noise"),
+    (68, "x_phasemod"),
+    (69, "x_lfo_noise"),
+    (70, "y_phasemod"),
+    (71, "y_lfo_noise"),
+    (72, "input_exposure"),
+    (73, "input_gamma"),
+    (74, "mesh_smoothing"),
+    (75, "x_lfo_bias"),
+    (76, "y_lfo_bias"),
+    (77, "z_lfo_bias"),
+    (78, "audio_zoom_amount"),
+    (79, "master_intensity"),
+    (80, "save_pattern"),
+    (81, "load_pattern"),
+    (82, "x_noise_resolution"),
+    (83, "y_noise_resolution"),
+    (84, "z_noise_resolution"),
+    (85, "x_noise_amp"),
+    (86, "y_noise_amp"),
+    (87, "z_noise_amp"),
+    (88, "x_noise_kind"),
+    (89, "y_noise_kind"),
+    (90, "z_noise_kind"),
+    (91, "bloom_intensity"),
+    (92, "bloom_threshold"),
+    (93, "audio_bloom_gain"),
+    (94, "bloom"),
+    (95, "audio_chroma_gain"),
+    (96, "chroma_follow"),
+    (120, "center_x"),
+    (121, "center_y"),
+    (122, "z_lfo_rate"),
+    (123, "z_lfo_amp"),
+    (124, "x_lfo_rate"),
+    (125, "x_lfo_amp"),
+    (126, "y_lfo_rate"),
+    (127, "y_lfo_amp"),
+];
+
+/// Output-only binding: a CC bound to it sends the p_lock loop position on
+/// `--midi-out` (e.g. for an LED ring) and ignores input
+const LOOP_POSITION: &str = "loop_position";
+
+/// `--midi-map` bindings by CC and by note number
+#[derive(Clone, Debug, Default, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct MidiMap {
+    #[serde(default)]
+    pub cc: BTreeMap<u8, MidiBinding>,
+    /// Notes have no default assignment
+    #[serde(default)]
+    pub note: BTreeMap<u8, MidiBinding>,
+}
+
+/// The built-in CC assignments, as a `--midi-map` file would give them
+pub fn default_midi_map() -> MidiMap {
+    let cc = DEFAULT_MAP
+        .iter()
+        .map(|&(control, command)| {
+            let binding = MidiBinding {
+                command: command.to_string(),
+                scale: MidiScale::Normalized,
+            };
+            (control, binding)
+        })
+        .collect();
+    MidiMap {
+        cc,
+        note: BTreeMap::new(),
+    }
+}
+
+/// Read a `--midi-map` file: CC and note numbers to bindings, e.g.
+/// `{ "cc": { "1": { "command": "rotate_z", "scale": "bipolar" } },
+/// "note": { "36": { "command": "ripple" } } }`.
+/// CCs it doesn't list keep their default assignment.
+pub fn load_midi_map(path: &Path) -> Result<MidiMap, String> {
+    let text = std::fs::read_to_string(path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    let map: MidiMap = serde_json::from_str(&text).map_err(|e| format!("{}: {}", path.display(), e))?;
+    let entries = map.cc.iter().map(|entry| ("CC", entry)).chain(map.note.iter().map(|entry| ("note", entry)));
+    for (kind, (number, binding)) in entries {
+        if *number > 127 {
+            return Err(format!("{}: {} {} is out of range (0-127)", path.display(), kind, number));
+        }
+        // Every command fires when on; buttons only then
+        if binding.command != LOOP_POSITION && binding_command(&binding.command, 1.0, true).is_none() {
+            return Err(format!(
+                "{}: {} {}: unknown command '{}' (see --dump-midi-map)",
+                path.display(),
+                kind,
+                number,
+                binding.command
+            ));
+        }
+    }
+    Ok(map)
+}
+
+/// Command for `name` at the scaled control value `v`. Parameters follow `v`;
+/// switches follow `on` and buttons fire when it's set (CC value 127, or a
+/// note held down).
+fn binding_command(name: &str, v: f32, on: bool) -> Option<MidiCommand> {
+    // Knob split into `count` equal ranges
+    let index = |count: usize| (v.clamp(0.0, 1.0) * (count - 1) as f32).round() as usize;
+
+    if let Some(id) = ParamId::from_name(name) {
+        return Some(param_command(id, v));
+    }
+    match name {
+        "z_source" => Some(MidiCommand::ZSource(index(5))),
+        "x_source" => Some(MidiCommand::XSource(index(5))),
+        "y_source" => Some(MidiCommand::YSource(index(5))),
+        "ripple_shape" => Some(MidiCommand::RippleShape(index(4))),
+        "x_noise_kind" => Some(MidiCommand::NoiseKind(0, index(5))),
+        "y_noise_kind" => Some(MidiCommand::NoiseKind(1, index(5))),
+        "z_noise_kind" => Some(MidiCommand::NoiseKind(2, index(5))),
+        "z_lfo_shape" => LfoShape::from_i32(index(4) as i32).map(MidiCommand::ZLfoShape),
+        "x_lfo_shape" => LfoShape::from_i32(index(4) as i32).map(MidiCommand::XLfoShape),
+        "y_lfo_shape" => LfoShape::from_i32(index(4) as i32).map(MidiCommand::YLfoShape),
+        "rotate_x" => Some(MidiCommand::RotateX(v * PI)),
+        "rotate_y" => Some(MidiCommand::RotateY(v * PI)),
+        "rotate_z" => Some(MidiCommand::RotateZ(v * PI)),
+
+        // Shape while held, sine when released
+        "z_lfo_square" => Some(MidiCommand::ZLfoShape(if on { LfoShape::Square } else { LfoShape::Sine })),
+        "z_lfo_saw" => Some(MidiCommand::ZLfoShape(if on { LfoShape::Triangle } else { LfoShape::Sine })),
+        "z_lfo_noise" => Some(MidiCommand::ZLfoShape(if on { LfoShape::Noise } else { LfoShape::Sine })),
+        "x_lfo_square" => Some(MidiCommand::XLfoShape(if on { LfoShape::Square } else { LfoShape::Sine })),
+        "x_lfo_saw" => Some(MidiCommand::XLfoShape(if on { LfoShape::Triangle } else { LfoShape::Sine })),
+        "x_lfo_noise" => Some(MidiCommand::XLfoShape(if on { LfoShape::Noise } else { LfoShape::Sine })),
+        "y_lfo_square" => Some(MidiCommand::YLfoShape(if on { LfoShape::Square } else { LfoShape::Sine })),
+        "y_lfo_saw" => Some(MidiCommand::YLfoShape(if on { LfoShape::Triangle } else { LfoShape::Sine })),
+        "y_lfo_noise" => Some(MidiCommand::YLfoShape(if on { LfoShape::Noise } else { LfoShape::Sine })),
+
+        // Switches
+        "z_freq_zero" => Some(MidiCommand::ZFreqZero(on)),
+        "x_freq_zero" => Some(MidiCommand::XFreqZero(on)),
+        "y_freq_zero" => Some(MidiCommand::YFreqZero(on)),
+        "z_ringmod" => Some(MidiCommand::ZRingMod(on)),
+        "x_ringmod" => Some(MidiCommand::XRingMod(on)),
+        "y_ringmod" => Some(MidiCommand::YRingMod(on)),
+        "z_phasemod" => Some(MidiCommand::ZPhaseMod(on)),
+        "x_phasemod" => Some(MidiCommand::XPhaseMod(on)),
+        "y_phasemod" => Some(MidiCommand::YPhaseMod(on)),
+        "greyscale" => Some(MidiCommand::Greyscale(on)),
+        "invert" => Some(MidiCommand::Invert(on)),
+        "bright_switch" => Some(MidiCommand::BrightSwitch(on)),
+        "freeze_noise" => Some(MidiCommand::FreezeNoise(on)),
+        "bloom" => Some(MidiCommand::Bloom(on)),
+        "chroma_follow" => Some(MidiCommand::ChromaFollow(on)),
+        "record" => Some(if on { MidiCommand::RecordStart } else { MidiCommand::RecordStop }),
+
+        // Buttons
+        "reset" => on.then_some(MidiCommand::Reset),
+        "save_pattern" => on.then_some(MidiCommand::SavePattern),
+        "load_pattern" => on.then_some(MidiCommand::LoadPattern),
+        "mesh_triangles" => on.then_some(MidiCommand::SetTriangleMesh),
+        "mesh_grid" => on.then_some(MidiCommand::SetGridMesh),
+        "mesh_wireframe" => on.then_some(MidiCommand::SetWireframe),
+        "mesh_horizontal" => on.then_some(MidiCommand::SetHorizontalLines),
+        "mesh_vertical" => on.then_some(MidiCommand::SetVerticalLines),
+        "camera_next" => on.then_some(MidiCommand::SelectCamera(None)),
+        "ripple" => on.then_some(MidiCommand::SpawnRipple(v)),
+        _ => None,
+    }
+}
+
+/// Parameters with a dedicated command keep it, so recorded sessions read the
+/// same whichever map produced them
+fn param_command(id: ParamId, v: f32) -> MidiCommand {
+    match id {
+        ParamId::LumaKeyLevel => MidiCommand::LumaKeyLevel(v),
+        ParamId::DisplaceX => MidiCommand::DisplaceX(v),
+        ParamId::DisplaceY => MidiCommand::DisplaceY(v),
+        ParamId::ZFrequency => MidiCommand::ZFrequency(v),
+        ParamId::XFrequency => MidiCommand::XFrequency(v),
+        ParamId::YFrequency => MidiCommand::YFrequency(v),
+        ParamId::Zoom => MidiCommand::Zoom(v),
+        ParamId::Scale => MidiCommand::Scale(v),
+        ParamId::CenterX => MidiCommand::CenterX(v),
+        ParamId::CenterY => MidiCommand::CenterY(v),
+        ParamId::ZLfoRate => MidiCommand::ZLfoArg(v),
+        ParamId::ZLfoAmp => MidiCommand::ZLfoAmp(v),
+        ParamId::XLfoRate => MidiCommand::XLfoArg(v),
+        ParamId::XLfoAmp => MidiCommand::XLfoAmp(v),
+        ParamId::YLfoRate => MidiCommand::YLfoArg(v),
+        ParamId::YLfoAmp => MidiCommand::YLfoAmp(v),
+        ParamId::VideoMix => MidiCommand::VideoMix(v),
+        ParamId::ChromaSplit => MidiCommand::ChromaSplit(v),
+        ParamId::FeedbackDecay => MidiCommand::FeedbackDecay(v),
+        ParamId::BackgroundRed => MidiCommand::ClearColor(0, v),
+        ParamId::BackgroundGreen => MidiCommand::ClearColor(1, v),
+        ParamId::BackgroundBlue => MidiCommand::ClearColor(2, v),
+        ParamId::StrokeWeight => MidiCommand::StrokeWeight(v),
+        ParamId::AudioStrokeGain => MidiCommand::AudioStrokeGain(v),
+        ParamId::AutoCenterSpeed => MidiCommand::AutoCenterSpeed(v),
+        ParamId::Fov => MidiCommand::Fov(v),
+        ParamId::NoiseTimeScale => MidiCommand::NoiseTimeScale(v),
+        ParamId::InputExposure => MidiCommand::InputExposure(v),
+        ParamId::InputGamma => MidiCommand::InputGamma(v),
+        ParamId::MeshSmoothing => MidiCommand::MeshSmoothing(v),
+        ParamId::XLfoBias => MidiCommand::XLfoBias(v),
+        ParamId::YLfoBias => MidiCommand::YLfoBias(v),
+        ParamId::ZLfoBias => MidiCommand::ZLfoBias(v),
+        ParamId::AudioZoomAmount => MidiCommand::AudioZoomAmount(v),
+        ParamId::MasterIntensity => MidiCommand::MasterIntensity(v),
+        ParamId::XNoiseResolution => MidiCommand::NoiseResolution(0, v),
+        ParamId::YNoiseResolution => MidiCommand::NoiseResolution(1, v),
+        ParamId::ZNoiseResolution => MidiCommand::NoiseResolution(2, v),
+        ParamId::XNoiseAmp => MidiCommand::NoiseAmp(0, v),
+        ParamId::YNoiseAmp => MidiCommand::NoiseAmp(1, v),
+        ParamId::ZNoiseAmp => MidiCommand::NoiseAmp(2, v),
+        ParamId::BloomIntensity => MidiCommand::BloomIntensity(v),
+        ParamId::BloomThreshold => MidiCommand::BloomThreshold(v),
+        ParamId::AudioBloomGain => MidiCommand::AudioBloomGain(v),
+        ParamId::AudioChromaGain => MidiCommand::AudioChromaGain(v),
+        _ => MidiCommand::Control(id, v),
+    }
 }
 
 pub struct MidiHandler {
     #[allow(dead_code)]
     connection: Option<MidiInputConnection<()>>,
     receiver: Receiver<MidiCommand>,
+    feedback: Option<MidiFeedback>,
+}
+
+/// What a feedback CC reports
+#[derive(Clone, Copy)]
+enum FeedbackSource {
+    Param(ParamId),
+    /// How far through the p_lock pattern playback is
+    LoopPosition,
+}
+
+/// Parameter values echoed to a controller's LED rings or motor faders
+struct MidiFeedback {
+    connection: MidiOutputConnection,
+    /// CC, source, scale and inversion of every CC bound to a parameter or
+    /// the loop position
+    controls: Vec<(u8, FeedbackSource, MidiScale, bool)>,
+    /// Last value sent on each CC, so only changes go out
+    sent: [Option<u8>; 128],
 }
 
 impl MidiHandler {
-    pub fn new(port_index: usize) -> Result<Self, String> {
+    /// Connect to an input port. CCs listed in `inverted` have their value
+    /// flipped (127 - value) before mapping, for controls that read reversed.
+    /// `map` (from `load_midi_map`) takes precedence over the default CCs.
+    pub fn new(
+        port_index: usize,
+        inverted: &[u8],
+        map: &MidiMap,
+    ) -> Result<Self, String> {
         let midi_in = MidiInput::new("spectral_mesh")
             .map_err(|e| format!("Failed to create MIDI input: {}", e))?;
 
@@ -88,6 +470,7 @@ impl MidiHandler {
             return Ok(Self {
                 connection: None,
                 receiver,
+                feedback: None,
             });
         }
 
@@ -109,6 +492,8 @@ impl MidiHandler {
         log::info!("Connecting to MIDI port: {}", port_name);
 
         let (sender, receiver) = channel::<MidiCommand>();
+        let inverted = inverted.to_vec();
+        let map = map.clone();
 
         let mut midi_in = MidiInput::new("spectral_mesh_handler")
             .map_err(|e| format!("Failed to create MIDI handler: {}", e))?;
@@ -119,8 +504,8 @@ impl MidiHandler {
                 port,
                 "spectral_mesh_input",
                 move |_stamp, message, _| {
-                    if message.len() >= 3 {
-                        Self::process_message(message, &sender);
+                    if !message.is_empty() {
+                        Self::process_message(message, &inverted, &map, &sender);
                     }
                 },
                 (),
@@ -130,124 +515,174 @@ impl MidiHandler {
         Ok(Self {
             connection: Some(connection),
             receiver,
+            feedback: None,
+        })
+    }
+
+    /// Create an input port called `name` that other software (a DAW, a
+    /// software controller) can send to, instead of connecting to hardware.
+    /// Available with ALSA, JACK and CoreMIDI; not on Windows.
+    #[cfg(unix)]
+    pub fn new_virtual(
+        name: &str,
+        inverted: &[u8],
+        map: &MidiMap,
+    ) -> Result<Self, String> {
+        use midir::os::unix::VirtualInput;
+
+        let (sender, receiver) = channel::<MidiCommand>();
+        let inverted = inverted.to_vec();
+        let map = map.clone();
+
+        let mut midi_in = MidiInput::new("spectral_mesh")
+            .map_err(|e| format!("Failed to create MIDI input: {}", e))?;
+        midi_in.ignore(Ignore::None);
+
+        let connection = midi_in
+            .create_virtual(
+                name,
+                move |_stamp, message, _| {
+                    if !message.is_empty() {
+                        Self::process_message(message, &inverted, &map, &sender);
+                    }
+                },
+                (),
+            )
+            .map_err(|e| format!("Failed to create virtual MIDI port '{}': {}", name, e))?;
+
+        Ok(Self {
+            connection: Some(connection),
+            receiver,
+            feedback: None,
         })
     }
 
-    fn process_message(message: &[u8], sender: &Sender<MidiCommand>) {
+    #[cfg(not(unix))]
+    pub fn new_virtual(
+        _name: &str,
+        _inverted: &[u8],
+        _map: &MidiMap,
+    ) -> Result<Self, String> {
+        Err("virtual MIDI ports are not supported on this platform".to_string())
+    }
+
+    fn process_message(
+        message: &[u8],
+        inverted: &[u8],
+        map: &MidiMap,
+        sender: &Sender<MidiCommand>,
+    ) {
+        // System real-time messages are a single status byte
+        let realtime = match message[0] {
+            0xF8 => Some(MidiCommand::Clock),
+            0xFA => Some(MidiCommand::ClockStart),
+            0xFB => Some(MidiCommand::ClockContinue),
+            0xFC => Some(MidiCommand::ClockStop),
+            _ => None,
+        };
+        if let Some(cmd) = realtime {
+            let _ = sender.send(cmd);
+            return;
+        }
+
+        if message.len() < 3 {
+            return;
+        }
+
         let status = message[0] & 0xF0;
-        let control = message[1];
+        let number = message[1];
         let value = message[2];
 
-        // Control Change messages
-        if status == 0xB0 {
-            let normalized = value as f32 / 127.0;
-            let bipolar = (value as f32 - MIDI_MAGIC) / MIDI_MAGIC;
-
-            let cmd = match control {
-                // Main continuous controls
-                16 => Some(MidiCommand::LumaKeyLevel(normalized)),
-                17 => Some(MidiCommand::DisplaceX(bipolar)),
-                18 => Some(MidiCommand::DisplaceY(bipolar)),
-                19 => Some(MidiCommand::ZFrequency(normalized)),
-                20 => Some(MidiCommand::XFrequency(bipolar)),
-                21 => Some(MidiCommand::YFrequency(bipolar)),
-                22 => Some(MidiCommand::Zoom(bipolar)),
-                23 => Some(MidiCommand::Scale(normalized)),
-
-                // Center/offset controls
-                120 => Some(MidiCommand::CenterX(bipolar)),
-                121 => Some(MidiCommand::CenterY(bipolar)),
-                122 => Some(MidiCommand::ZLfoArg(bipolar * 0.1)),
-                123 => Some(MidiCommand::ZLfoAmp(bipolar)),
-                124 => Some(MidiCommand::XLfoArg(bipolar * 0.1)),
-                125 => Some(MidiCommand::XLfoAmp(bipolar)),
-                126 => Some(MidiCommand::YLfoArg(bipolar * 0.1)),
-                127 => Some(MidiCommand::YLfoAmp(bipolar)),
-
-                // Record/reset
-                60 => {
-                    if value == 127 {
-                        Some(MidiCommand::RecordStart)
-                    } else {
-                        Some(MidiCommand::RecordStop)
-                    }
-                }
-                58 => {
-                    if value == 127 {
-                        Some(MidiCommand::Reset)
-                    } else {
-                        None
-                    }
+        let cmd = match status {
+            // Control Change: the --midi-map binding, else the default
+            0xB0 => {
+                let value = if inverted.contains(&number) { 127u8.saturating_sub(value) } else { value };
+                let on = value == 127;
+                match map.cc.get(&number) {
+                    Some(binding) => binding_command(&binding.command, binding.scale.apply(value), on),
+                    None => DEFAULT_MAP.iter().find(|(cc, _)| *cc == number).and_then(|(_, command)| {
+                        binding_command(command, MidiScale::Normalized.apply(value), on)
+                    }),
                 }
+            }
+            // Note On with its velocity, Note Off (or velocity 0) as value 0
+            0x90 | 0x80 => {
+                let velocity = if status == 0x90 { value } else { 0 };
+                map.note.get(&number).and_then(|binding| {
+                    binding_command(&binding.command, binding.scale.apply(velocity), velocity > 0)
+                })
+            }
+            _ => None,
+        };
+        if let Some(cmd) = cmd {
+            let _ = sender.send(cmd);
+        }
+    }
 
-                // Z LFO shapes
-                35 => Some(MidiCommand::ZLfoShape(if value == 127 { 1 } else { 0 })),
-                51 => Some(MidiCommand::ZLfoShape(if value == 127 { 2 } else { 0 })),
-                67 => Some(MidiCommand::ZLfoShape(if value == 127 { 3 } else { 0 })),
-
-                // X LFO shapes
-                37 => Some(MidiCommand::XLfoShape(if value == 127 { 1 } else { 0 })),
-                53 => Some(MidiCommand::XLfoShape(if value == 127 { 2 } else { 0 })),
-                69 => Some(MidiCommand::XLfoShape(if value == 127 { 3 } else { 0 })),
-
-                // Y LFO shapes
-                39 => Some(MidiCommand::YLfoShape(if value == 127 { 1 } else { 0 })),
-                55 => Some(MidiCommand::YLfoShape(if value == 127 { 2 } else { 0 })),
-                71 => Some(MidiCommand::YLfoShape(if value == 127 { 3 } else { 0 })),
-
-                // Ring/phase modulation
-                34 => Some(MidiCommand::ZFreqZero(value == 127)),
-                50 => Some(MidiCommand::ZRingMod(value == 127)),
-                66 => Some(MidiCommand::ZPhaseMod(value == 127)),
-                36 => Some(MidiCommand::XFreqZero(value == 127)),
-                52 => Some(MidiCommand::XRingMod(value == 127)),
-                68 => Some(MidiCommand::XPhaseMod(value == 127)),
-                38 => Some(MidiCommand::YFreqZero(value == 127)),
-                54 => Some(MidiCommand::YRingMod(value == 127)),
-                70 => Some(MidiCommand::YPhaseMod(value == 127)),
-
-                // Mesh types
-                41 => {
-                    if value == 127 {
-                        Some(MidiCommand::SetWireframe)
-                    } else {
-                        None
-                    }
-                }
-                42 => {
-                    if value == 127 {
-                        Some(MidiCommand::SetVerticalLines)
-                    } else {
-                        None
-                    }
-                }
-                43 => {
-                    if value == 127 {
-                        Some(MidiCommand::SetTriangleMesh)
-                    } else {
-                        None
-                    }
-                }
-                44 => {
-                    if value == 127 {
-                        Some(MidiCommand::SetHorizontalLines)
-                    } else {
-                        None
+    /// Open output port `port_index` for `send_feedback`, with the same
+    /// inverted CCs and map as the input
+    pub fn open_output(&mut self, port_index: usize, inverted: &[u8], map: &MidiMap) -> Result<(), String> {
+        let midi_out =
+            MidiOutput::new("spectral_mesh").map_err(|e| format!("Failed to create MIDI output: {}", e))?;
+        let ports = midi_out.ports();
+        let port = ports.get(port_index).ok_or_else(|| {
+            format!("MIDI output port {} not available (found {} ports)", port_index, ports.len())
+        })?;
+        let port_name = midi_out.port_name(port).unwrap_or_default();
+        let connection = midi_out
+            .connect(port, "spectral_mesh_feedback")
+            .map_err(|e| format!("Failed to connect to MIDI output port: {}", e))?;
+        log::info!("Sending MIDI feedback to {}", port_name);
+
+        let controls = (0..=127u8)
+            .filter_map(|control| {
+                let (command, scale) = match map.cc.get(&control) {
+                    Some(binding) => (binding.command.as_str(), binding.scale),
+                    None => {
+                        let &(_, command) = DEFAULT_MAP.iter().find(|(cc, _)| *cc == control)?;
+                        (command, MidiScale::Normalized)
                     }
-                }
-
-                // Visual effects
-                46 => Some(MidiCommand::Greyscale(value == 127)),
-                59 => Some(MidiCommand::Invert(value == 127)),
-                61 => Some(MidiCommand::BrightSwitch(value == 127)),
-                45 => Some(MidiCommand::StrokeWeight(normalized * 5.0)),
+                };
+                let source = match command {
+                    LOOP_POSITION => FeedbackSource::LoopPosition,
+                    _ => FeedbackSource::Param(ParamId::from_name(command)?),
+                };
+                Some((control, source, scale, inverted.contains(&control)))
+            })
+            .collect();
+        self.feedback = Some(MidiFeedback {
+            connection,
+            controls,
+            sent: [None; 128],
+        });
+        Ok(())
+    }
 
-                _ => None,
+    /// Send the CC value of every parameter with a CC whose value changed since
+    /// the last call (all of them the first time), so controllers with LED rings
+    /// or motor faders follow the p_lock playback, resets and pattern loads.
+    /// CCs bound to `LOOP_POSITION` get how far through the pattern playback is.
+    /// Does nothing without `open_output`.
+    pub fn send_feedback(&mut self, state: &AppState) {
+        let Some(feedback) = &mut self.feedback else {
+            return;
+        };
+        for &(control, source, scale, inverted) in &feedback.controls {
+            let normalized = match source {
+                FeedbackSource::Param(id) => id.spec().normalize(state.param(id)),
+                FeedbackSource::LoopPosition => state.p_lock.position(),
             };
-
-            if let Some(cmd) = cmd {
-                let _ = sender.send(cmd);
+            let value = scale.value(normalized);
+            let value = if inverted { 127 - value } else { value };
+            if feedback.sent[control as usize] == Some(value) {
+                continue;
+            }
+            if let Err(e) = feedback.connection.send(&[0xB0, control, value]) {
+                log::warn!("MIDI feedback stopped: {}", e);
+                self.feedback = None;
+                return;
             }
+            feedback.sent[control as usize] = Some(value);
         }
     }
 
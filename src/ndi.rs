@@ -0,0 +1,214 @@
+//! NDI network output. The NDI runtime (libndi, from the NDI SDK or NDI Tools)
+//! is loaded when the sender starts, so builds don't need the SDK and the app
+//! still runs where it isn't installed.
+
+use crate::renderer::CapturedFrame;
+#[cfg(feature = "ndi")]
+use std::sync::mpsc::{sync_channel, Receiver};
+use std::sync::mpsc::{SyncSender, TrySendError};
+use std::thread;
+
+pub struct NdiSender {
+    frames: SyncSender<CapturedFrame>,
+    #[allow(dead_code)]
+    handle: Option<thread::JoinHandle<()>>,
+}
+
+impl NdiSender {
+    /// Announce an NDI source called `name` on the network. Frames are sent
+    /// from a background thread.
+    #[cfg(feature = "ndi")]
+    pub fn new(name: &str) -> Result<Self, String> {
+        let source = runtime::Source::create(name)?;
+        log::info!("NDI source '{}' started", name);
+
+        // One frame in flight; newer frames are dropped while it's being sent
+        let (frames, receiver) = sync_channel(1);
+        let handle = thread::spawn(move || Self::send_thread(source, receiver));
+        Ok(Self {
+            frames,
+            handle: Some(handle),
+        })
+    }
+
+    #[cfg(not(feature = "ndi"))]
+    pub fn new(_name: &str) -> Result<Self, String> {
+        Err("NDI support not compiled. Enable 'ndi' feature.".to_string())
+    }
+
+    #[cfg(feature = "ndi")]
+    fn send_thread(source: runtime::Source, receiver: Receiver<CapturedFrame>) {
+        for frame in receiver {
+            // Converted here, so the render thread never touches the pixels
+            source.send(&frame.to_rgba(), frame.width(), frame.height());
+        }
+    }
+
+    /// Queue a captured frame. Skipped if the previous frame is still on its
+    /// way, so a slow network never holds up rendering.
+    pub fn send(&self, frame: CapturedFrame) {
+        match self.frames.try_send(frame) {
+            Ok(()) | Err(TrySendError::Full(_)) => {}
+            Err(TrySendError::Disconnected(_)) => log::warn!("NDI sender stopped"),
+        }
+    }
+}
+
+/// Minimal binding to the NDI C API, resolved from the runtime library
+#[cfg(feature = "ndi")]
+mod runtime {
+    use libloading::{Library, Symbol};
+    use std::ffi::{c_char, c_void, CString};
+
+    #[cfg(target_os = "windows")]
+    const LIBRARY_NAMES: &[&str] = &["Processing.NDI.Lib.x64.dll"];
+    #[cfg(target_os = "macos")]
+    const LIBRARY_NAMES: &[&str] = &["libndi.dylib", "/usr/local/lib/libndi.dylib"];
+    #[cfg(not(any(target_os = "windows", target_os = "macos")))]
+    const LIBRARY_NAMES: &[&str] = &["libndi.so.6", "libndi.so.5", "libndi.so"];
+
+    /// Folders the NDI installers point at
+    const RUNTIME_DIR_VARS: &[&str] = &["NDI_RUNTIME_DIR_V6", "NDI_RUNTIME_DIR_V5"];
+
+    /// NDIlib_FourCC_video_type_RGBA
+    const FOURCC_RGBA: u32 = u32::from_le_bytes(*b"RGBA");
+    /// NDIlib_frame_format_type_progressive
+    const FRAME_FORMAT_PROGRESSIVE: i32 = 1;
+    /// NDIlib_send_timecode_synthesize
+    const TIMECODE_SYNTHESIZE: i64 = i64::MAX;
+
+    #[repr(C)]
+    struct SendCreate {
+        p_ndi_name: *const c_char,
+        p_groups: *const c_char,
+        clock_video: bool,
+        clock_audio: bool,
+    }
+
+    #[repr(C)]
+    struct VideoFrameV2 {
+        xres: i32,
+        yres: i32,
+        four_cc: u32,
+        frame_rate_n: i32,
+        frame_rate_d: i32,
+        picture_aspect_ratio: f32,
+        frame_format_type: i32,
+        timecode: i64,
+        p_data: *const u8,
+        line_stride_in_bytes: i32,
+        p_metadata: *const c_char,
+        timestamp: i64,
+    }
+
+    type Initialize = unsafe extern "C" fn() -> bool;
+    type Destroy = unsafe extern "C" fn();
+    type SendCreateFn = unsafe extern "C" fn(*const SendCreate) -> *mut c_void;
+    type SendDestroy = unsafe extern "C" fn(*mut c_void);
+    type SendVideo = unsafe extern "C" fn(*mut c_void, *const VideoFrameV2);
+
+    /// A running NDI source; torn down on drop
+    pub struct Source {
+        library: Library,
+        instance: *mut c_void,
+    }
+
+    // The NDI send instance may be used from any one thread at a time
+    unsafe impl Send for Source {}
+
+    impl Source {
+        pub fn create(name: &str) -> Result<Self, String> {
+            let library = load_library()?;
+            let name = CString::new(name).map_err(|_| "NDI name must not contain NUL".to_string())?;
+
+            // SAFETY: the signatures match the NDI SDK headers (Processing.NDI.Lib.h)
+            unsafe {
+                let initialize: Symbol<Initialize> = symbol(&library, b"NDIlib_initialize\0")?;
+                if !initialize() {
+                    return Err("NDI runtime refused to start (unsupported CPU?)".to_string());
+                }
+                let send_create: Symbol<SendCreateFn> = symbol(&library, b"NDIlib_send_create\0")?;
+                let settings = SendCreate {
+                    p_ndi_name: name.as_ptr(),
+                    p_groups: std::ptr::null(),
+                    // Paced by the render loop, not by NDI
+                    clock_video: false,
+                    clock_audio: false,
+                };
+                let instance = send_create(&settings);
+                if instance.is_null() {
+                    return Err(format!("Failed to create NDI source '{}'", name.to_string_lossy()));
+                }
+                Ok(Self { library, instance })
+            }
+        }
+
+        /// Send tightly packed RGBA, top row first, synchronously; NDI copies
+        /// the frame before returning
+        pub fn send(&self, rgba: &[u8], width: u32, height: u32) {
+            let video = VideoFrameV2 {
+                xres: width as i32,
+                yres: height as i32,
+                four_cc: FOURCC_RGBA,
+                // Nominal rate; receivers follow the actual arrival
+                frame_rate_n: 60000,
+                frame_rate_d: 1000,
+                picture_aspect_ratio: width as f32 / height.max(1) as f32,
+                frame_format_type: FRAME_FORMAT_PROGRESSIVE,
+                timecode: TIMECODE_SYNTHESIZE,
+                p_data: rgba.as_ptr(),
+                line_stride_in_bytes: (width * 4) as i32,
+                p_metadata: std::ptr::null(),
+                timestamp: 0,
+            };
+            // SAFETY: `rgba` outlives the call and matches the declared size
+            unsafe {
+                match symbol::<SendVideo>(&self.library, b"NDIlib_send_send_video_v2\0") {
+                    Ok(send_video) => send_video(self.instance, &video),
+                    Err(e) => log::warn!("{}", e),
+                }
+            }
+        }
+    }
+
+    impl Drop for Source {
+        fn drop(&mut self) {
+            // SAFETY: the instance came from NDIlib_send_create of this library
+            unsafe {
+                if let Ok(send_destroy) = symbol::<SendDestroy>(&self.library, b"NDIlib_send_destroy\0") {
+                    send_destroy(self.instance);
+                }
+                if let Ok(destroy) = symbol::<Destroy>(&self.library, b"NDIlib_destroy\0") {
+                    destroy();
+                }
+            }
+        }
+    }
+
+    unsafe fn symbol<'a, T>(library: &'a Library, name: &[u8]) -> Result<Symbol<'a, T>, String> {
+        library.get(name).map_err(|e| {
+            format!("NDI runtime is missing {}: {}", String::from_utf8_lossy(&name[..name.len() - 1]), e)
+        })
+    }
+
+    fn load_library() -> Result<Library, String> {
+        let dirs: Vec<std::path::PathBuf> =
+            RUNTIME_DIR_VARS.iter().filter_map(std::env::var_os).map(Into::into).collect();
+        let candidates = dirs
+            .iter()
+            .flat_map(|dir| LIBRARY_NAMES.iter().map(move |name| dir.join(name)))
+            .chain(LIBRARY_NAMES.iter().map(Into::into));
+
+        for path in candidates {
+            // SAFETY: loading runs the library's initializers; libndi's are benign
+            if let Ok(library) = unsafe { Library::new(&path) } {
+                log::info!("Loaded NDI runtime from {}", path.display());
+                return Ok(library);
+            }
+        }
+        Err(format!(
+            "NDI runtime not found (looked for {}); install NDI Tools or the NDI SDK",
+            LIBRARY_NAMES.join(", ")
+        ))
+    }
+}
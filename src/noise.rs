@@ -1,7 +1,66 @@
-use noise::{NoiseFn, Perlin};
+use noise::core::worley::ReturnType;
+use noise::{Billow, Fbm, NoiseFn, OpenSimplex, Perlin, Worley};
+use std::path::Path;
+
+/// Noise texture resolution unless --noise-width / --noise-height say otherwise
+pub const DEFAULT_NOISE_WIDTH: u32 = 180;
+pub const DEFAULT_NOISE_HEIGHT: u32 = 120;
+/// Largest noise texture side, within every backend's texture limit
+pub const MAX_NOISE_SIZE: u32 = 2048;
+
+/// Captured noise fields, one greyscale PNG per axis
+const MAP_FILES: [&str; 3] = ["noise_x.png", "noise_y.png", "noise_z.png"];
+
+/// Noise function behind an axis' texture
+#[derive(Clone, Copy, Debug, Default, PartialEq, clap::ValueEnum)]
+pub enum NoiseKind {
+    /// Smooth gradient noise (original behavior)
+    #[default]
+    Perlin,
+    /// Like Perlin with fewer grid-aligned artifacts
+    Simplex,
+    /// Distance to the nearest cell point: cellular, liquid-looking
+    Worley,
+    /// Folded Perlin octaves: puffy, billowing ridges
+    Billow,
+    /// Summed Perlin octaves: finer detail on top of the large shapes
+    Fbm,
+}
+
+impl NoiseKind {
+    const ALL: [NoiseKind; 5] = [
+        NoiseKind::Perlin,
+        NoiseKind::Simplex,
+        NoiseKind::Worley,
+        NoiseKind::Billow,
+        NoiseKind::Fbm,
+    ];
+
+    /// Kind by index, clamped to the last one
+    pub fn from_index(index: usize) -> Self {
+        Self::ALL[index.min(Self::ALL.len() - 1)]
+    }
+
+    pub fn next(self) -> Self {
+        Self::ALL[(self as usize + 1) % Self::ALL.len()]
+    }
+
+    /// The noise function for this kind, all in roughly -1..1
+    fn source(self, seed: u32) -> Box<dyn NoiseFn<f64, 3>> {
+        match self {
+            NoiseKind::Perlin => Box::new(Perlin::new(seed)),
+            NoiseKind::Simplex => Box::new(OpenSimplex::new(seed)),
+            NoiseKind::Worley => Box::new(Worley::new(seed).set_return_type(ReturnType::Distance)),
+            NoiseKind::Billow => Box::new(Billow::<Perlin>::new(seed)),
+            NoiseKind::Fbm => Box::new(Fbm::<Perlin>::new(seed)),
+        }
+    }
+}
 
 pub struct NoiseGenerator {
-    perlin: Perlin,
+    source: Box<dyn NoiseFn<f64, 3>>,
+    kind: NoiseKind,
+    seed: u32,
     pub width: u32,
     pub height: u32,
     pixels: Vec<u8>,
@@ -10,14 +69,16 @@ pub struct NoiseGenerator {
 impl NoiseGenerator {
     pub fn new(width: u32, height: u32, seed: u32) -> Self {
         Self {
-            perlin: Perlin::new(seed),
+            source: NoiseKind::Perlin.source(seed),
+            kind: NoiseKind::Perlin,
+            seed,
             width,
             height,
             pixels: vec![0u8; (width * height) as usize],
         }
     }
 
-    /// Generate Perlin noise texture
+    /// Generate the noise texture
     /// theta: time/animation offset
     /// resolution: noise scale (smaller = smoother)
     pub fn generate(&mut self, theta: f32, resolution: f32) -> &[u8] {
@@ -26,7 +87,7 @@ impl NoiseGenerator {
 
         for y in 0..self.height {
             for x in 0..self.width {
-                let noise_value = self.perlin.get([
+                let noise_value = self.source.get([
                     (x as f64) * resolution as f64,
                     (y as f64) * resolution as f64,
                     theta as f64,
@@ -41,9 +102,54 @@ impl NoiseGenerator {
         &self.pixels
     }
 
+    /// Change the texture size; the new buffer is blank until the next `generate`
+    pub fn resize(&mut self, width: u32, height: u32) {
+        if (width, height) != (self.width, self.height) {
+            self.width = width;
+            self.height = height;
+            self.pixels = vec![0u8; (width * height) as usize];
+        }
+    }
+
+    /// Swap the noise function, keeping the pixel buffer
+    pub fn reseed(&mut self, seed: u32) {
+        self.seed = seed;
+        self.source = self.kind.source(seed);
+    }
+
+    /// Switch the noise function; the texture follows on the next `generate`
+    pub fn set_kind(&mut self, kind: NoiseKind) {
+        if kind != self.kind {
+            self.kind = kind;
+            self.source = kind.source(self.seed);
+        }
+    }
+
     pub fn pixels(&self) -> &[u8] {
         &self.pixels
     }
+
+    fn save_png(&self, path: &Path) -> Result<(), String> {
+        image::save_buffer(path, &self.pixels, self.width, self.height, image::ExtendedColorType::L8)
+            .map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+    }
+
+    /// Replace the texture with a greyscale image, resized to fit
+    fn load_png(&mut self, path: &Path) -> Result<(), String> {
+        let mut map = image::open(path)
+            .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?
+            .to_luma8();
+        if map.dimensions() != (self.width, self.height) {
+            map = image::imageops::resize(&map, self.width, self.height, image::imageops::FilterType::Triangle);
+        }
+        self.pixels = map.into_raw();
+        Ok(())
+    }
+}
+
+/// Per-axis seeds derived from one, as `NoiseBank::with_seed` uses them
+pub fn axis_seeds(seed: u32) -> [u32; 3] {
+    [seed, seed.wrapping_add(1), seed.wrapping_add(2)]
 }
 
 pub struct NoiseBank {
@@ -54,13 +160,50 @@ pub struct NoiseBank {
 
 impl NoiseBank {
     pub fn new(width: u32, height: u32) -> Self {
+        Self::with_seed(width, height, 0)
+    }
+
+    /// Noise fields from Perlin seeds `seed`, `seed + 1` and `seed + 2` (X, Y, Z)
+    pub fn with_seed(width: u32, height: u32, seed: u32) -> Self {
+        Self::with_seeds(width, height, axis_seeds(seed))
+    }
+
+    /// Noise fields from one Perlin seed per axis (X, Y, Z)
+    pub fn with_seeds(width: u32, height: u32, seeds: [u32; 3]) -> Self {
         Self {
-            x_noise: NoiseGenerator::new(width, height, 0),
-            y_noise: NoiseGenerator::new(width, height, 1),
-            z_noise: NoiseGenerator::new(width, height, 2),
+            x_noise: NoiseGenerator::new(width, height, seeds[0]),
+            y_noise: NoiseGenerator::new(width, height, seeds[1]),
+            z_noise: NoiseGenerator::new(width, height, seeds[2]),
         }
     }
 
+    /// Rebuild the noise functions in place; the textures keep their buffers
+    /// and pick up the new seeds on the next `update`
+    pub fn reseed(&mut self, seeds: [u32; 3]) {
+        self.x_noise.reseed(seeds[0]);
+        self.y_noise.reseed(seeds[1]);
+        self.z_noise.reseed(seeds[2]);
+    }
+
+    /// Texture width and height, the same for every axis
+    pub fn size(&self) -> (u32, u32) {
+        (self.x_noise.width, self.x_noise.height)
+    }
+
+    /// Change the resolution of all three textures, keeping seeds and kinds
+    pub fn resize(&mut self, width: u32, height: u32) {
+        self.x_noise.resize(width, height);
+        self.y_noise.resize(width, height);
+        self.z_noise.resize(width, height);
+    }
+
+    /// Noise function per axis (X, Y, Z)
+    pub fn set_kinds(&mut self, kinds: [NoiseKind; 3]) {
+        self.x_noise.set_kind(kinds[0]);
+        self.y_noise.set_kind(kinds[1]);
+        self.z_noise.set_kind(kinds[2]);
+    }
+
     /// Update all noise textures with their respective parameters
     pub fn update(
         &mut self,
@@ -75,4 +218,21 @@ impl NoiseBank {
         self.y_noise.generate(y_theta, y_resolution);
         self.z_noise.generate(z_theta, z_resolution);
     }
+
+    /// Write the current X/Y/Z noise to `dir` as noise_x.png, noise_y.png, noise_z.png
+    pub fn save_png(&self, dir: &Path) -> Result<(), String> {
+        std::fs::create_dir_all(dir).map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+        for (noise, file) in [&self.x_noise, &self.y_noise, &self.z_noise].into_iter().zip(MAP_FILES) {
+            noise.save_png(&dir.join(file))?;
+        }
+        Ok(())
+    }
+
+    /// Load maps written by `save_png`; all three must be present
+    pub fn load_png(&mut self, dir: &Path) -> Result<(), String> {
+        for (noise, file) in [&mut self.x_noise, &mut self.y_noise, &mut self.z_noise].into_iter().zip(MAP_FILES) {
+            noise.load_png(&dir.join(file))?;
+        }
+        Ok(())
+    }
 }
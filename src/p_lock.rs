@@ -1,8 +1,27 @@
-/// Parameter Lock system for recording and playing back parameter automation
-/// Ported from the original spectral_mesh p_lock implementation
+//! Parameter Lock system for recording and playing back parameter automation
+//! Ported from the original spectral_mesh p_lock implementation
+
+use serde::{Deserialize, Serialize};
+use std::io;
+use std::path::Path;
 
 pub const P_LOCK_SIZE: usize = 240;
-pub const P_LOCK_NUMBER: usize = 17;
+pub const P_LOCK_NUMBER: usize = 19;
+
+/// MIDI clock pulses per bar (24 ppqn, 4/4)
+pub const CLOCK_PULSES_PER_BAR: u32 = 96;
+/// Updates without a clock pulse before falling back to free-running steps
+const CLOCK_TIMEOUT_UPDATES: u32 = 30;
+/// Pattern file kept next to the saved defaults unless --pattern says otherwise
+pub const PATTERN_FILE_NAME: &str = "pattern.json";
+
+/// Recorded automation as written by `save_to_file`: one row of steps per parameter
+#[derive(Serialize, Deserialize)]
+struct PatternFile {
+    smooth_factor: f32,
+    increment: usize,
+    locks: Vec<Vec<f32>>,
+}
 
 pub struct PLockSystem {
     /// 2D array of parameter values [param_index][step]
@@ -17,6 +36,16 @@ pub struct PLockSystem {
     pub recording: bool,
     /// Smoothing factor (0.0 - 1.0)
     pub smooth_factor: f32,
+    /// MIDI clock pulses spanning the whole pattern (0 = ignore clock)
+    pub clock_pulses_per_pattern: u32,
+    /// Clock pulses since the start of the pattern
+    clock_pulse: u32,
+    /// Updates since the last clock pulse
+    clock_idle: u32,
+    /// Transport stopped by a MIDI Stop message
+    transport_stopped: bool,
+    /// The pattern wrapped back to its first step since `take_loop_start`
+    looped: bool,
 }
 
 impl PLockSystem {
@@ -28,6 +57,11 @@ impl PLockSystem {
             increment: 0,
             recording: false,
             smooth_factor: 0.5,
+            clock_pulses_per_pattern: 4 * CLOCK_PULSES_PER_BAR,
+            clock_pulse: 0,
+            clock_idle: CLOCK_TIMEOUT_UPDATES,
+            transport_stopped: false,
+            looped: false,
         };
 
         // Set initial default values for effects to be visible
@@ -39,6 +73,7 @@ impl PLockSystem {
         // 10: z_lfo_arg, 11: z_lfo_amp
         // 12: x_lfo_arg, 13: x_lfo_amp
         // 14: y_lfo_arg, 15: y_lfo_amp
+        // 16-18: x/y/z_lfo_bias (0 = centered)
 
         // Default luma key level (0.5 = mid-brightness threshold)
         system.set_all(0, 0.5);
@@ -108,11 +143,53 @@ impl PLockSystem {
             }
         }
 
-        if self.recording {
+        // Free-running advance, unless an external clock is driving the steps
+        self.clock_idle = self.clock_idle.saturating_add(1);
+        if self.recording && !self.clock_synced() && !self.transport_stopped {
             self.increment = (self.increment + 1) % P_LOCK_SIZE;
+            self.looped |= self.increment == 0;
         }
     }
 
+    /// True while MIDI clock pulses are arriving
+    pub fn clock_synced(&self) -> bool {
+        self.clock_pulses_per_pattern > 0 && self.clock_idle < CLOCK_TIMEOUT_UPDATES
+    }
+
+    /// Advance on a MIDI clock pulse, mapping the pattern onto the clock bar length
+    pub fn clock_tick(&mut self) {
+        if self.clock_pulses_per_pattern == 0 {
+            return;
+        }
+        self.clock_idle = 0;
+        if self.transport_stopped {
+            return;
+        }
+
+        self.clock_pulse = (self.clock_pulse + 1) % self.clock_pulses_per_pattern;
+        if self.recording {
+            self.increment = (self.clock_pulse as usize * P_LOCK_SIZE) / self.clock_pulses_per_pattern as usize;
+            self.looped |= self.clock_pulse == 0;
+        }
+    }
+
+    /// MIDI Start: rewind to the first step
+    pub fn clock_start(&mut self) {
+        self.transport_stopped = false;
+        self.clock_pulse = 0;
+        self.increment = 0;
+    }
+
+    /// MIDI Continue: resume from the current step
+    pub fn clock_continue(&mut self) {
+        self.transport_stopped = false;
+    }
+
+    /// MIDI Stop: freeze the current step
+    pub fn clock_stop(&mut self) {
+        self.transport_stopped = true;
+    }
+
     /// Get smoothed value for a parameter
     pub fn get(&self, index: usize) -> f32 {
         if index < P_LOCK_NUMBER {
@@ -167,6 +244,29 @@ impl PLockSystem {
         }
     }
 
+    /// Turn a parameter's recording into steps: the pattern is split into
+    /// `divisions` equal parts, each holding the value recorded at its start
+    pub fn quantize(&mut self, index: usize, divisions: usize) {
+        if index >= P_LOCK_NUMBER {
+            return;
+        }
+        let divisions = divisions.clamp(1, P_LOCK_SIZE);
+        let steps = &mut self.locks[index];
+        for d in 0..divisions {
+            let start = d * P_LOCK_SIZE / divisions;
+            let end = (d + 1) * P_LOCK_SIZE / divisions;
+            let held = steps[start];
+            steps[start..end].fill(held);
+        }
+    }
+
+    /// Quantize every parameter
+    pub fn quantize_all(&mut self, divisions: usize) {
+        for i in 0..P_LOCK_NUMBER {
+            self.quantize(i, divisions);
+        }
+    }
+
     /// Stop recording
     pub fn stop_recording(&mut self) {
         self.recording = false;
@@ -176,6 +276,66 @@ impl PLockSystem {
     pub fn current_step(&self) -> usize {
         self.increment
     }
+
+    /// Steps in the pattern
+    pub fn length(&self) -> usize {
+        P_LOCK_SIZE
+    }
+
+    /// How far through the pattern playback is (0 - 1)
+    pub fn position(&self) -> f32 {
+        self.increment as f32 / P_LOCK_SIZE as f32
+    }
+
+    /// Write the recorded automation, its smoothing and the current step as JSON
+    pub fn save_to_file(&self, path: &Path) -> io::Result<()> {
+        let pattern = PatternFile {
+            smooth_factor: self.smooth_factor,
+            increment: self.increment,
+            locks: self.locks.iter().map(|steps| steps.to_vec()).collect(),
+        };
+        let json = serde_json::to_string(&pattern).map_err(io::Error::other)?;
+        if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+            std::fs::create_dir_all(dir)?;
+        }
+        std::fs::write(path, json)
+    }
+
+    /// Replace the automation with a pattern from `save_to_file`. A file of
+    /// another size is refused and leaves the current pattern as it was.
+    pub fn load_from_file(&mut self, path: &Path) -> io::Result<()> {
+        let json = std::fs::read_to_string(path)?;
+        let pattern: PatternFile =
+            serde_json::from_str(&json).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+
+        let steps = pattern.locks.first().map_or(0, Vec::len);
+        if pattern.locks.len() != P_LOCK_NUMBER || pattern.locks.iter().any(|row| row.len() != P_LOCK_SIZE) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!(
+                    "pattern has {} parameters of {} steps, expected {} of {}",
+                    pattern.locks.len(),
+                    steps,
+                    P_LOCK_NUMBER,
+                    P_LOCK_SIZE
+                ),
+            ));
+        }
+
+        for (locks, row) in self.locks.iter_mut().zip(&pattern.locks) {
+            locks.copy_from_slice(row);
+        }
+        self.smooth_factor = pattern.smooth_factor.clamp(0.0, 1.0);
+        self.increment = pattern.increment % P_LOCK_SIZE;
+        // Knobs have to catch the loaded values again before they write
+        self.midi_active = [false; P_LOCK_NUMBER];
+        Ok(())
+    }
+
+    /// True once each time the pattern wraps back to its first step
+    pub fn take_loop_start(&mut self) -> bool {
+        std::mem::take(&mut self.looped)
+    }
 }
 
 impl Default for PLockSystem {
@@ -0,0 +1,269 @@
+//! Parameter metadata: range, default and response curve for every continuous
+//! control. External controls (MIDI, keyboard, config) send a normalized 0-1 value
+//! and `AppState::set_param` scales it through the spec.
+//!
+//! Ranges are in the units the value is stored in (p_lock slot or AppState field);
+//! `calculate_render_params` then maps p_lock values into clip space.
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Curve {
+    Linear,
+    /// Finer control near `min`
+    Squared,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub enum ParamId {
+    LumaKeyLevel,
+    DisplaceX,
+    DisplaceY,
+    ZFrequency,
+    XFrequency,
+    YFrequency,
+    Zoom,
+    Scale,
+    CenterX,
+    CenterY,
+    ZLfoRate,
+    ZLfoAmp,
+    XLfoRate,
+    XLfoAmp,
+    YLfoRate,
+    YLfoAmp,
+    VideoMix,
+    ChromaSplit,
+    FeedbackDecay,
+    BackgroundRed,
+    BackgroundGreen,
+    BackgroundBlue,
+    StrokeWeight,
+    AudioStrokeGain,
+    AutoCenterSpeed,
+    AudioSensitivity,
+    Fov,
+    NoiseTimeScale,
+    // Stacked LFOs (see state::ExtraLfo), rate / amp / frequency each,
+    // in state.extra_lfos order; must stay contiguous for `extra_lfo`
+    XLfo2Rate,
+    XLfo2Amp,
+    XLfo2Frequency,
+    XLfo3Rate,
+    XLfo3Amp,
+    XLfo3Frequency,
+    YLfo2Rate,
+    YLfo2Amp,
+    YLfo2Frequency,
+    YLfo3Rate,
+    YLfo3Amp,
+    YLfo3Frequency,
+    ZLfo2Rate,
+    ZLfo2Amp,
+    ZLfo2Frequency,
+    ZLfo3Rate,
+    ZLfo3Amp,
+    ZLfo3Frequency,
+    InputExposure,
+    InputGamma,
+    MeshSmoothing,
+    XLfoBias,
+    YLfoBias,
+    ZLfoBias,
+    AudioZoomAmount,
+    MasterIntensity,
+    // Noise texture scale and displacement gain, per axis
+    XNoiseResolution,
+    YNoiseResolution,
+    ZNoiseResolution,
+    XNoiseAmp,
+    YNoiseAmp,
+    ZNoiseAmp,
+    BloomIntensity,
+    BloomThreshold,
+    AudioBloomGain,
+    AudioChromaGain,
+}
+
+pub struct ParamSpec {
+    pub id: ParamId,
+    pub name: &'static str,
+    pub min: f32,
+    pub max: f32,
+    pub default: f32,
+    pub curve: Curve,
+}
+
+impl ParamSpec {
+    const fn new(id: ParamId, name: &'static str, min: f32, max: f32, default: f32) -> Self {
+        Self {
+            id,
+            name,
+            min,
+            max,
+            default,
+            curve: Curve::Linear,
+        }
+    }
+
+    const fn curve(mut self, curve: Curve) -> Self {
+        self.curve = curve;
+        self
+    }
+
+    /// Normalized control value (0-1) to parameter value
+    pub fn scale(&self, normalized: f32) -> f32 {
+        let t = normalized.clamp(0.0, 1.0);
+        let t = match self.curve {
+            Curve::Linear => t,
+            Curve::Squared => t * t,
+        };
+        self.min + t * (self.max - self.min)
+    }
+
+    /// Parameter value back to a normalized control value (0-1)
+    pub fn normalize(&self, value: f32) -> f32 {
+        let t = ((value - self.min) / (self.max - self.min)).clamp(0.0, 1.0);
+        match self.curve {
+            Curve::Linear => t,
+            Curve::Squared => t.sqrt(),
+        }
+    }
+}
+
+use ParamId::*;
+
+/// One entry per `ParamId`, in declaration order
+pub const PARAMS: [ParamSpec; 64] = [
+    ParamSpec::new(LumaKeyLevel, "luma_key_level", 0.0, 1.0, 0.5),
+    ParamSpec::new(DisplaceX, "displace_x", -1.0, 1.0, 0.1),
+    ParamSpec::new(DisplaceY, "displace_y", -1.0, 1.0, 0.1),
+    ParamSpec::new(ZFrequency, "z_frequency", 0.0, 1.0, 0.2),
+    ParamSpec::new(XFrequency, "x_frequency", -1.0, 1.0, 0.3),
+    ParamSpec::new(YFrequency, "y_frequency", -1.0, 1.0, 0.3),
+    ParamSpec::new(Zoom, "zoom", -1.0, 1.0, 0.0),
+    ParamSpec::new(Scale, "scale", 0.0, 1.0, 0.5),
+    ParamSpec::new(CenterX, "center_x", -1.0, 1.0, 0.5),
+    ParamSpec::new(CenterY, "center_y", -1.0, 1.0, 0.5),
+    ParamSpec::new(ZLfoRate, "z_lfo_rate", -0.1, 0.1, 0.02),
+    ParamSpec::new(ZLfoAmp, "z_lfo_amp", -1.0, 1.0, 0.2),
+    ParamSpec::new(XLfoRate, "x_lfo_rate", -0.1, 0.1, 0.015),
+    ParamSpec::new(XLfoAmp, "x_lfo_amp", -1.0, 1.0, 0.3),
+    ParamSpec::new(YLfoRate, "y_lfo_rate", -0.1, 0.1, 0.018),
+    ParamSpec::new(YLfoAmp, "y_lfo_amp", -1.0, 1.0, 0.3),
+    ParamSpec::new(VideoMix, "video_mix", 0.0, 1.0, 0.0),
+    ParamSpec::new(ChromaSplit, "chroma_split", 0.0, 1.0, 0.0),
+    ParamSpec::new(FeedbackDecay, "feedback_decay", 0.0, 0.99, 0.0),
+    ParamSpec::new(BackgroundRed, "background_red", 0.0, 1.0, 0.0),
+    ParamSpec::new(BackgroundGreen, "background_green", 0.0, 1.0, 0.0),
+    ParamSpec::new(BackgroundBlue, "background_blue", 0.0, 1.0, 0.0),
+    ParamSpec::new(StrokeWeight, "stroke_weight", 0.0, 5.0, 1.0),
+    ParamSpec::new(AudioStrokeGain, "audio_stroke_gain", 0.0, crate::mesh::MAX_STROKE_WEIGHT, 0.0),
+    ParamSpec::new(AutoCenterSpeed, "auto_center_speed", 0.0, 0.25, 0.05).curve(Curve::Squared),
+    ParamSpec::new(AudioSensitivity, "audio_sensitivity", 0.0, 5.0, 1.0),
+    ParamSpec::new(Fov, "fov", 20.0, 120.0, 60.0),
+    // Squared so the knob's center is 1x
+    ParamSpec::new(NoiseTimeScale, "noise_time_scale", 0.0, 4.0, 1.0).curve(Curve::Squared),
+    // Same ranges as the main LFOs; amplitude 0 leaves a stacked LFO off
+    ParamSpec::new(XLfo2Rate, "x_lfo2_rate", -0.1, 0.1, 0.0),
+    ParamSpec::new(XLfo2Amp, "x_lfo2_amp", -1.0, 1.0, 0.0),
+    ParamSpec::new(XLfo2Frequency, "x_lfo2_frequency", -1.0, 1.0, 0.0),
+    ParamSpec::new(XLfo3Rate, "x_lfo3_rate", -0.1, 0.1, 0.0),
+    ParamSpec::new(XLfo3Amp, "x_lfo3_amp", -1.0, 1.0, 0.0),
+    ParamSpec::new(XLfo3Frequency, "x_lfo3_frequency", -1.0, 1.0, 0.0),
+    ParamSpec::new(YLfo2Rate, "y_lfo2_rate", -0.1, 0.1, 0.0),
+    ParamSpec::new(YLfo2Amp, "y_lfo2_amp", -1.0, 1.0, 0.0),
+    ParamSpec::new(YLfo2Frequency, "y_lfo2_frequency", -1.0, 1.0, 0.0),
+    ParamSpec::new(YLfo3Rate, "y_lfo3_rate", -0.1, 0.1, 0.0),
+    ParamSpec::new(YLfo3Amp, "y_lfo3_amp", -1.0, 1.0, 0.0),
+    ParamSpec::new(YLfo3Frequency, "y_lfo3_frequency", -1.0, 1.0, 0.0),
+    ParamSpec::new(ZLfo2Rate, "z_lfo2_rate", -0.1, 0.1, 0.0),
+    ParamSpec::new(ZLfo2Amp, "z_lfo2_amp", -1.0, 1.0, 0.0),
+    ParamSpec::new(ZLfo2Frequency, "z_lfo2_frequency", 0.0, 1.0, 0.0),
+    ParamSpec::new(ZLfo3Rate, "z_lfo3_rate", -0.1, 0.1, 0.0),
+    ParamSpec::new(ZLfo3Amp, "z_lfo3_amp", -1.0, 1.0, 0.0),
+    ParamSpec::new(ZLfo3Frequency, "z_lfo3_frequency", 0.0, 1.0, 0.0),
+    // Source correction before the effects: exposure in stops, gamma squared
+    // so the knob's center sits near 1
+    ParamSpec::new(InputExposure, "input_exposure", -3.0, 3.0, 0.0),
+    ParamSpec::new(InputGamma, "input_gamma", 0.25, 4.0, 1.0).curve(Curve::Squared),
+    // How much LFO detail finer than the mesh is filtered out (0 = none)
+    ParamSpec::new(MeshSmoothing, "mesh_smoothing", 0.0, 1.0, 0.0),
+    // Static offset the main LFOs oscillate around (0 = centered)
+    ParamSpec::new(XLfoBias, "x_lfo_bias", -1.0, 1.0, 0.0),
+    ParamSpec::new(YLfoBias, "y_lfo_bias", -1.0, 1.0, 0.0),
+    ParamSpec::new(ZLfoBias, "z_lfo_bias", -1.0, 1.0, 0.0),
+    // Breathing zoom: extra scale at full audio level (1 = twice the size)
+    ParamSpec::new(AudioZoomAmount, "audio_zoom_amount", 0.0, 1.0, 0.0),
+    // Scales every distortion at once (0 = clean video, 1 = as programmed)
+    ParamSpec::new(MasterIntensity, "master_intensity", 0.0, 1.0, 1.0),
+    // Noise feature size, independent of the LFO frequencies (higher = finer);
+    // defaults match the frequencies the noise used to follow
+    ParamSpec::new(XNoiseResolution, "x_noise_resolution", 0.0, 1.0, 0.3),
+    ParamSpec::new(YNoiseResolution, "y_noise_resolution", 0.0, 1.0, 0.3),
+    ParamSpec::new(ZNoiseResolution, "z_noise_resolution", 0.0, 1.0, 0.2),
+    // Gain on the noise source's displacement, squared so the knob's center is 1x
+    ParamSpec::new(XNoiseAmp, "x_noise_amp", 0.0, 4.0, 1.0).curve(Curve::Squared),
+    ParamSpec::new(YNoiseAmp, "y_noise_amp", 0.0, 4.0, 1.0).curve(Curve::Squared),
+    ParamSpec::new(ZNoiseAmp, "z_noise_amp", 0.0, 4.0, 1.0).curve(Curve::Squared),
+    // Glow around bright areas: strength (0 = off), the brightness it starts
+    // at, and extra strength at full audio level
+    ParamSpec::new(BloomIntensity, "bloom_intensity", 0.0, 2.0, 0.0),
+    ParamSpec::new(BloomThreshold, "bloom_threshold", 0.0, 1.0, 0.6),
+    ParamSpec::new(AudioBloomGain, "audio_bloom_gain", 0.0, 2.0, 0.0),
+    // Extra RGB split at full audio level, on top of chroma_split
+    ParamSpec::new(AudioChromaGain, "audio_chroma_gain", 0.0, 1.0, 0.0),
+];
+
+impl ParamId {
+    pub fn spec(self) -> &'static ParamSpec {
+        &PARAMS[self as usize]
+    }
+
+    pub fn from_name(name: &str) -> Option<ParamId> {
+        PARAMS.iter().find(|spec| spec.name == name).map(|spec| spec.id)
+    }
+
+    /// Stacked LFO index and field (0 = rate, 1 = amp, 2 = frequency)
+    pub fn extra_lfo(self) -> Option<(usize, usize)> {
+        let i = (self as usize).checked_sub(XLfo2Rate as usize)?;
+        (i < 3 * crate::state::EXTRA_LFOS).then_some((i / 3, i % 3))
+    }
+
+    /// p_lock slot holding this parameter, for the recordable ones
+    pub fn p_lock_slot(self) -> Option<usize> {
+        match self {
+            LumaKeyLevel => Some(0),
+            DisplaceX => Some(1),
+            DisplaceY => Some(2),
+            ZFrequency => Some(3),
+            XFrequency => Some(4),
+            YFrequency => Some(5),
+            Zoom => Some(6),
+            Scale => Some(7),
+            CenterX => Some(8),
+            CenterY => Some(9),
+            ZLfoRate => Some(10),
+            ZLfoAmp => Some(11),
+            XLfoRate => Some(12),
+            XLfoAmp => Some(13),
+            YLfoRate => Some(14),
+            YLfoAmp => Some(15),
+            XLfoBias => Some(16),
+            YLfoBias => Some(17),
+            ZLfoBias => Some(18),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn params_are_listed_in_variant_order() {
+        for (i, spec) in PARAMS.iter().enumerate() {
+            assert_eq!(spec.id as usize, i, "PARAMS[{}] is {} ({:?})", i, spec.name, spec.id);
+            assert_eq!(spec.id.spec().name, spec.name);
+        }
+    }
+}
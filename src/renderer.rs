@@ -1,15 +1,39 @@
-use crate::mesh::{Mesh, MeshType, Vertex};
-use crate::state::AppState;
+use crate::effects::EffectChain;
+use crate::mesh::{Mesh, Vertex};
+use crate::noise::{DEFAULT_NOISE_HEIGHT, DEFAULT_NOISE_WIDTH};
+use crate::state::{AppState, ProjectionMode, Solo, EXTRA_LFOS, MAX_RIPPLES};
+use crate::video::ResizeFilter;
 use bytemuck::{Pod, Zeroable};
 use glam::{Mat4, Vec3};
+use std::sync::atomic::{AtomicU8, Ordering};
+use std::sync::Arc;
 use wgpu::util::DeviceExt;
 
+/// Bits of `Uniforms::effect_flags`, one per on/off effect. Mirrored as the
+/// FLAG_* constants in displace.wgsl; add new toggles to both.
+pub mod effect_flags {
+    pub const INVERT: u32 = 1 << 0;
+    pub const GREYSCALE: u32 = 1 << 1;
+    /// Displace by darkness instead of brightness
+    pub const BRIGHT_INVERT: u32 = 1 << 2;
+    /// Luma key removes bright instead of dark areas
+    pub const LUMA_KEY_BRIGHT: u32 = 1 << 3;
+    pub const X_RINGMOD: u32 = 1 << 4;
+    pub const Y_RINGMOD: u32 = 1 << 5;
+    pub const Z_RINGMOD: u32 = 1 << 6;
+    pub const X_PHASEMOD: u32 = 1 << 7;
+    pub const Y_PHASEMOD: u32 = 1 << 8;
+    pub const Z_PHASEMOD: u32 = 1 << 9;
+    /// RGB split along the mesh displacement instead of the LFO direction
+    pub const CHROMA_FOLLOW: u32 = 1 << 10;
+}
+
 #[repr(C)]
 #[derive(Copy, Clone, Pod, Zeroable)]
 pub struct Uniforms {
     pub mvp: [[f32; 4]; 4],          // 64 bytes, offset 0
     pub xy: [f32; 2],                 // 8 bytes, offset 64
-    pub xy_offset: [f32; 2],          // 8 bytes, offset 72
+    pub pivot: [f32; 2],              // 8 bytes, offset 72
     pub x_lfo_arg: f32,               // 4 bytes, offset 80
     pub x_lfo_amp: f32,               // 4 bytes, offset 84
     pub x_lfo_other: f32,             // 4 bytes, offset 88
@@ -20,86 +44,753 @@ pub struct Uniforms {
     pub z_lfo_amp: f32,               // 4 bytes, offset 108
     pub z_lfo_other: f32,             // 4 bytes, offset 112
     pub luma_key_level: f32,          // 4 bytes, offset 116
-    pub invert_switch: f32,           // 4 bytes, offset 120
-    pub b_w_switch: f32,              // 4 bytes, offset 124
-    pub bright_switch: i32,           // 4 bytes, offset 128
-    pub x_lfo_shape: i32,             // 4 bytes, offset 132
-    pub y_lfo_shape: i32,             // 4 bytes, offset 136
-    pub z_lfo_shape: i32,             // 4 bytes, offset 140
-    pub x_ringmod_switch: i32,        // 4 bytes, offset 144
-    pub y_ringmod_switch: i32,        // 4 bytes, offset 148
-    pub z_ringmod_switch: i32,        // 4 bytes, offset 152
-    pub x_phasemod_switch: i32,       // 4 bytes, offset 156
-    pub y_phasemod_switch: i32,       // 4 bytes, offset 160
-    pub z_phasemod_switch: i32,       // 4 bytes, offset 164
-    pub luma_switch: i32,             // 4 bytes, offset 168
-    pub width: i32,                   // 4 bytes, offset 172
-    pub height: i32,                  // 4 bytes, offset 176
-    pub audio_displacement: f32,      // 4 bytes, offset 180
-    pub audio_z: f32,                 // 4 bytes, offset 184
-    pub audio_wave_phase: f32,        // 4 bytes, offset 188 - wave phase for line undulation
-    pub audio_wave_amp: f32,          // 4 bytes, offset 192 - wave amplitude from bass
-    pub audio_wave_freq: f32,         // 4 bytes, offset 200 - wave frequency from audio energy
-    pub _pad: [f32; 6],               // 24 bytes padding (total 224, matches WGSL alignment)
+    pub effect_flags: u32,            // 4 bytes, offset 120 - on/off effects, see effect_flags
+    pub x_lfo_shape: i32,             // 4 bytes, offset 124
+    pub y_lfo_shape: i32,             // 4 bytes, offset 128
+    pub z_lfo_shape: i32,             // 4 bytes, offset 132
+    pub width: i32,                   // 4 bytes, offset 136
+    pub height: i32,                  // 4 bytes, offset 140
+    pub audio_displacement: f32,      // 4 bytes, offset 144
+    pub audio_z: f32,                 // 4 bytes, offset 148
+    pub audio_wave_phase: f32,        // 4 bytes, offset 152 - wave phase for line undulation
+    pub audio_wave_amp: f32,          // 4 bytes, offset 156 - wave amplitude from bass
+    pub audio_wave_freq: f32,         // 4 bytes, offset 160 - wave frequency from audio energy
+    pub video_mix: f32,               // 4 bytes, offset 164 - crossfade to second video source
+    pub chroma_split: f32,            // 4 bytes, offset 168 - RGB split offset in tex coords
+    pub x_source: i32,                // 4 bytes, offset 172 - DisplaceSource per axis
+    pub y_source: i32,                // 4 bytes, offset 176
+    pub z_source: i32,                // 4 bytes, offset 180
+    pub audio_level: f32,             // 4 bytes, offset 184 - bass envelope for the audio source
+    pub _align: f32,                  // 4 bytes, offset 188 - keeps ripples 16-byte aligned
+    pub ripples: [[f32; 4]; MAX_RIPPLES], // 256 bytes, offset 192 - [x, y, radius, intensity]
+    pub ripple_shape: i32,            // 4 bytes, offset 448 - RippleShape
+    pub audio_displace_x: f32,        // 4 bytes, offset 452 - per-axis audio displacement
+    pub audio_displace_y: f32,        // 4 bytes, offset 456
+    pub ripple_count: i32,            // 4 bytes, offset 460 - ripples in use
+    pub extra_lfos: [[f32; 4]; EXTRA_LFOS], // 96 bytes, offset 464 - [phase, amp, frequency, shape]
+    pub input_exposure: f32,          // 4 bytes, offset 560 - linear gain on the source color
+    pub input_gamma: f32,             // 4 bytes, offset 564
+    pub flash: f32,                   // 4 bytes, offset 568 - strobe amount (0 = none)
+    pub flash_mode: i32,              // 4 bytes, offset 572 - FlashMode
+    pub mesh_smoothing: f32,          // 4 bytes, offset 576 - LFO band-limiting (0 = off)
+    pub grid_cells: f32,              // 4 bytes, offset 580 - mesh cells per side
+    pub output_level: f32,            // 4 bytes, offset 584 - master fade (1 = full, 0 = black)
+    pub x_lfo_bias: f32,              // 4 bytes, offset 588 - added to the LFO outputs
+    pub y_lfo_bias: f32,              // 4 bytes, offset 592
+    pub z_lfo_bias: f32,              // 4 bytes, offset 596
+    pub _pad: [f32; 2],               // 8 bytes, offset 600
+    pub noise_amp: [f32; 4],          // 16 bytes, offset 608 - noise source gain [x, y, z, unused] (total 624)
+}
+
+/// How the video-sized mesh maps into the window
+struct Extents {
+    aspect_scale: f32,
+    half_w: f32,
+    half_h: f32,
+    proj_w: f32,
+    proj_h: f32,
+}
+
+#[repr(C)]
+#[derive(Copy, Clone, Pod, Zeroable)]
+struct FeedbackUniforms {
+    decay: f32,
+    _pad: [f32; 3],
+}
+
+#[repr(C)]
+#[derive(Copy, Clone, Pod, Zeroable)]
+struct BloomUniforms {
+    threshold: f32,
+    intensity: f32,
+    /// One texel of the bloom buffers, in tex coords
+    texel: [f32; 2],
+}
+
+#[repr(C)]
+#[derive(Copy, Clone, Pod, Zeroable)]
+struct HudUniforms {
+    /// Left, top, right, bottom in clip space
+    rect: [f32; 4],
+}
+
+const MESH_SHADER: &str = include_str!("../shaders/displace.wgsl");
+
+/// Depth format shared by every mesh pipeline, so rotated meshes occlude
+/// themselves instead of overdrawing in vertex order
+const DEPTH_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Depth32Float;
+
+/// Window height per integer step of the HUD's pixel scale
+const HUD_SCALE_STEP: u32 = 540;
+/// HUD distance from the window corner, in unscaled pixels
+const HUD_MARGIN: u32 = 8;
+
+/// Mesh bind group entries, and the textures and samplers the vertex stage
+/// (the busier one) sees of them
+const MESH_BINDINGS: u32 = 9;
+const MESH_TEXTURES: u32 = 5;
+const MESH_SAMPLERS: u32 = 3;
+/// Smallest texture side the renderer accepts, enough for 1080p video
+const MIN_TEXTURE_DIMENSION: u32 = 2048;
+
+/// Smallest fraction of the surface size the scene can be rendered at
+pub const MIN_RENDER_SCALE: f32 = 0.25;
+
+/// Offscreen targets for video feedback: the mesh is drawn into `scene`, composited
+/// over the previous frame into one of the ping-pong `frames`, then blitted to the surface.
+/// Sized to the surface times the render scale and rebuilt on resize.
+struct FeedbackBuffers {
+    scene_view: wgpu::TextureView,
+    /// Depth for the mesh drawn into `scene`
+    scene_depth_view: wgpu::TextureView,
+    frame_views: [wgpu::TextureView; 2],
+    /// composite_bind_groups[i] writes frames[i] from scene + frames[1 - i]
+    composite_bind_groups: [wgpu::BindGroup; 2],
+    /// blit_bind_groups[i] copies frames[i] to the surface
+    blit_bind_groups: [wgpu::BindGroup; 2],
+    /// Frame holding the most recent composite
+    current: usize,
+    /// Frames hold stale or uninitialized data and must be cleared before use
+    needs_clear: bool,
+}
+
+impl FeedbackBuffers {
+    fn new(
+        device: &wgpu::Device,
+        layout: &wgpu::BindGroupLayout,
+        uniform_buffer: &wgpu::Buffer,
+        sampler: &wgpu::Sampler,
+        format: wgpu::TextureFormat,
+        width: u32,
+        height: u32,
+    ) -> Self {
+        let create_view = |label| {
+            device
+                .create_texture(&wgpu::TextureDescriptor {
+                    label: Some(label),
+                    size: wgpu::Extent3d {
+                        width,
+                        height,
+                        depth_or_array_layers: 1,
+                    },
+                    mip_level_count: 1,
+                    sample_count: 1,
+                    dimension: wgpu::TextureDimension::D2,
+                    format,
+                    usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::TEXTURE_BINDING,
+                    view_formats: &[],
+                })
+                .create_view(&wgpu::TextureViewDescriptor::default())
+        };
+
+        let scene_view = create_view("feedback_scene");
+        let scene_depth_view = create_depth_view(device, width, height);
+        let frame_views = [create_view("feedback_frame_0"), create_view("feedback_frame_1")];
+
+        let create_bind_group = |current: &wgpu::TextureView, previous: &wgpu::TextureView| {
+            device.create_bind_group(&wgpu::BindGroupDescriptor {
+                layout,
+                entries: &[
+                    wgpu::BindGroupEntry {
+                        binding: 0,
+                        resource: uniform_buffer.as_entire_binding(),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 1,
+                        resource: wgpu::BindingResource::TextureView(current),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 2,
+                        resource: wgpu::BindingResource::TextureView(previous),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 3,
+                        resource: wgpu::BindingResource::Sampler(sampler),
+                    },
+                ],
+                label: Some("feedback_bind_group"),
+            })
+        };
+
+        let composite_bind_groups = [
+            create_bind_group(&scene_view, &frame_views[1]),
+            create_bind_group(&scene_view, &frame_views[0]),
+        ];
+        let blit_bind_groups = [
+            create_bind_group(&frame_views[0], &frame_views[1]),
+            create_bind_group(&frame_views[1], &frame_views[0]),
+        ];
+
+        Self {
+            scene_view,
+            scene_depth_view,
+            frame_views,
+            composite_bind_groups,
+            blit_bind_groups,
+            current: 0,
+            needs_clear: true,
+        }
+    }
+}
+
+/// Depth buffer for a mesh pass into a `width` x `height` target
+fn create_depth_view(device: &wgpu::Device, width: u32, height: u32) -> wgpu::TextureView {
+    device
+        .create_texture(&wgpu::TextureDescriptor {
+            label: Some("Depth Texture"),
+            size: wgpu::Extent3d {
+                width,
+                height,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: DEPTH_FORMAT,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
+            view_formats: &[],
+        })
+        .create_view(&wgpu::TextureViewDescriptor::default())
+}
+
+/// Half-resolution ping-pong targets for the glow: the bright parts of the
+/// composited frame go into `views[0]`, are blurred across into `views[1]`,
+/// and back down into `views[0]`, which is added over the output.
+/// Rebuilt with the feedback buffers.
+struct BloomBuffers {
+    views: [wgpu::TextureView; 2],
+    /// bright_bind_groups[i] reads feedback frame i
+    bright_bind_groups: [wgpu::BindGroup; 2],
+    /// blur_bind_groups[i] reads views[i]
+    blur_bind_groups: [wgpu::BindGroup; 2],
+    width: u32,
+    height: u32,
+}
+
+impl BloomBuffers {
+    #[allow(clippy::too_many_arguments)]
+    fn new(
+        device: &wgpu::Device,
+        layout: &wgpu::BindGroupLayout,
+        uniform_buffer: &wgpu::Buffer,
+        sampler: &wgpu::Sampler,
+        format: wgpu::TextureFormat,
+        frame_views: &[wgpu::TextureView; 2],
+        width: u32,
+        height: u32,
+    ) -> Self {
+        let create_view = |label| {
+            device
+                .create_texture(&wgpu::TextureDescriptor {
+                    label: Some(label),
+                    size: wgpu::Extent3d {
+                        width,
+                        height,
+                        depth_or_array_layers: 1,
+                    },
+                    mip_level_count: 1,
+                    sample_count: 1,
+                    dimension: wgpu::TextureDimension::D2,
+                    format,
+                    usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::TEXTURE_BINDING,
+                    view_formats: &[],
+                })
+                .create_view(&wgpu::TextureViewDescriptor::default())
+        };
+        let views = [create_view("bloom_0"), create_view("bloom_1")];
+
+        let create_bind_group = |source: &wgpu::TextureView| {
+            device.create_bind_group(&wgpu::BindGroupDescriptor {
+                layout,
+                entries: &[
+                    wgpu::BindGroupEntry {
+                        binding: 0,
+                        resource: uniform_buffer.as_entire_binding(),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 1,
+                        resource: wgpu::BindingResource::TextureView(source),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 2,
+                        resource: wgpu::BindingResource::Sampler(sampler),
+                    },
+                ],
+                label: Some("bloom_bind_group"),
+            })
+        };
+        let bright_bind_groups = [create_bind_group(&frame_views[0]), create_bind_group(&frame_views[1])];
+        let blur_bind_groups = [create_bind_group(&views[0]), create_bind_group(&views[1])];
+
+        Self {
+            views,
+            bright_bind_groups,
+            blur_bind_groups,
+            width,
+            height,
+        }
+    }
+}
+
+/// Parameter overlay (`hud::Hud`) drawn over the finished frame in the top
+/// left corner, pixel-doubled on large windows
+struct HudLayer {
+    pipeline: wgpu::RenderPipeline,
+    bind_group_layout: wgpu::BindGroupLayout,
+    uniform_buffer: wgpu::Buffer,
+    sampler: wgpu::Sampler,
+    /// Overlay image and its bind group, from the first upload on
+    texture: Option<(wgpu::Texture, wgpu::BindGroup)>,
+    visible: bool,
+}
+
+impl HudLayer {
+    fn new(device: &wgpu::Device, format: wgpu::TextureFormat) -> Self {
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("HUD Shader"),
+            source: wgpu::ShaderSource::Wgsl(include_str!("../shaders/hud.wgsl").into()),
+        });
+        let uniform_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("HUD Uniform Buffer"),
+            size: std::mem::size_of::<HudUniforms>() as u64,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            mag_filter: wgpu::FilterMode::Nearest,
+            min_filter: wgpu::FilterMode::Nearest,
+            ..Default::default()
+        });
+
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::VERTEX,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        multisampled: false,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 2,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                    count: None,
+                },
+            ],
+            label: Some("hud_bind_group_layout"),
+        });
+        let layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("HUD Pipeline Layout"),
+            bind_group_layouts: &[&bind_group_layout],
+            push_constant_ranges: &[],
+        });
+        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("fs_hud"),
+            layout: Some(&layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: "vs_hud",
+                buffers: &[],
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: "fs_hud",
+                targets: &[Some(wgpu::ColorTargetState {
+                    format,
+                    blend: Some(wgpu::BlendState::PREMULTIPLIED_ALPHA_BLENDING),
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+            }),
+            primitive: wgpu::PrimitiveState::default(),
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState::default(),
+            multiview: None,
+        });
+
+        Self {
+            pipeline,
+            bind_group_layout,
+            uniform_buffer,
+            sampler,
+            texture: None,
+            visible: false,
+        }
+    }
+
+    /// Upload a new overlay image, replacing the texture if the size changed
+    fn upload(&mut self, device: &wgpu::Device, queue: &wgpu::Queue, rgba: &[u8], width: u32, height: u32) {
+        let size = wgpu::Extent3d {
+            width,
+            height,
+            depth_or_array_layers: 1,
+        };
+        if self.texture.as_ref().map(|(texture, _)| texture.size()) != Some(size) {
+            let texture = device.create_texture(&wgpu::TextureDescriptor {
+                label: Some("hud"),
+                size,
+                mip_level_count: 1,
+                sample_count: 1,
+                dimension: wgpu::TextureDimension::D2,
+                // Not sRGB: the image is already what should land on screen
+                format: wgpu::TextureFormat::Rgba8Unorm,
+                usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+                view_formats: &[],
+            });
+            let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+            let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+                layout: &self.bind_group_layout,
+                entries: &[
+                    wgpu::BindGroupEntry {
+                        binding: 0,
+                        resource: self.uniform_buffer.as_entire_binding(),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 1,
+                        resource: wgpu::BindingResource::TextureView(&view),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 2,
+                        resource: wgpu::BindingResource::Sampler(&self.sampler),
+                    },
+                ],
+                label: Some("hud_bind_group"),
+            });
+            self.texture = Some((texture, bind_group));
+        }
+        if let Some((texture, _)) = &self.texture {
+            queue.write_texture(
+                texture.as_image_copy(),
+                rgba,
+                wgpu::ImageDataLayout {
+                    offset: 0,
+                    bytes_per_row: Some(width * 4),
+                    rows_per_image: Some(height),
+                },
+                size,
+            );
+        }
+    }
+
+    /// Draw over `target`, a view of `target_width` x `target_height`
+    fn draw(
+        &self,
+        queue: &wgpu::Queue,
+        encoder: &mut wgpu::CommandEncoder,
+        target: &wgpu::TextureView,
+        target_width: u32,
+        target_height: u32,
+    ) {
+        let Some((texture, bind_group)) = self.texture.as_ref().filter(|_| self.visible) else {
+            return;
+        };
+        let scale = (target_height / HUD_SCALE_STEP).max(1);
+        let left = HUD_MARGIN * scale;
+        let right = left + texture.width() * scale;
+        let bottom = left + texture.height() * scale;
+        let clip_x = |x: u32| 2.0 * x as f32 / target_width as f32 - 1.0;
+        let clip_y = |y: u32| 1.0 - 2.0 * y as f32 / target_height as f32;
+        let uniforms = HudUniforms {
+            rect: [clip_x(left), clip_y(left), clip_x(right), clip_y(bottom)],
+        };
+        queue.write_buffer(&self.uniform_buffer, 0, bytemuck::cast_slice(&[uniforms]));
+
+        let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("HUD Pass"),
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view: target,
+                resolve_target: None,
+                ops: wgpu::Operations {
+                    load: wgpu::LoadOp::Load,
+                    store: wgpu::StoreOp::Store,
+                },
+            })],
+            depth_stencil_attachment: None,
+            occlusion_query_set: None,
+            timestamp_writes: None,
+        });
+        render_pass.set_pipeline(&self.pipeline);
+        render_pass.set_bind_group(0, bind_group, &[]);
+        render_pass.draw(0..6, 0..1);
+    }
+}
+
+/// Readback buffers a frame in flight can be copied into. Three cover a frame
+/// being mapped, one waiting for its consumer and one being consumed.
+const CAPTURE_SLOTS: usize = 3;
+
+/// `CaptureSlot::state` values
+const SLOT_IDLE: u8 = 0;
+const SLOT_MAPPING: u8 = 1;
+const SLOT_READY: u8 = 2;
+const SLOT_TAKEN: u8 = 3;
+
+/// Presented frames copied into a ring of readback buffers and mapped
+/// asynchronously, so they arrive a frame or two late instead of stalling
+/// the render loop. Rebuilt when the output size changes.
+struct FrameCapture {
+    slots: Vec<CaptureSlot>,
+    width: u32,
+    height: u32,
+    /// Frames copied so far, which orders the slots
+    frames: u64,
+}
+
+struct CaptureSlot {
+    buffer: Arc<wgpu::Buffer>,
+    /// SLOT_IDLE until copied into, SLOT_MAPPING until the map completes,
+    /// then SLOT_READY, and SLOT_TAKEN while a `CapturedFrame` holds it
+    state: Arc<AtomicU8>,
+    frame: u64,
+}
+
+impl FrameCapture {
+    fn new(device: &wgpu::Device, width: u32, height: u32) -> Self {
+        let slots = (0..CAPTURE_SLOTS)
+            .map(|_| CaptureSlot {
+                buffer: Arc::new(device.create_buffer(&wgpu::BufferDescriptor {
+                    label: Some("Capture Buffer"),
+                    size: (padded_row_bytes(width) * height) as u64,
+                    usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+                    mapped_at_creation: false,
+                })),
+                state: Arc::new(AtomicU8::new(SLOT_IDLE)),
+                frame: 0,
+            })
+            .collect();
+        Self {
+            slots,
+            width,
+            height,
+            frames: 0,
+        }
+    }
+
+    /// Copy `texture` into a free slot and return the slot to map once the copy
+    /// is submitted. None, dropping the frame, while every slot is busy.
+    fn record(&mut self, encoder: &mut wgpu::CommandEncoder, texture: &wgpu::Texture) -> Option<usize> {
+        let index = self.slots.iter().position(|slot| slot.state.load(Ordering::Acquire) == SLOT_IDLE)?;
+        let slot = &mut self.slots[index];
+        encoder.copy_texture_to_buffer(
+            texture.as_image_copy(),
+            wgpu::ImageCopyBuffer {
+                buffer: &slot.buffer,
+                layout: wgpu::ImageDataLayout {
+                    offset: 0,
+                    bytes_per_row: Some(padded_row_bytes(self.width)),
+                    rows_per_image: Some(self.height),
+                },
+            },
+            wgpu::Extent3d {
+                width: self.width,
+                height: self.height,
+                depth_or_array_layers: 1,
+            },
+        );
+        self.frames += 1;
+        slot.frame = self.frames;
+        slot.state.store(SLOT_MAPPING, Ordering::Release);
+        Some(index)
+    }
+
+    /// Start mapping a slot filled by `record`, after its copy was submitted
+    fn map(&self, index: usize) {
+        let state = Arc::clone(&self.slots[index].state);
+        self.slots[index].buffer.slice(..).map_async(wgpu::MapMode::Read, move |result| {
+            state.store(if result.is_ok() { SLOT_READY } else { SLOT_IDLE }, Ordering::Release);
+        });
+    }
+
+    /// The newest mapped frame; older mapped ones are released unread
+    fn take(&mut self, bgra: bool) -> Option<CapturedFrame> {
+        let mut ready: Vec<&CaptureSlot> =
+            self.slots.iter().filter(|slot| slot.state.load(Ordering::Acquire) == SLOT_READY).collect();
+        ready.sort_by_key(|slot| slot.frame);
+        let newest = ready.pop()?;
+        for stale in ready {
+            stale.buffer.unmap();
+            stale.state.store(SLOT_IDLE, Ordering::Release);
+        }
+        newest.state.store(SLOT_TAKEN, Ordering::Release);
+        Some(CapturedFrame {
+            buffer: Arc::clone(&newest.buffer),
+            state: Arc::clone(&newest.state),
+            width: self.width,
+            height: self.height,
+            bgra,
+        })
+    }
+}
+
+/// A presented frame in mapped GPU memory, from `Renderer::take_captured_frame`.
+/// Can be handed to another thread; its buffer goes back to the renderer on drop.
+pub struct CapturedFrame {
+    buffer: Arc<wgpu::Buffer>,
+    state: Arc<AtomicU8>,
+    width: u32,
+    height: u32,
+    bgra: bool,
+}
+
+impl CapturedFrame {
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    /// Pixels as tightly packed RGBA8, top row first
+    pub fn to_rgba(&self) -> Vec<u8> {
+        let data = self.buffer.slice(..).get_mapped_range();
+        unpad_rows(&data, self.width, self.height, self.bgra)
+    }
+}
+
+impl Drop for CapturedFrame {
+    fn drop(&mut self) {
+        self.buffer.unmap();
+        self.state.store(SLOT_IDLE, Ordering::Release);
+    }
+}
+
+/// Surface present modes selectable from the command line
+#[derive(Clone, Copy, Debug, PartialEq, Eq, clap::ValueEnum)]
+pub enum PresentMode {
+    /// Vsync, picks the best supported vsync mode
+    AutoVsync,
+    /// No vsync, picks Immediate or Mailbox when supported
+    AutoNoVsync,
+    /// Classic vsync, always supported
+    Fifo,
+    /// Uncapped, may tear
+    Immediate,
+    /// Uncapped without tearing, drops stale frames
+    Mailbox,
+}
+
+impl PresentMode {
+    pub fn to_wgpu(self) -> wgpu::PresentMode {
+        match self {
+            PresentMode::AutoVsync => wgpu::PresentMode::AutoVsync,
+            PresentMode::AutoNoVsync => wgpu::PresentMode::AutoNoVsync,
+            PresentMode::Fifo => wgpu::PresentMode::Fifo,
+            PresentMode::Immediate => wgpu::PresentMode::Immediate,
+            PresentMode::Mailbox => wgpu::PresentMode::Mailbox,
+        }
+    }
+
+    /// Next mode in the runtime cycle (Fifo is covered by AutoVsync)
+    pub fn next(self) -> Self {
+        match self {
+            PresentMode::AutoVsync => PresentMode::AutoNoVsync,
+            PresentMode::AutoNoVsync => PresentMode::Immediate,
+            PresentMode::Immediate => PresentMode::Mailbox,
+            PresentMode::Mailbox | PresentMode::Fifo => PresentMode::AutoVsync,
+        }
+    }
 }
 
 pub struct Renderer {
-    surface: wgpu::Surface<'static>,
+    // None for headless renderers, which only draw with `render_to`
+    surface: Option<wgpu::Surface<'static>>,
     device: wgpu::Device,
     queue: wgpu::Queue,
     config: wgpu::SurfaceConfiguration,
+    present_modes: Vec<wgpu::PresentMode>,
+    alpha_modes: Vec<wgpu::CompositeAlphaMode>,
+    // Background behind the mesh (linear, premultiplied)
+    clear_color: wgpu::Color,
     render_pipeline_triangles: wgpu::RenderPipeline,
     render_pipeline_lines: wgpu::RenderPipeline,
+    // Quads of anti-aliased lines, the only ones paying for edge coverage
+    render_pipeline_smooth_lines: wgpu::RenderPipeline,
+    // Kept to rebuild the mesh pipelines with another shader (effect chains)
+    pipeline_layout: wgpu::PipelineLayout,
     vertex_buffer: wgpu::Buffer,
     vertex_count: u32,
     uniform_buffer: wgpu::Buffer,
     bind_group: wgpu::BindGroup,
     bind_group_layout: wgpu::BindGroupLayout,
     video_texture: wgpu::Texture,
+    video_texture2: wgpu::Texture,
     x_noise_texture: wgpu::Texture,
     y_noise_texture: wgpu::Texture,
     z_noise_texture: wgpu::Texture,
     sampler: wgpu::Sampler,
-    current_mesh_type: MeshType,
+    // Point sampling for the upscale blit with ResizeFilter::Nearest
+    nearest_sampler: wgpu::Sampler,
+    topology: wgpu::PrimitiveTopology,
+    // Mesh quads need the smooth line pipeline
+    smooth_edges: bool,
+    // Video feedback (trails)
+    feedback_decay: f32,
+    feedback_uniform_buffer: wgpu::Buffer,
+    feedback_bind_group_layout: wgpu::BindGroupLayout,
+    feedback_pipeline: wgpu::RenderPipeline,
+    blit_pipeline: wgpu::RenderPipeline,
+    feedback: FeedbackBuffers,
+    // Glow around bright areas, drawn from the feedback frame when active
+    bloom_active: bool,
+    bloom_format: wgpu::TextureFormat,
+    bloom_uniform_buffer: wgpu::Buffer,
+    bloom_bind_group_layout: wgpu::BindGroupLayout,
+    bloom_bright_pipeline: wgpu::RenderPipeline,
+    bloom_blur_h_pipeline: wgpu::RenderPipeline,
+    bloom_blur_v_pipeline: wgpu::RenderPipeline,
+    bloom_add_pipeline: wgpu::RenderPipeline,
+    bloom: BloomBuffers,
+    // Depth for the mesh drawn straight to the output, at the output size
+    depth_view: wgpu::TextureView,
+    hud: HudLayer,
+    // Fraction of the surface size the scene is drawn at, upscaled by the blit
+    render_scale: f32,
+    upscale_filter: ResizeFilter,
     pub size: winit::dpi::PhysicalSize<u32>,
     // Video/source dimensions for aspect ratio
     pub video_width: u32,
     pub video_height: u32,
+    // Second video source dimensions (crossfaded via video_mix)
+    video2_width: u32,
+    video2_height: u32,
+    video2_active: bool,
+    // Noise texture dimensions, shared by the three axes
+    noise_width: u32,
+    noise_height: u32,
+    // Native aspect ratio of the capture device, if it differs from the video size
+    source_aspect: Option<f32>,
+    // Usages the window surface supports, for copying frames out of it
+    surface_usages: wgpu::TextureUsages,
+    // Presented frames read back for an output like NDI
+    capture: Option<FrameCapture>,
 }
 
 impl Renderer {
-    pub async fn new(window: std::sync::Arc<winit::window::Window>) -> Self {
+    pub async fn new(window: std::sync::Arc<winit::window::Window>) -> Result<Self, String> {
         let size = window.inner_size();
 
-        let instance = wgpu::Instance::new(wgpu::InstanceDescriptor {
-            backends: wgpu::Backends::all(),
-            ..Default::default()
-        });
+        let instance = Self::create_instance();
+        let surface = instance
+            .create_surface(window)
+            .map_err(|e| format!("Failed to create window surface: {}", e))?;
+        let adapter = Self::request_adapter(&instance, Some(&surface)).await?;
 
-        let surface = instance.create_surface(window).unwrap();
-
-        let adapter = instance
-            .request_adapter(&wgpu::RequestAdapterOptions {
-                power_preference: wgpu::PowerPreference::HighPerformance,
-                compatible_surface: Some(&surface),
-                force_fallback_adapter: false,
-            })
-            .await
-            .unwrap();
-
-        log::info!("Using adapter: {:?}", adapter.get_info());
-
-        let (device, queue) = adapter
-            .request_device(
-                &wgpu::DeviceDescriptor {
-                    label: None,
-                    required_features: wgpu::Features::empty(),
-                    required_limits: wgpu::Limits::default(),
-                },
-                None,
-            )
-            .await
-            .unwrap();
+        let (device, queue) = Self::request_device(&adapter).await?;
+        let (width, height) = Self::fit_texture_limit(&device, size.width, size.height);
 
         let surface_caps = surface.get_capabilities(&adapter);
         let surface_format = surface_caps
@@ -112,26 +803,258 @@ impl Renderer {
         let config = wgpu::SurfaceConfiguration {
             usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
             format: surface_format,
-            width: size.width,
-            height: size.height,
+            width,
+            height,
             present_mode: wgpu::PresentMode::AutoVsync,
             alpha_mode: surface_caps.alpha_modes[0],
             view_formats: vec![],
             desired_maximum_frame_latency: 2,
         };
         surface.configure(&device, &config);
+        let bloom_format = Self::bloom_format(&adapter, surface_format);
+
+        let mut renderer = Self::with_target(
+            device,
+            queue,
+            Some(surface),
+            config,
+            surface_caps.present_modes,
+            surface_caps.alpha_modes,
+            bloom_format,
+        );
+        renderer.size = size;
+        renderer.surface_usages = surface_caps.usages;
+        Ok(renderer)
+    }
+
+    /// Renderer without a window, drawing only into caller-provided views
+    /// (`render_to`) of `format()` at the given size
+    pub async fn new_headless(width: u32, height: u32) -> Result<Self, String> {
+        let instance = Self::create_instance();
+        let adapter = Self::request_adapter(&instance, None).await?;
+
+        let (device, queue) = Self::request_device(&adapter).await?;
+        let (width, height) = Self::fit_texture_limit(&device, width.max(1), height.max(1));
+
+        // Only format and size matter without a surface
+        let config = wgpu::SurfaceConfiguration {
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
+            format: wgpu::TextureFormat::Rgba8UnormSrgb,
+            width,
+            height,
+            present_mode: wgpu::PresentMode::Fifo,
+            alpha_mode: wgpu::CompositeAlphaMode::Opaque,
+            view_formats: vec![],
+            desired_maximum_frame_latency: 2,
+        };
+        let alpha_modes = vec![wgpu::CompositeAlphaMode::Opaque, wgpu::CompositeAlphaMode::PreMultiplied];
+        let bloom_format = Self::bloom_format(&adapter, config.format);
+
+        Ok(Self::with_target(device, queue, None, config, vec![wgpu::PresentMode::Fifo], alpha_modes, bloom_format))
+    }
+
+    /// Every backend, or those named in WGPU_BACKEND (e.g. "vulkan", "gl")
+    fn create_instance() -> wgpu::Instance {
+        wgpu::Instance::new(wgpu::InstanceDescriptor {
+            backends: wgpu::util::backend_bits_from_env().unwrap_or(wgpu::Backends::all()),
+            ..Default::default()
+        })
+    }
+
+    /// The discrete GPU if there is one, else an integrated GPU, else a
+    /// software adapter (e.g. llvmpipe or WARP) as the last resort
+    async fn request_adapter(
+        instance: &wgpu::Instance,
+        surface: Option<&wgpu::Surface<'_>>,
+    ) -> Result<wgpu::Adapter, String> {
+        let attempts = [
+            (wgpu::PowerPreference::HighPerformance, false),
+            (wgpu::PowerPreference::LowPower, false),
+            (wgpu::PowerPreference::None, true),
+        ];
+        for (power_preference, force_fallback_adapter) in attempts {
+            let options = wgpu::RequestAdapterOptions {
+                power_preference,
+                compatible_surface: surface,
+                force_fallback_adapter,
+            };
+            if let Some(adapter) = instance.request_adapter(&options).await {
+                if force_fallback_adapter {
+                    log::warn!("No hardware GPU adapter, using the software fallback (slow)");
+                }
+                return Ok(adapter);
+            }
+        }
+
+        let target = if surface.is_some() { " for the window" } else { "" };
+        Err(format!(
+            "No GPU adapter found{} (tried high-performance, low-power and software adapters). \
+             Check that a Vulkan, Metal, DX12 or OpenGL driver is installed, or pick a backend \
+             with WGPU_BACKEND=vulkan|metal|dx12|gl",
+            target
+        ))
+    }
+
+    async fn request_device(adapter: &wgpu::Adapter) -> Result<(wgpu::Device, wgpu::Queue), String> {
+        log::info!("Using adapter: {:?}", adapter.get_info());
+        let required_limits = Self::device_limits(adapter)?;
+
+        adapter
+            .request_device(
+                &wgpu::DeviceDescriptor {
+                    label: None,
+                    required_features: wgpu::Features::empty(),
+                    required_limits,
+                },
+                None,
+            )
+            .await
+            .map_err(|e| format!("Failed to create GPU device: {}", e))
+    }
+
+    /// wgpu's default limits, lowered to what the adapter supports. Older and
+    /// embedded GPUs (the Pi among them) fall short of the defaults in places
+    /// the renderer doesn't need; only the limits it does need are checked.
+    fn device_limits(adapter: &wgpu::Adapter) -> Result<wgpu::Limits, String> {
+        let supported = adapter.limits();
+        let mut limits = wgpu::Limits::default();
+        let mut reduced = Vec::new();
+
+        // Every field of wgpu::Limits: max_* are capped by the adapter, min_*
+        // alignments raised to it
+        macro_rules! fit {
+            ($($name:ident: $pick:ident),* $(,)?) => {$(
+                let fitted = limits.$name.$pick(supported.$name);
+                if fitted != limits.$name {
+                    reduced.push(format!("{} {} -> {}", stringify!($name), limits.$name, fitted));
+                    limits.$name = fitted;
+                }
+            )*};
+        }
+        fit!(
+            max_texture_dimension_1d: min,
+            max_texture_dimension_2d: min,
+            max_texture_dimension_3d: min,
+            max_texture_array_layers: min,
+            max_bind_groups: min,
+            max_bindings_per_bind_group: min,
+            max_dynamic_uniform_buffers_per_pipeline_layout: min,
+            max_dynamic_storage_buffers_per_pipeline_layout: min,
+            max_sampled_textures_per_shader_stage: min,
+            max_samplers_per_shader_stage: min,
+            max_storage_buffers_per_shader_stage: min,
+            max_storage_textures_per_shader_stage: min,
+            max_uniform_buffers_per_shader_stage: min,
+            max_uniform_buffer_binding_size: min,
+            max_storage_buffer_binding_size: min,
+            max_vertex_buffers: min,
+            max_vertex_attributes: min,
+            max_vertex_buffer_array_stride: min,
+            max_push_constant_size: min,
+            min_uniform_buffer_offset_alignment: max,
+            min_storage_buffer_offset_alignment: max,
+            max_inter_stage_shader_components: min,
+            max_compute_workgroup_storage_size: min,
+            max_compute_invocations_per_workgroup: min,
+            max_compute_workgroup_size_x: min,
+            max_compute_workgroup_size_y: min,
+            max_compute_workgroup_size_z: min,
+            max_compute_workgroups_per_dimension: min,
+            max_buffer_size: min,
+            max_non_sampler_bindings: min,
+        );
+        if !reduced.is_empty() {
+            log::warn!("GPU limits below the wgpu defaults: {}", reduced.join(", "));
+        }
+
+        // What the mesh pipeline actually binds
+        let needed = [
+            ("max_bind_groups", 1, limits.max_bind_groups),
+            ("max_bindings_per_bind_group", MESH_BINDINGS, limits.max_bindings_per_bind_group),
+            ("max_sampled_textures_per_shader_stage", MESH_TEXTURES, limits.max_sampled_textures_per_shader_stage),
+            ("max_samplers_per_shader_stage", MESH_SAMPLERS, limits.max_samplers_per_shader_stage),
+            (
+                "max_uniform_buffer_binding_size",
+                std::mem::size_of::<Uniforms>() as u32,
+                limits.max_uniform_buffer_binding_size,
+            ),
+            ("max_texture_dimension_2d", MIN_TEXTURE_DIMENSION, limits.max_texture_dimension_2d),
+        ];
+        for (name, need, allowed) in needed {
+            if allowed < need {
+                return Err(format!(
+                    "GPU '{}' is not supported: {} is {}, the renderer needs at least {}",
+                    adapter.get_info().name,
+                    name,
+                    allowed,
+                    need
+                ));
+            }
+        }
+        Ok(limits)
+    }
+
+    /// Half-float bloom buffers where the adapter can render to and filter them,
+    /// so the glow keeps its range and doesn't band; `fallback` otherwise
+    fn bloom_format(adapter: &wgpu::Adapter, fallback: wgpu::TextureFormat) -> wgpu::TextureFormat {
+        let format = wgpu::TextureFormat::Rgba16Float;
+        let features = adapter.get_texture_format_features(format);
+        let usable = features
+            .allowed_usages
+            .contains(wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::TEXTURE_BINDING)
+            && features.flags.contains(wgpu::TextureFormatFeatureFlags::FILTERABLE);
+        if usable {
+            format
+        } else {
+            fallback
+        }
+    }
+
+    /// `width` x `height` scaled down to fit the device's largest texture, keeping
+    /// the aspect ratio. Oversized windows are drawn smaller and stretched.
+    fn fit_texture_limit(device: &wgpu::Device, width: u32, height: u32) -> (u32, u32) {
+        let limit = device.limits().max_texture_dimension_2d;
+        let longest = width.max(height);
+        if longest <= limit {
+            return (width, height);
+        }
+        let fit = |side: u32| ((side as u64 * limit as u64 / longest as u64) as u32).max(1);
+        log::warn!(
+            "{}x{} is above the GPU's {} pixel texture limit, rendering at {}x{}",
+            width,
+            height,
+            limit,
+            fit(width),
+            fit(height)
+        );
+        (fit(width), fit(height))
+    }
+
+    /// Everything past device and surface setup, shared by windowed and headless renderers
+    fn with_target(
+        device: wgpu::Device,
+        queue: wgpu::Queue,
+        surface: Option<wgpu::Surface<'static>>,
+        config: wgpu::SurfaceConfiguration,
+        present_modes: Vec<wgpu::PresentMode>,
+        alpha_modes: Vec<wgpu::CompositeAlphaMode>,
+        bloom_format: wgpu::TextureFormat,
+    ) -> Self {
+        let size = winit::dpi::PhysicalSize::new(config.width, config.height);
+        let surface_format = config.format;
 
         // Create shader module
         let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
             label: Some("Displacement Shader"),
-            source: wgpu::ShaderSource::Wgsl(include_str!("../shaders/displace.wgsl").into()),
+            source: wgpu::ShaderSource::Wgsl(MESH_SHADER.into()),
         });
 
         // Create textures
         let video_texture = Self::create_texture(&device, 640, 480, "video");
-        let x_noise_texture = Self::create_texture(&device, 180, 120, "x_noise");
-        let y_noise_texture = Self::create_texture(&device, 180, 120, "y_noise");
-        let z_noise_texture = Self::create_texture(&device, 180, 120, "z_noise");
+        let video_texture2 = Self::create_texture(&device, 640, 480, "video2");
+        let x_noise_texture = Self::create_texture(&device, DEFAULT_NOISE_WIDTH, DEFAULT_NOISE_HEIGHT, "x_noise");
+        let y_noise_texture = Self::create_texture(&device, DEFAULT_NOISE_WIDTH, DEFAULT_NOISE_HEIGHT, "y_noise");
+        let z_noise_texture = Self::create_texture(&device, DEFAULT_NOISE_WIDTH, DEFAULT_NOISE_HEIGHT, "z_noise");
 
         let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
             address_mode_u: wgpu::AddressMode::ClampToEdge,
@@ -142,12 +1065,21 @@ impl Renderer {
             mipmap_filter: wgpu::FilterMode::Nearest,
             ..Default::default()
         });
+        let nearest_sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            address_mode_u: wgpu::AddressMode::ClampToEdge,
+            address_mode_v: wgpu::AddressMode::ClampToEdge,
+            address_mode_w: wgpu::AddressMode::ClampToEdge,
+            mag_filter: wgpu::FilterMode::Nearest,
+            min_filter: wgpu::FilterMode::Nearest,
+            mipmap_filter: wgpu::FilterMode::Nearest,
+            ..Default::default()
+        });
 
         // Create uniform buffer
         let uniforms = Uniforms {
             mvp: Mat4::IDENTITY.to_cols_array_2d(),
             xy: [0.0, 0.0],
-            xy_offset: [0.0, 0.0],
+            pivot: [0.0, 0.0],
             x_lfo_arg: 0.0,
             x_lfo_amp: 0.0,
             x_lfo_other: 0.0,
@@ -158,19 +1090,10 @@ impl Renderer {
             z_lfo_amp: 0.0,
             z_lfo_other: 0.0,
             luma_key_level: 0.0,
-            invert_switch: 0.0,
-            b_w_switch: 0.0,
-            bright_switch: 0,
+            effect_flags: 0,
             x_lfo_shape: 0,
             y_lfo_shape: 0,
             z_lfo_shape: 0,
-            x_ringmod_switch: 0,
-            y_ringmod_switch: 0,
-            z_ringmod_switch: 0,
-            x_phasemod_switch: 0,
-            y_phasemod_switch: 0,
-            z_phasemod_switch: 0,
-            luma_switch: 0,
             width: 640,
             height: 480,
             audio_displacement: 0.0,
@@ -178,7 +1101,31 @@ impl Renderer {
             audio_wave_phase: 0.0,
             audio_wave_amp: 0.0,
             audio_wave_freq: 10.0,
-            _pad: [0.0; 6],
+            video_mix: 0.0,
+            chroma_split: 0.0,
+            x_source: 0,
+            y_source: 0,
+            z_source: 0,
+            audio_level: 0.0,
+            _align: 0.0,
+            ripples: [[0.0; 4]; MAX_RIPPLES],
+            ripple_shape: 0,
+            audio_displace_x: 0.0,
+            audio_displace_y: 0.0,
+            ripple_count: 0,
+            extra_lfos: [[0.0; 4]; EXTRA_LFOS],
+            input_exposure: 1.0,
+            input_gamma: 1.0,
+            flash: 0.0,
+            flash_mode: 0,
+            mesh_smoothing: 0.0,
+            grid_cells: 1.0,
+            output_level: 1.0,
+            x_lfo_bias: 0.0,
+            y_lfo_bias: 0.0,
+            z_lfo_bias: 0.0,
+            _pad: [0.0; 2],
+            noise_amp: [1.0, 1.0, 1.0, 0.0],
         };
 
         let uniform_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
@@ -252,6 +1199,22 @@ impl Renderer {
                     ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
                     count: None,
                 },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 7,
+                    visibility: wgpu::ShaderStages::VERTEX | wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        multisampled: false,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 8,
+                    visibility: wgpu::ShaderStages::VERTEX | wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                    count: None,
+                },
             ],
             label: Some("bind_group_layout"),
         });
@@ -261,6 +1224,7 @@ impl Renderer {
             &bind_group_layout,
             &uniform_buffer,
             &video_texture,
+            &video_texture2,
             &x_noise_texture,
             &y_noise_texture,
             &z_noise_texture,
@@ -274,13 +1238,14 @@ impl Renderer {
             push_constant_ranges: &[],
         });
 
-        // Create render pipelines (one for triangles, one for lines)
+        // Create render pipelines (triangles, hard lines, smooth line quads)
         let render_pipeline_triangles = Self::create_pipeline(
             &device,
             &pipeline_layout,
             &shader,
             surface_format,
             wgpu::PrimitiveTopology::TriangleList,
+            ("vs_main", "fs_main"),
         );
 
         let render_pipeline_lines = Self::create_pipeline(
@@ -289,8 +1254,179 @@ impl Renderer {
             &shader,
             surface_format,
             wgpu::PrimitiveTopology::LineList,
+            ("vs_main", "fs_main"),
+        );
+
+        let render_pipeline_smooth_lines = Self::create_pipeline(
+            &device,
+            &pipeline_layout,
+            &shader,
+            surface_format,
+            wgpu::PrimitiveTopology::TriangleList,
+            ("vs_smooth_line", "fs_smooth_line"),
+        );
+
+        // Feedback composite and blit passes
+        let feedback_shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Feedback Shader"),
+            source: wgpu::ShaderSource::Wgsl(include_str!("../shaders/feedback.wgsl").into()),
+        });
+
+        let feedback_uniform_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Feedback Uniform Buffer"),
+            contents: bytemuck::cast_slice(&[FeedbackUniforms {
+                decay: 0.0,
+                _pad: [0.0; 3],
+            }]),
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        });
+
+        let feedback_texture_entry = |binding| wgpu::BindGroupLayoutEntry {
+            binding,
+            visibility: wgpu::ShaderStages::FRAGMENT,
+            ty: wgpu::BindingType::Texture {
+                sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                view_dimension: wgpu::TextureViewDimension::D2,
+                multisampled: false,
+            },
+            count: None,
+        };
+        let feedback_bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+                feedback_texture_entry(1),
+                feedback_texture_entry(2),
+                wgpu::BindGroupLayoutEntry {
+                    binding: 3,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                    count: None,
+                },
+            ],
+            label: Some("feedback_bind_group_layout"),
+        });
+
+        let feedback_pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Feedback Pipeline Layout"),
+            bind_group_layouts: &[&feedback_bind_group_layout],
+            push_constant_ranges: &[],
+        });
+
+        let feedback_pipeline = Self::create_fullscreen_pipeline(
+            &device,
+            &feedback_pipeline_layout,
+            &feedback_shader,
+            "fs_composite",
+            surface_format,
+            None,
+        );
+        let blit_pipeline = Self::create_fullscreen_pipeline(
+            &device,
+            &feedback_pipeline_layout,
+            &feedback_shader,
+            "fs_blit",
+            surface_format,
+            Some(wgpu::BlendState::PREMULTIPLIED_ALPHA_BLENDING),
+        );
+
+        let feedback = FeedbackBuffers::new(
+            &device,
+            &feedback_bind_group_layout,
+            &feedback_uniform_buffer,
+            &sampler,
+            surface_format,
+            size.width.max(1),
+            size.height.max(1),
+        );
+
+        // Bloom: bright pass, blur across and down, added over the output
+        let bloom_shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Bloom Shader"),
+            source: wgpu::ShaderSource::Wgsl(include_str!("../shaders/bloom.wgsl").into()),
+        });
+
+        let bloom_uniform_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Bloom Uniform Buffer"),
+            contents: bytemuck::cast_slice(&[BloomUniforms {
+                threshold: 1.0,
+                intensity: 0.0,
+                texel: [0.0; 2],
+            }]),
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        });
+
+        let bloom_bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+                feedback_texture_entry(1),
+                wgpu::BindGroupLayoutEntry {
+                    binding: 2,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                    count: None,
+                },
+            ],
+            label: Some("bloom_bind_group_layout"),
+        });
+
+        let bloom_pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Bloom Pipeline Layout"),
+            bind_group_layouts: &[&bloom_bind_group_layout],
+            push_constant_ranges: &[],
+        });
+
+        let bloom_pipeline = |entry, format, blend| {
+            Self::create_fullscreen_pipeline(&device, &bloom_pipeline_layout, &bloom_shader, entry, format, blend)
+        };
+        let bloom_bright_pipeline = bloom_pipeline("fs_bright", bloom_format, None);
+        let bloom_blur_h_pipeline = bloom_pipeline("fs_blur_h", bloom_format, None);
+        let bloom_blur_v_pipeline = bloom_pipeline("fs_blur_v", bloom_format, None);
+        let additive = wgpu::BlendComponent {
+            src_factor: wgpu::BlendFactor::One,
+            dst_factor: wgpu::BlendFactor::One,
+            operation: wgpu::BlendOperation::Add,
+        };
+        let bloom_add_pipeline = bloom_pipeline(
+            "fs_add",
+            surface_format,
+            Some(wgpu::BlendState {
+                color: additive,
+                alpha: additive,
+            }),
         );
 
+        let bloom = BloomBuffers::new(
+            &device,
+            &bloom_bind_group_layout,
+            &bloom_uniform_buffer,
+            &sampler,
+            bloom_format,
+            &feedback.frame_views,
+            (size.width / 2).max(1),
+            (size.height / 2).max(1),
+        );
+
+        let depth_view = create_depth_view(&device, config.width, config.height);
+        let hud = HudLayer::new(&device, surface_format);
+
         // Create initial mesh
         let mesh = Mesh::triangle_mesh(100, 640.0, 480.0);
         let vertex_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
@@ -304,22 +1440,57 @@ impl Renderer {
             device,
             queue,
             config,
+            present_modes,
+            alpha_modes,
+            clear_color: wgpu::Color::BLACK,
             render_pipeline_triangles,
             render_pipeline_lines,
+            render_pipeline_smooth_lines,
+            pipeline_layout,
             vertex_buffer,
             vertex_count: mesh.vertices.len() as u32,
             uniform_buffer,
             bind_group,
             bind_group_layout,
             video_texture,
+            video_texture2,
             x_noise_texture,
             y_noise_texture,
             z_noise_texture,
             sampler,
-            current_mesh_type: MeshType::Triangles,
+            nearest_sampler,
+            topology: mesh.primitive_topology(),
+            smooth_edges: mesh.smooth_edges,
+            feedback_decay: 0.0,
+            feedback_uniform_buffer,
+            feedback_bind_group_layout,
+            feedback_pipeline,
+            blit_pipeline,
+            feedback,
+            bloom_active: false,
+            bloom_format,
+            bloom_uniform_buffer,
+            bloom_bind_group_layout,
+            bloom_bright_pipeline,
+            bloom_blur_h_pipeline,
+            bloom_blur_v_pipeline,
+            bloom_add_pipeline,
+            bloom,
+            depth_view,
+            hud,
+            render_scale: 1.0,
+            upscale_filter: ResizeFilter::Bilinear,
             size,
             video_width: 640,
             video_height: 480,
+            video2_width: 640,
+            video2_height: 480,
+            video2_active: false,
+            noise_width: DEFAULT_NOISE_WIDTH,
+            noise_height: DEFAULT_NOISE_HEIGHT,
+            source_aspect: None,
+            surface_usages: wgpu::TextureUsages::RENDER_ATTACHMENT,
+            capture: None,
         }
     }
 
@@ -340,11 +1511,13 @@ impl Renderer {
         })
     }
 
+    #[allow(clippy::too_many_arguments)]
     fn create_bind_group(
         device: &wgpu::Device,
         layout: &wgpu::BindGroupLayout,
         uniform_buffer: &wgpu::Buffer,
         video_texture: &wgpu::Texture,
+        video_texture2: &wgpu::Texture,
         x_noise_texture: &wgpu::Texture,
         y_noise_texture: &wgpu::Texture,
         z_noise_texture: &wgpu::Texture,
@@ -389,32 +1562,71 @@ impl Renderer {
                     binding: 6,
                     resource: wgpu::BindingResource::Sampler(sampler),
                 },
+                wgpu::BindGroupEntry {
+                    binding: 7,
+                    resource: wgpu::BindingResource::TextureView(
+                        &video_texture2.create_view(&wgpu::TextureViewDescriptor::default()),
+                    ),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 8,
+                    resource: wgpu::BindingResource::Sampler(sampler),
+                },
             ],
             label: Some("bind_group"),
         })
     }
 
+    /// Rebuild the mesh pipelines with a post-effect chain spliced into the
+    /// shader. On a shader error the current pipelines stay in place.
+    pub fn set_effect_chain(&mut self, chain: &EffectChain) -> Result<(), String> {
+        let source = chain.shader_source(MESH_SHADER)?;
+
+        self.device.push_error_scope(wgpu::ErrorFilter::Validation);
+        let shader = self.device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Displacement Shader (effect chain)"),
+            source: wgpu::ShaderSource::Wgsl(source.into()),
+        });
+        let pipeline = |topology, entries| {
+            Self::create_pipeline(&self.device, &self.pipeline_layout, &shader, self.config.format, topology, entries)
+        };
+        let triangles = pipeline(wgpu::PrimitiveTopology::TriangleList, ("vs_main", "fs_main"));
+        let lines = pipeline(wgpu::PrimitiveTopology::LineList, ("vs_main", "fs_main"));
+        let smooth_lines = pipeline(wgpu::PrimitiveTopology::TriangleList, ("vs_smooth_line", "fs_smooth_line"));
+        if let Some(error) = pollster::block_on(self.device.pop_error_scope()) {
+            return Err(format!("Effect chain shader failed to build: {}", error));
+        }
+
+        self.render_pipeline_triangles = triangles;
+        self.render_pipeline_lines = lines;
+        self.render_pipeline_smooth_lines = smooth_lines;
+        Ok(())
+    }
+
+    /// Mesh pipeline with its (vertex, fragment) entry points
     fn create_pipeline(
         device: &wgpu::Device,
         layout: &wgpu::PipelineLayout,
         shader: &wgpu::ShaderModule,
         format: wgpu::TextureFormat,
         topology: wgpu::PrimitiveTopology,
+        (vertex_entry, fragment_entry): (&str, &str),
     ) -> wgpu::RenderPipeline {
         device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
-            label: Some("Render Pipeline"),
+            label: Some(fragment_entry),
             layout: Some(layout),
             vertex: wgpu::VertexState {
                 module: shader,
-                entry_point: "vs_main",
+                entry_point: vertex_entry,
                 buffers: &[Vertex::desc()],
             },
             fragment: Some(wgpu::FragmentState {
                 module: shader,
-                entry_point: "fs_main",
+                entry_point: fragment_entry,
                 targets: &[Some(wgpu::ColorTargetState {
                     format,
-                    blend: Some(wgpu::BlendState::ALPHA_BLENDING),
+                    // Mesh fragment shaders output premultiplied color
+                    blend: Some(wgpu::BlendState::PREMULTIPLIED_ALPHA_BLENDING),
                     write_mask: wgpu::ColorWrites::ALL,
                 })],
             }),
@@ -427,42 +1639,468 @@ impl Renderer {
                 unclipped_depth: false,
                 conservative: false,
             },
-            depth_stencil: None,
+            // Ties pass, so coplanar parts of a flat mesh still draw in order
+            depth_stencil: Some(wgpu::DepthStencilState {
+                format: DEPTH_FORMAT,
+                depth_write_enabled: true,
+                depth_compare: wgpu::CompareFunction::LessEqual,
+                stencil: wgpu::StencilState::default(),
+                bias: wgpu::DepthBiasState::default(),
+            }),
             multisample: wgpu::MultisampleState {
                 count: 1,
                 mask: !0,
                 alpha_to_coverage_enabled: false,
             },
-            multiview: None,
+            multiview: None,
+        })
+    }
+
+    fn create_fullscreen_pipeline(
+        device: &wgpu::Device,
+        layout: &wgpu::PipelineLayout,
+        shader: &wgpu::ShaderModule,
+        fragment_entry: &str,
+        format: wgpu::TextureFormat,
+        blend: Option<wgpu::BlendState>,
+    ) -> wgpu::RenderPipeline {
+        device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some(fragment_entry),
+            layout: Some(layout),
+            vertex: wgpu::VertexState {
+                module: shader,
+                entry_point: "vs_fullscreen",
+                buffers: &[],
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: shader,
+                entry_point: fragment_entry,
+                targets: &[Some(wgpu::ColorTargetState {
+                    format,
+                    blend,
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+            }),
+            primitive: wgpu::PrimitiveState::default(),
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState::default(),
+            multiview: None,
+        })
+    }
+
+    /// Draw once with every pipeline into a small offscreen target, so drivers that
+    /// compile shaders lazily do it at startup instead of on the first mesh or
+    /// feedback switch mid-performance
+    pub fn prewarm(&self) {
+        let start = std::time::Instant::now();
+
+        let target = self.device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("Prewarm Target"),
+            size: wgpu::Extent3d {
+                width: 16,
+                height: 16,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: self.config.format,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
+            view_formats: &[],
+        });
+        let view = target.create_view(&wgpu::TextureViewDescriptor::default());
+        let depth_view = create_depth_view(&self.device, 16, 16);
+
+        let mut encoder = self.device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("Prewarm Encoder"),
+        });
+        for pipeline in [
+            &self.render_pipeline_triangles,
+            &self.render_pipeline_lines,
+            &self.render_pipeline_smooth_lines,
+        ] {
+            let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: Some("Prewarm Pass"),
+                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                    view: &view,
+                    resolve_target: None,
+                    ops: wgpu::Operations {
+                        load: wgpu::LoadOp::Clear(wgpu::Color::TRANSPARENT),
+                        store: wgpu::StoreOp::Store,
+                    },
+                })],
+                depth_stencil_attachment: Some(Self::depth_attachment(&depth_view)),
+                occlusion_query_set: None,
+                timestamp_writes: None,
+            });
+            render_pass.set_pipeline(pipeline);
+            render_pass.set_bind_group(0, &self.bind_group, &[]);
+            render_pass.set_vertex_buffer(0, self.vertex_buffer.slice(..));
+            // Even count, valid for both triangle and line lists
+            render_pass.draw(0..self.vertex_count.min(6), 0..1);
+        }
+        let clear = wgpu::LoadOp::Clear(wgpu::Color::TRANSPARENT);
+        Self::fullscreen_pass(
+            &mut encoder,
+            &view,
+            &self.feedback_pipeline,
+            &self.feedback.composite_bind_groups[0],
+            clear,
+        );
+        Self::fullscreen_pass(
+            &mut encoder,
+            &view,
+            &self.blit_pipeline,
+            &self.feedback.blit_bind_groups[0],
+            clear,
+        );
+        self.draw_bloom(&mut encoder, 0);
+        Self::fullscreen_pass(&mut encoder, &view, &self.bloom_add_pipeline, &self.bloom.blur_bind_groups[0], clear);
+        self.queue.submit(std::iter::once(encoder.finish()));
+        self.device.poll(wgpu::Maintain::Wait);
+
+        log::info!("Prewarmed pipelines in {:.1} ms", start.elapsed().as_secs_f32() * 1000.0);
+    }
+
+    /// Draw the current output into a small offscreen target and read it back as
+    /// tightly packed RGBA8. Blocks until the GPU is done, so call it sparingly.
+    pub fn read_preview(&self, width: u32, height: u32) -> Vec<u8> {
+        let target = self.readback_target(width, height);
+        let view = target.create_view(&wgpu::TextureViewDescriptor::default());
+
+        let mut encoder = self.device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("Preview Encoder"),
+        });
+        if self.renders_offscreen() {
+            Self::fullscreen_pass(
+                &mut encoder,
+                &view,
+                &self.blit_pipeline,
+                &self.feedback.blit_bind_groups[self.feedback.current],
+                wgpu::LoadOp::Clear(self.clear_color),
+            );
+            // The last frame's glow is still in the bloom buffer
+            if self.bloom_active {
+                Self::fullscreen_pass(
+                    &mut encoder,
+                    &view,
+                    &self.bloom_add_pipeline,
+                    &self.bloom.blur_bind_groups[0],
+                    wgpu::LoadOp::Load,
+                );
+            }
+        } else {
+            let depth_view = create_depth_view(&self.device, width, height);
+            self.draw_mesh(&mut encoder, &view, &depth_view, self.clear_color);
+        }
+        self.read_back(encoder, &target)
+    }
+
+    /// Draw the next frame, like `render_to`, into an offscreen target of the
+    /// output size and read it back as tightly packed RGBA8, top row first.
+    /// Blocks until the GPU is done.
+    pub fn render_to_rgba(&mut self) -> Vec<u8> {
+        let target = self.readback_target(self.config.width, self.config.height);
+        let view = target.create_view(&wgpu::TextureViewDescriptor::default());
+        self.render_to(&view);
+
+        let encoder = self.device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("Readback Encoder"),
+        });
+        self.read_back(encoder, &target)
+    }
+
+    /// Render target in the output format that can be copied out
+    fn readback_target(&self, width: u32, height: u32) -> wgpu::Texture {
+        self.device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("Readback Target"),
+            size: wgpu::Extent3d {
+                width,
+                height,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: self.config.format,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::COPY_SRC,
+            view_formats: &[],
         })
     }
 
+    /// Finish `encoder` with a copy of `target` into a mapped buffer and
+    /// return its pixels as RGBA8 (BGRA surfaces are swizzled)
+    fn read_back(&self, mut encoder: wgpu::CommandEncoder, target: &wgpu::Texture) -> Vec<u8> {
+        let (width, height) = (target.width(), target.height());
+
+        let buffer = self.device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Readback Buffer"),
+            size: (padded_row_bytes(width) * height) as u64,
+            usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+            mapped_at_creation: false,
+        });
+
+        encoder.copy_texture_to_buffer(
+            target.as_image_copy(),
+            wgpu::ImageCopyBuffer {
+                buffer: &buffer,
+                layout: wgpu::ImageDataLayout {
+                    offset: 0,
+                    bytes_per_row: Some(padded_row_bytes(width)),
+                    rows_per_image: Some(height),
+                },
+            },
+            wgpu::Extent3d {
+                width,
+                height,
+                depth_or_array_layers: 1,
+            },
+        );
+        self.queue.submit(std::iter::once(encoder.finish()));
+
+        let slice = buffer.slice(..);
+        slice.map_async(wgpu::MapMode::Read, |_| {});
+        self.device.poll(wgpu::Maintain::Wait);
+
+        let pixels = unpad_rows(&slice.get_mapped_range(), width, height, self.output_is_bgra());
+        buffer.unmap();
+        pixels
+    }
+
+    fn output_is_bgra(&self) -> bool {
+        matches!(
+            self.config.format,
+            wgpu::TextureFormat::Bgra8Unorm | wgpu::TextureFormat::Bgra8UnormSrgb
+        )
+    }
+
+    /// Start or stop copying every presented frame, without the HUD, for
+    /// `take_captured_frame`. Fails if the window surface can't be copied from;
+    /// headless renderers present nothing, so they never capture.
+    pub fn set_frame_capture(&mut self, enabled: bool) -> Result<(), String> {
+        if enabled == self.capture.is_some() {
+            return Ok(());
+        }
+        if enabled && !self.surface_usages.contains(wgpu::TextureUsages::COPY_SRC) {
+            return Err("the window surface doesn't support copying frames out".to_string());
+        }
+        self.config.usage.set(wgpu::TextureUsages::COPY_SRC, enabled);
+        self.configure_surface();
+        self.capture = enabled.then(|| FrameCapture::new(&self.device, self.config.width, self.config.height));
+        Ok(())
+    }
+
+    /// The newest presented frame whose readback has finished, as RGBA8 once
+    /// converted. Never waits: None until a frame is ready, usually a frame or
+    /// two after it was presented.
+    pub fn take_captured_frame(&mut self) -> Option<CapturedFrame> {
+        let bgra = self.output_is_bgra();
+        let capture = self.capture.as_mut()?;
+        self.device.poll(wgpu::Maintain::Poll);
+        capture.take(bgra)
+    }
+
     pub fn resize(&mut self, new_size: winit::dpi::PhysicalSize<u32>) {
         if new_size.width > 0 && new_size.height > 0 {
+            let (width, height) = Self::fit_texture_limit(&self.device, new_size.width, new_size.height);
             self.size = new_size;
-            self.config.width = new_size.width;
-            self.config.height = new_size.height;
-            self.surface.configure(&self.device, &self.config);
+            self.config.width = width;
+            self.config.height = height;
+            self.configure_surface();
+            self.depth_view = create_depth_view(&self.device, width, height);
+            self.rebuild_offscreen();
+            if self.capture.is_some() {
+                self.capture = Some(FrameCapture::new(&self.device, width, height));
+            }
+        }
+    }
+
+    /// Draw the scene at `scale` (MIN_RENDER_SCALE - 1) times the surface size and
+    /// upscale it with `filter`, trading sharpness for fill rate
+    pub fn set_render_scale(&mut self, scale: f32, filter: ResizeFilter) {
+        let scale = if scale.is_finite() { scale.clamp(MIN_RENDER_SCALE, 1.0) } else { 1.0 };
+        if scale == self.render_scale && filter == self.upscale_filter {
+            return;
+        }
+        self.render_scale = scale;
+        self.upscale_filter = filter;
+        self.rebuild_offscreen();
+    }
+
+    /// Offscreen targets go through the feedback composite and blit: always with
+    /// trails and bloom, and when the scene is drawn below the surface size
+    fn renders_offscreen(&self) -> bool {
+        self.feedback_decay > 0.0 || self.bloom_active || self.render_scale < 1.0
+    }
+
+    /// Recreate the offscreen targets at the current size, scale and filter
+    fn rebuild_offscreen(&mut self) {
+        let scaled = |side: u32| ((side as f32 * self.render_scale).round() as u32).max(1);
+        let sampler = match self.upscale_filter {
+            ResizeFilter::Nearest => &self.nearest_sampler,
+            ResizeFilter::Bilinear => &self.sampler,
+        };
+        let (width, height) = (scaled(self.config.width), scaled(self.config.height));
+        self.feedback = FeedbackBuffers::new(
+            &self.device,
+            &self.feedback_bind_group_layout,
+            &self.feedback_uniform_buffer,
+            sampler,
+            self.config.format,
+            width,
+            height,
+        );
+        // Always bilinear: the blur relies on filtering between texels
+        self.bloom = BloomBuffers::new(
+            &self.device,
+            &self.bloom_bind_group_layout,
+            &self.bloom_uniform_buffer,
+            &self.sampler,
+            self.bloom_format,
+            &self.feedback.frame_views,
+            (width / 2).max(1),
+            (height / 2).max(1),
+        );
+    }
+
+    fn configure_surface(&self) {
+        if let Some(surface) = &self.surface {
+            surface.configure(&self.device, &self.config);
+        }
+    }
+
+    pub fn device(&self) -> &wgpu::Device {
+        &self.device
+    }
+
+    pub fn queue(&self) -> &wgpu::Queue {
+        &self.queue
+    }
+
+    /// Color format of the output; views passed to `render_to` must use it
+    pub fn format(&self) -> wgpu::TextureFormat {
+        self.config.format
+    }
+
+    /// Output size in pixels, after fitting to the texture limit; the size of
+    /// `render_to` views and `render_to_rgba` frames
+    pub fn output_size(&self) -> (u32, u32) {
+        (self.config.width, self.config.height)
+    }
+
+    /// Show or hide the parameter overlay drawn by `render_to`
+    pub fn set_hud_visible(&mut self, visible: bool) {
+        self.hud.visible = visible;
+    }
+
+    /// Replace the parameter overlay image (premultiplied RGBA, top row first)
+    pub fn update_hud(&mut self, rgba: &[u8], width: u32, height: u32) {
+        self.hud.upload(&self.device, &self.queue, rgba, width, height);
+    }
+
+    /// Reconfigure the surface with a new present mode.
+    /// Falls back to the matching Auto mode if the surface doesn't support it.
+    pub fn set_present_mode(&mut self, mode: PresentMode) {
+        let requested = mode.to_wgpu();
+        let present_mode = match requested {
+            wgpu::PresentMode::AutoVsync | wgpu::PresentMode::AutoNoVsync => requested,
+            _ if self.present_modes.contains(&requested) => requested,
+            wgpu::PresentMode::Immediate | wgpu::PresentMode::Mailbox => {
+                log::warn!("Present mode {:?} not supported, using AutoNoVsync", requested);
+                wgpu::PresentMode::AutoNoVsync
+            }
+            _ => {
+                log::warn!("Present mode {:?} not supported, using AutoVsync", requested);
+                wgpu::PresentMode::AutoVsync
+            }
+        };
+
+        self.config.present_mode = present_mode;
+        self.configure_surface();
+        log::info!("Present mode: {:?} ({:?})", present_mode, self.resolved_present_mode(present_mode));
+    }
+
+    /// Concrete mode an auto mode ends up as, in wgpu's order of preference
+    fn resolved_present_mode(&self, mode: wgpu::PresentMode) -> wgpu::PresentMode {
+        let preference: &[wgpu::PresentMode] = match mode {
+            wgpu::PresentMode::AutoVsync => &[wgpu::PresentMode::FifoRelaxed, wgpu::PresentMode::Fifo],
+            wgpu::PresentMode::AutoNoVsync => {
+                &[wgpu::PresentMode::Immediate, wgpu::PresentMode::Mailbox, wgpu::PresentMode::Fifo]
+            }
+            _ => return mode,
+        };
+        preference
+            .iter()
+            .copied()
+            .find(|mode| self.present_modes.contains(mode))
+            .unwrap_or(wgpu::PresentMode::Fifo)
+    }
+
+    /// Set the background color from sRGB components (0.0 - 1.0).
+    /// Alpha below 1 switches the surface to a non-opaque alpha mode when the
+    /// platform supports one, so whatever is behind the window shows through.
+    pub fn set_clear_color(&mut self, rgba: [f32; 4]) {
+        let to_linear = |c: f32| {
+            if self.config.format.is_srgb() {
+                c.clamp(0.0, 1.0).powf(2.2)
+            } else {
+                c.clamp(0.0, 1.0)
+            }
+        };
+        let a = rgba[3].clamp(0.0, 1.0);
+        let color = wgpu::Color {
+            r: (to_linear(rgba[0]) * a) as f64,
+            g: (to_linear(rgba[1]) * a) as f64,
+            b: (to_linear(rgba[2]) * a) as f64,
+            a: a as f64,
+        };
+        if color == self.clear_color {
+            return;
+        }
+        self.clear_color = color;
+
+        let alpha_mode = if a < 1.0 {
+            let transparent = [wgpu::CompositeAlphaMode::PreMultiplied, wgpu::CompositeAlphaMode::Inherit]
+                .into_iter()
+                .find(|mode| self.alpha_modes.contains(mode));
+            if transparent.is_none() && self.config.alpha_mode == self.alpha_modes[0] {
+                log::warn!("Transparent background not supported by this surface, staying opaque");
+            }
+            transparent.unwrap_or(self.alpha_modes[0])
+        } else {
+            self.alpha_modes[0]
+        };
+
+        if alpha_mode != self.config.alpha_mode {
+            self.config.alpha_mode = alpha_mode;
+            self.configure_surface();
+            log::info!("Surface alpha mode: {:?}", alpha_mode);
         }
     }
 
+    /// Display the mesh at the given source aspect ratio instead of the video texture's
+    pub fn set_source_aspect(&mut self, aspect: Option<f32>) {
+        self.source_aspect = aspect;
+    }
+
     /// Get video dimensions for mesh generation
     pub fn video_dimensions(&self) -> (f32, f32) {
         (self.video_width as f32, self.video_height as f32)
     }
 
     pub fn update_mesh(&mut self, mesh: &Mesh) {
-        if mesh.mesh_type != self.current_mesh_type || mesh.vertices.len() as u32 != self.vertex_count {
+        if mesh.primitive_topology() != self.topology || mesh.vertices.len() as u32 != self.vertex_count {
             self.vertex_buffer = self.device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
                 label: Some("Vertex Buffer"),
                 contents: bytemuck::cast_slice(&mesh.vertices),
                 usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
             });
             self.vertex_count = mesh.vertices.len() as u32;
-            self.current_mesh_type = mesh.mesh_type;
+            self.topology = mesh.primitive_topology();
         } else {
             self.queue.write_buffer(&self.vertex_buffer, 0, bytemuck::cast_slice(&mesh.vertices));
         }
+        self.smooth_edges = mesh.smooth_edges;
     }
 
     pub fn update_video_texture(&mut self, data: &[u8], width: u32, height: u32) {
@@ -477,6 +2115,30 @@ impl Renderer {
                 &self.bind_group_layout,
                 &self.uniform_buffer,
                 &self.video_texture,
+                &self.video_texture2,
+                &self.x_noise_texture,
+                &self.y_noise_texture,
+                &self.z_noise_texture,
+                &self.sampler,
+            );
+        }
+
+        self.write_rgba(&self.video_texture, data, width, height);
+    }
+
+    /// Upload the second video source, crossfaded with the first by `video_mix`
+    pub fn update_video_texture2(&mut self, data: &[u8], width: u32, height: u32) {
+        // Recreate texture if dimensions changed, independently of the first source
+        if width != self.video2_width || height != self.video2_height {
+            self.video2_width = width;
+            self.video2_height = height;
+            self.video_texture2 = Self::create_texture(&self.device, width, height, "video2");
+            self.bind_group = Self::create_bind_group(
+                &self.device,
+                &self.bind_group_layout,
+                &self.uniform_buffer,
+                &self.video_texture,
+                &self.video_texture2,
                 &self.x_noise_texture,
                 &self.y_noise_texture,
                 &self.z_noise_texture,
@@ -484,9 +2146,28 @@ impl Renderer {
             );
         }
 
+        self.write_rgba(&self.video_texture2, data, width, height);
+        self.video2_active = true;
+    }
+
+    /// Upload a straight-alpha frame. Frames with transparency are premultiplied
+    /// first, so filtering doesn't bleed the color of clear texels into their
+    /// neighbours; opaque frames (cameras, test patterns, noise) go up as they are.
+    ///
+    /// Rows are tightly packed at any width: `write_texture` stages them with
+    /// the copy alignment itself. Only buffer copies need rows padded to
+    /// `COPY_BYTES_PER_ROW_ALIGNMENT` (see `read_back`).
+    fn write_rgba(&self, texture: &wgpu::Texture, data: &[u8], width: u32, height: u32) {
+        let premultiplied;
+        let data = if data.chunks_exact(4).any(|px| px[3] < 255) {
+            premultiplied = premultiply_alpha(data);
+            &premultiplied[..]
+        } else {
+            data
+        };
         self.queue.write_texture(
             wgpu::ImageCopyTexture {
-                texture: &self.video_texture,
+                texture,
                 mip_level: 0,
                 origin: wgpu::Origin3d::ZERO,
                 aspect: wgpu::TextureAspect::All,
@@ -506,6 +2187,26 @@ impl Renderer {
     }
 
     pub fn update_noise_texture(&mut self, axis: usize, data: &[u8], width: u32, height: u32) {
+        // Recreate all three textures if the noise resolution changed
+        if width != self.noise_width || height != self.noise_height {
+            self.noise_width = width;
+            self.noise_height = height;
+            self.x_noise_texture = Self::create_texture(&self.device, width, height, "x_noise");
+            self.y_noise_texture = Self::create_texture(&self.device, width, height, "y_noise");
+            self.z_noise_texture = Self::create_texture(&self.device, width, height, "z_noise");
+            self.bind_group = Self::create_bind_group(
+                &self.device,
+                &self.bind_group_layout,
+                &self.uniform_buffer,
+                &self.video_texture,
+                &self.video_texture2,
+                &self.x_noise_texture,
+                &self.y_noise_texture,
+                &self.z_noise_texture,
+                &self.sampler,
+            );
+        }
+
         // Convert grayscale to RGBA
         let rgba: Vec<u8> = data.iter().flat_map(|&g| [g, g, g, 255]).collect();
 
@@ -514,40 +2215,25 @@ impl Renderer {
             1 => &self.y_noise_texture,
             _ => &self.z_noise_texture,
         };
-
-        self.queue.write_texture(
-            wgpu::ImageCopyTexture {
-                texture,
-                mip_level: 0,
-                origin: wgpu::Origin3d::ZERO,
-                aspect: wgpu::TextureAspect::All,
-            },
-            &rgba,
-            wgpu::ImageDataLayout {
-                offset: 0,
-                bytes_per_row: Some(4 * width),
-                rows_per_image: Some(height),
-            },
-            wgpu::Extent3d {
-                width,
-                height,
-                depth_or_array_layers: 1,
-            },
-        );
+        self.write_rgba(texture, &rgba, width, height);
     }
 
-    pub fn update_uniforms(&mut self, state: &AppState) {
-        let params = state.calculate_render_params();
-
+    fn extents(&self) -> Extents {
         // Use video dimensions for base coordinates
         let vw = self.video_width as f32;
         let vh = self.video_height as f32;
-        let half_w = vw / 2.0;
+
+        // Stretch horizontally so the mesh matches the camera's native aspect
+        let aspect_scale = match self.source_aspect {
+            Some(aspect) => aspect / (vw / vh),
+            None => 1.0,
+        };
+        let half_w = vw * aspect_scale / 2.0;
         let half_h = vh / 2.0;
 
         // Create MVP matrix with correct aspect ratio
         let window_aspect = self.size.width as f32 / self.size.height as f32;
-        let video_aspect = vw / vh;
+        let video_aspect = vw * aspect_scale / vh;
 
         // Adjust projection to fit video aspect ratio into window
         let (proj_w, proj_h) = if window_aspect > video_aspect {
@@ -558,25 +2244,78 @@ impl Renderer {
             (half_w, half_w / window_aspect)
         };
 
-        let projection = Mat4::orthographic_rh(-proj_w, proj_w, -proj_h, proj_h, -1000.0, 1000.0);
+        Extents {
+            aspect_scale,
+            half_w,
+            half_h,
+            proj_w,
+            proj_h,
+        }
+    }
+
+    /// Window position (physical pixels) in NDC, where the pivot lives
+    pub fn window_to_clip(&self, x: f64, y: f64) -> (f32, f32) {
+        (2.0 * x as f32 / self.size.width as f32 - 1.0, 1.0 - 2.0 * y as f32 / self.size.height as f32)
+    }
+
+    /// Map a window position (physical pixels) to normalized video coordinates,
+    /// (0, 0) bottom-left to (1, 1) top-right, matching mesh tex coords.
+    /// Ignores rotation and zoom; points in the letterbox fall outside 0..1.
+    pub fn window_to_video(&self, state: &AppState, x: f64, y: f64) -> (f32, f32) {
+        let Extents {
+            half_w,
+            half_h,
+            proj_w,
+            proj_h,
+            ..
+        } = self.extents();
+        let (clip_x, clip_y) = self.window_to_clip(x, y);
+        let u = (clip_x * proj_w + half_w - state.global_x_displace) / (2.0 * half_w);
+        let v = (clip_y * proj_h + half_h - state.global_y_displace) / (2.0 * half_h);
+        (u, v)
+    }
+
+    pub fn update_uniforms(&mut self, state: &AppState) {
+        let params = state.calculate_render_params();
+        let Extents {
+            aspect_scale,
+            half_w,
+            half_h,
+            proj_w,
+            proj_h,
+        } = self.extents();
+
+        let projection = match state.projection {
+            ProjectionMode::Orthographic => Mat4::orthographic_rh(-proj_w, proj_w, -proj_h, proj_h, -1000.0, 1000.0),
+            ProjectionMode::Perspective => {
+                // Camera pulled back until the z = 0 plane fills the same extents as ortho
+                let fov = state.fov.to_radians();
+                let distance = proj_h / (fov / 2.0).tan();
+                Mat4::perspective_rh(fov, proj_w / proj_h, state.near, state.far)
+                    * Mat4::from_translation(Vec3::new(0.0, 0.0, -distance))
+            }
+        };
 
+        // Audio-driven motion follows the master intensity like the distortion
+        let audio_rotate = state.audio_rotate_mod.map(|angle| angle * state.master_intensity);
         let view = Mat4::from_translation(Vec3::new(0.0, 0.0, params.zoom))
-            * Mat4::from_rotation_x(state.rotate_x)
-            * Mat4::from_rotation_y(state.rotate_y)
-            * Mat4::from_rotation_z(state.rotate_z);
+            * Mat4::from_rotation_x(state.rotate_x + state.idle_rotate[0] + audio_rotate[0])
+            * Mat4::from_rotation_y(state.rotate_y + state.idle_rotate[1] + audio_rotate[1])
+            * Mat4::from_rotation_z(state.rotate_z + state.idle_rotate[2] + audio_rotate[2])
+            * Mat4::from_scale(Vec3::splat(1.0 + state.audio_zoom_mod * state.master_intensity));
 
         let model = Mat4::from_translation(Vec3::new(
             -half_w + state.global_x_displace,
             -half_h + state.global_y_displace,
             0.0,
-        ));
+        )) * Mat4::from_scale(Vec3::new(aspect_scale, 1.0, 1.0));
 
         let mvp = projection * view * model;
 
         let uniforms = Uniforms {
             mvp: mvp.to_cols_array_2d(),
             xy: [params.displace_x, params.displace_y],
-            xy_offset: [params.center_x, params.center_y],
+            pivot: [params.center_x, params.center_y],
             x_lfo_arg: state.x_lfo_arg,
             x_lfo_amp: params.x_lfo_amp,
             x_lfo_other: params.x_frequency,
@@ -587,19 +2326,11 @@ impl Renderer {
             z_lfo_amp: params.z_lfo_amp,
             z_lfo_other: params.z_frequency,
             luma_key_level: params.luma_key_level,
-            invert_switch: if state.invert { 1.0 } else { 0.0 },
-            b_w_switch: if state.greyscale { 1.0 } else { 0.0 },
-            bright_switch: if state.bright_switch { 1 } else { 0 },
-            x_lfo_shape: state.x_lfo_shape,
-            y_lfo_shape: state.y_lfo_shape,
-            z_lfo_shape: state.z_lfo_shape,
-            x_ringmod_switch: if state.x_ringmod { 1 } else { 0 },
-            y_ringmod_switch: if state.y_ringmod { 1 } else { 0 },
-            z_ringmod_switch: if state.z_ringmod { 1 } else { 0 },
-            x_phasemod_switch: if state.x_phasemod { 1 } else { 0 },
-            y_phasemod_switch: if state.y_phasemod { 1 } else { 0 },
-            z_phasemod_switch: if state.z_phasemod { 1 } else { 0 },
-            luma_switch: if state.luma_switch { 1 } else { 0 },
+            effect_flags: effect_flags(state),
+            // Always a valid index: LfoShape only holds the shapes `oscillate` handles
+            x_lfo_shape: state.x_lfo_shape.to_i32(),
+            y_lfo_shape: state.y_lfo_shape.to_i32(),
+            z_lfo_shape: state.z_lfo_shape.to_i32(),
             width: state.width as i32,
             height: state.height as i32,
             audio_displacement: params.audio_displacement,
@@ -607,50 +2338,419 @@ impl Renderer {
             audio_wave_phase: state.audio_wave_phase,
             audio_wave_amp: state.audio_wave_amp,
             audio_wave_freq: state.audio_wave_freq,
-            _pad: [0.0; 6],
+            video_mix: if self.video2_active { state.video_mix } else { 0.0 },
+            chroma_split: params.chroma_split,
+            x_source: state.x_source as i32,
+            y_source: state.y_source as i32,
+            z_source: state.z_source as i32,
+            audio_level: state.audio_envelope,
+            _align: 0.0,
+            // Ripples fade with the master intensity like every other distortion
+            ripples: state.ripples.ripples.map(|ripple| {
+                let [x, y, radius, intensity] = ripple.to_array();
+                [x, y, radius, intensity * state.master_intensity]
+            }),
+            ripple_shape: state.ripples.shape as i32,
+            audio_displace_x: params.audio_displace_xy[0],
+            audio_displace_y: params.audio_displace_xy[1],
+            ripple_count: state.ripples.count() as i32,
+            extra_lfos: params.extra_lfos,
+            input_exposure: state.input_exposure.exp2(),
+            input_gamma: state.input_gamma.max(0.01),
+            flash: state.strobe.level(),
+            flash_mode: state.strobe.mode as i32,
+            mesh_smoothing: state.mesh_smoothing.clamp(0.0, 1.0),
+            grid_cells: state.mesh_type.cells_per_side(state.scale.max(1)) as f32,
+            output_level: state.output_level.clamp(0.0, 1.0),
+            x_lfo_bias: params.x_lfo_bias,
+            y_lfo_bias: params.y_lfo_bias,
+            z_lfo_bias: params.z_lfo_bias,
+            _pad: [0.0; 2],
+            noise_amp: [state.noise_amp[0], state.noise_amp[1], state.noise_amp[2], 0.0],
         };
 
         self.queue.write_buffer(&self.uniform_buffer, 0, bytemuck::cast_slice(&[uniforms]));
+
+        // Trails: start from clean buffers whenever feedback is switched on
+        let decay = if state.effect_enabled(Solo::Feedback) {
+            state.feedback_decay.clamp(0.0, 0.99)
+        } else {
+            0.0
+        };
+        if self.feedback_decay <= 0.0 && decay > 0.0 {
+            self.feedback.needs_clear = true;
+        }
+        self.feedback_decay = decay;
+        let feedback_uniforms = FeedbackUniforms {
+            decay,
+            _pad: [0.0; 3],
+        };
+        self.queue.write_buffer(&self.feedback_uniform_buffer, 0, bytemuck::cast_slice(&[feedback_uniforms]));
+
+        // Bloom is bypassed, not just faded out, so switching it off saves the passes
+        self.bloom_active = state.bloom && (state.bloom_intensity > 0.0 || state.audio_bloom_gain > 0.0);
+        let bloom_uniforms = BloomUniforms {
+            threshold: state.bloom_threshold.clamp(0.0, 1.0),
+            intensity: (state.bloom_intensity + state.audio_bloom_gain * state.audio_envelope).max(0.0),
+            texel: [1.0 / self.bloom.width as f32, 1.0 / self.bloom.height as f32],
+        };
+        self.queue.write_buffer(&self.bloom_uniform_buffer, 0, bytemuck::cast_slice(&[bloom_uniforms]));
     }
 
+    /// Draw a frame to the window. Headless renderers have nothing to present
+    /// and draw with `render_to` instead.
     pub fn render(&mut self) -> Result<(), wgpu::SurfaceError> {
-        let output = self.surface.get_current_texture()?;
+        let Some(surface) = &self.surface else {
+            return Ok(());
+        };
+        let output = surface.get_current_texture()?;
         let view = output.texture.create_view(&wgpu::TextureViewDescriptor::default());
+        self.draw_frame(&view, Some(&output.texture));
+        output.present();
+
+        Ok(())
+    }
+
+    /// Draw a frame into `view`, which must be `size` and `format()`, and advance the
+    /// feedback trails as a presented frame would
+    pub fn render_to(&mut self, view: &wgpu::TextureView) {
+        self.draw_frame(view, None);
+    }
 
+    /// `render_to`, copying the frame before the HUD goes on into the capture
+    /// ring when capturing and `texture` is the one behind `view`
+    fn draw_frame(&mut self, view: &wgpu::TextureView, texture: Option<&wgpu::Texture>) {
         let mut encoder = self.device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
             label: Some("Render Encoder"),
         });
 
-        {
-            let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
-                label: Some("Render Pass"),
-                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
-                    view: &view,
-                    resolve_target: None,
-                    ops: wgpu::Operations {
-                        load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
-                        store: wgpu::StoreOp::Store,
-                    },
-                })],
-                depth_stencil_attachment: None,
-                occlusion_query_set: None,
-                timestamp_writes: None,
-            });
+        if self.renders_offscreen() {
+            if self.feedback.needs_clear {
+                for frame_view in &self.feedback.frame_views {
+                    Self::clear_pass(&mut encoder, frame_view);
+                }
+                self.feedback.needs_clear = false;
+            }
 
-            let pipeline = match self.current_mesh_type {
-                MeshType::Triangles => &self.render_pipeline_triangles,
-                MeshType::HorizontalLines | MeshType::VerticalLines | MeshType::Grid => &self.render_pipeline_lines,
-            };
+            // Mesh on transparent black, so the composite can see what was drawn
+            self.draw_mesh(
+                &mut encoder,
+                &self.feedback.scene_view,
+                &self.feedback.scene_depth_view,
+                wgpu::Color::TRANSPARENT,
+            );
 
-            render_pass.set_pipeline(pipeline);
-            render_pass.set_bind_group(0, &self.bind_group, &[]);
-            render_pass.set_vertex_buffer(0, self.vertex_buffer.slice(..));
-            render_pass.draw(0..self.vertex_count, 0..1);
+            let next = 1 - self.feedback.current;
+            Self::fullscreen_pass(
+                &mut encoder,
+                &self.feedback.frame_views[next],
+                &self.feedback_pipeline,
+                &self.feedback.composite_bind_groups[next],
+                wgpu::LoadOp::Clear(wgpu::Color::TRANSPARENT),
+            );
+            if self.bloom_active {
+                self.draw_bloom(&mut encoder, next);
+            }
+            // Blended over the background color, upscaled to the surface
+            Self::fullscreen_pass(
+                &mut encoder,
+                view,
+                &self.blit_pipeline,
+                &self.feedback.blit_bind_groups[next],
+                wgpu::LoadOp::Clear(self.clear_color),
+            );
+            if self.bloom_active {
+                Self::fullscreen_pass(
+                    &mut encoder,
+                    view,
+                    &self.bloom_add_pipeline,
+                    &self.bloom.blur_bind_groups[0],
+                    wgpu::LoadOp::Load,
+                );
+            }
+            self.feedback.current = next;
+        } else {
+            self.draw_mesh(&mut encoder, view, &self.depth_view, self.clear_color);
         }
+        let captured = match (&mut self.capture, texture) {
+            (Some(capture), Some(texture)) => capture.record(&mut encoder, texture),
+            _ => None,
+        };
+        self.hud.draw(&self.queue, &mut encoder, view, self.config.width, self.config.height);
 
         self.queue.submit(std::iter::once(encoder.finish()));
-        output.present();
+        if let (Some(capture), Some(slot)) = (&self.capture, captured) {
+            capture.map(slot);
+        }
+    }
 
-        Ok(())
+    /// Bright parts of feedback frame `frame`, blurred into `bloom.views[0]`
+    fn draw_bloom(&self, encoder: &mut wgpu::CommandEncoder, frame: usize) {
+        let clear = wgpu::LoadOp::Clear(wgpu::Color::TRANSPARENT);
+        Self::fullscreen_pass(
+            encoder,
+            &self.bloom.views[0],
+            &self.bloom_bright_pipeline,
+            &self.bloom.bright_bind_groups[frame],
+            clear,
+        );
+        Self::fullscreen_pass(
+            encoder,
+            &self.bloom.views[1],
+            &self.bloom_blur_h_pipeline,
+            &self.bloom.blur_bind_groups[0],
+            clear,
+        );
+        Self::fullscreen_pass(
+            encoder,
+            &self.bloom.views[0],
+            &self.bloom_blur_v_pipeline,
+            &self.bloom.blur_bind_groups[1],
+            clear,
+        );
+    }
+
+    /// `depth` must match the size of `target`
+    fn draw_mesh(
+        &self,
+        encoder: &mut wgpu::CommandEncoder,
+        target: &wgpu::TextureView,
+        depth: &wgpu::TextureView,
+        clear: wgpu::Color,
+    ) {
+        let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("Render Pass"),
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view: target,
+                resolve_target: None,
+                ops: wgpu::Operations {
+                    load: wgpu::LoadOp::Clear(clear),
+                    store: wgpu::StoreOp::Store,
+                },
+            })],
+            depth_stencil_attachment: Some(Self::depth_attachment(depth)),
+            occlusion_query_set: None,
+            timestamp_writes: None,
+        });
+
+        let pipeline = match self.topology {
+            wgpu::PrimitiveTopology::LineList => &self.render_pipeline_lines,
+            _ if self.smooth_edges => &self.render_pipeline_smooth_lines,
+            _ => &self.render_pipeline_triangles,
+        };
+
+        render_pass.set_pipeline(pipeline);
+        render_pass.set_bind_group(0, &self.bind_group, &[]);
+        render_pass.set_vertex_buffer(0, self.vertex_buffer.slice(..));
+        render_pass.draw(0..self.vertex_count, 0..1);
+    }
+
+    /// Depth cleared to the far plane and dropped after the pass
+    fn depth_attachment(view: &wgpu::TextureView) -> wgpu::RenderPassDepthStencilAttachment<'_> {
+        wgpu::RenderPassDepthStencilAttachment {
+            view,
+            depth_ops: Some(wgpu::Operations {
+                load: wgpu::LoadOp::Clear(1.0),
+                store: wgpu::StoreOp::Discard,
+            }),
+            stencil_ops: None,
+        }
+    }
+
+    fn fullscreen_pass(
+        encoder: &mut wgpu::CommandEncoder,
+        target: &wgpu::TextureView,
+        pipeline: &wgpu::RenderPipeline,
+        bind_group: &wgpu::BindGroup,
+        load: wgpu::LoadOp<wgpu::Color>,
+    ) {
+        let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("Fullscreen Pass"),
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view: target,
+                resolve_target: None,
+                ops: wgpu::Operations {
+                    load,
+                    store: wgpu::StoreOp::Store,
+                },
+            })],
+            depth_stencil_attachment: None,
+            occlusion_query_set: None,
+            timestamp_writes: None,
+        });
+
+        render_pass.set_pipeline(pipeline);
+        render_pass.set_bind_group(0, bind_group, &[]);
+        render_pass.draw(0..3, 0..1);
+    }
+
+    fn clear_pass(encoder: &mut wgpu::CommandEncoder, target: &wgpu::TextureView) {
+        encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("Clear Pass"),
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view: target,
+                resolve_target: None,
+                ops: wgpu::Operations {
+                    load: wgpu::LoadOp::Clear(wgpu::Color::TRANSPARENT),
+                    store: wgpu::StoreOp::Store,
+                },
+            })],
+            depth_stencil_attachment: None,
+            occlusion_query_set: None,
+            timestamp_writes: None,
+        });
+    }
+}
+
+/// Scale each pixel's color by its alpha (straight to premultiplied RGBA)
+fn premultiply_alpha(data: &[u8]) -> Vec<u8> {
+    data.chunks_exact(4)
+        .flat_map(|px| {
+            let a = px[3] as u16;
+            let scale = |c: u8| ((c as u16 * a + 127) / 255) as u8;
+            [scale(px[0]), scale(px[1]), scale(px[2]), px[3]]
+        })
+        .collect()
+}
+
+/// Bytes per row of a `width` RGBA8 texture copied into a buffer, padded to
+/// COPY_BYTES_PER_ROW_ALIGNMENT as buffer copies require
+fn padded_row_bytes(width: u32) -> u32 {
+    let align = wgpu::COPY_BYTES_PER_ROW_ALIGNMENT;
+    (width * 4).div_ceil(align) * align
+}
+
+/// Tightly packed RGBA8 from a buffer copy with padded rows, swizzling BGRA
+fn unpad_rows(data: &[u8], width: u32, height: u32, bgra: bool) -> Vec<u8> {
+    let row_bytes = (width * 4) as usize;
+    let mut pixels = Vec::with_capacity(row_bytes * height as usize);
+    for row in data.chunks(padded_row_bytes(width) as usize).take(height as usize) {
+        for px in row[..row_bytes].chunks(4) {
+            if bgra {
+                pixels.extend_from_slice(&[px[2], px[1], px[0], px[3]]);
+            } else {
+                pixels.extend_from_slice(px);
+            }
+        }
+    }
+    pixels
+}
+
+/// The on/off effects of `state` as `effect_flags` bits
+fn effect_flags(state: &AppState) -> u32 {
+    let color = state.effect_enabled(Solo::Color);
+    [
+        (state.invert && color, effect_flags::INVERT),
+        (state.greyscale && color, effect_flags::GREYSCALE),
+        (state.bright_switch, effect_flags::BRIGHT_INVERT),
+        (state.luma_switch, effect_flags::LUMA_KEY_BRIGHT),
+        (state.x_ringmod, effect_flags::X_RINGMOD),
+        (state.y_ringmod, effect_flags::Y_RINGMOD),
+        (state.z_ringmod, effect_flags::Z_RINGMOD),
+        (state.x_phasemod, effect_flags::X_PHASEMOD),
+        (state.y_phasemod, effect_flags::Y_PHASEMOD),
+        (state.z_phasemod, effect_flags::Z_PHASEMOD),
+        (state.chroma_follow, effect_flags::CHROMA_FOLLOW),
+    ]
+    .into_iter()
+    .filter(|&(on, _)| on)
+    .fold(0, |flags, (_, bit)| flags | bit)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn headless(test: &str) -> Option<Renderer> {
+        match pollster::block_on(Renderer::new_headless(64, 64)) {
+            Ok(renderer) => Some(renderer),
+            Err(e) => {
+                eprintln!("Skipping {}: {}", test, e);
+                None
+            }
+        }
+    }
+
+    /// A copyable 180x120 texture holding a gradient, and its pixels.
+    /// 180 px rows are 720 bytes, short of the 256-byte copy alignment,
+    /// so both the upload and the readback have to handle padding.
+    fn test_pattern(renderer: &Renderer) -> (wgpu::Texture, Vec<u8>) {
+        let (width, height) = (180, 120);
+        let texture = renderer.device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("Readback Test"),
+            size: wgpu::Extent3d {
+                width,
+                height,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: renderer.config.format,
+            usage: wgpu::TextureUsages::COPY_DST | wgpu::TextureUsages::COPY_SRC,
+            view_formats: &[],
+        });
+        // Opaque, so write_rgba uploads it without premultiplying
+        let pixels: Vec<u8> = (0..width * height)
+            .flat_map(|i| {
+                let (x, y) = (i % width, i / width);
+                [x as u8, y as u8, (x ^ y) as u8, 255]
+            })
+            .collect();
+        renderer.write_rgba(&texture, &pixels, width, height);
+        (texture, pixels)
+    }
+
+    #[test]
+    fn uploaded_pixels_read_back_unchanged() {
+        let Some(renderer) = headless("readback test") else { return };
+        let (texture, pixels) = test_pattern(&renderer);
+
+        let encoder = renderer.device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("Readback Test Encoder"),
+        });
+        let read = renderer.read_back(encoder, &texture);
+        assert_eq!(read.len(), pixels.len());
+        assert!(read == pixels, "read back pixels differ from the upload");
+    }
+
+    #[test]
+    fn captured_frames_arrive_without_waiting() {
+        let Some(renderer) = headless("capture test") else { return };
+        let (texture, pixels) = test_pattern(&renderer);
+        let mut capture = FrameCapture::new(&renderer.device, texture.width(), texture.height());
+        let copy = |capture: &mut FrameCapture| {
+            let mut encoder = renderer.device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                label: Some("Capture Test Encoder"),
+            });
+            let slot = capture.record(&mut encoder, &texture);
+            renderer.queue.submit(std::iter::once(encoder.finish()));
+            if let Some(slot) = slot {
+                capture.map(slot);
+            }
+            slot.is_some()
+        };
+
+        // Every slot fills, then frames are dropped until one is taken
+        for _ in 0..CAPTURE_SLOTS {
+            assert!(copy(&mut capture));
+        }
+        assert!(!copy(&mut capture));
+
+        let mut frame = None;
+        for _ in 0..1000 {
+            renderer.device.poll(wgpu::Maintain::Poll);
+            frame = capture.take(renderer.output_is_bgra());
+            if frame.is_some() {
+                break;
+            }
+            std::thread::sleep(std::time::Duration::from_millis(1));
+        }
+        let frame = frame.expect("no captured frame was mapped");
+        assert!(frame.to_rgba() == pixels, "captured pixels differ from the texture");
+
+        // Taken from another thread, and back in the ring once dropped
+        std::thread::spawn(move || drop(frame)).join().unwrap();
+        assert!(copy(&mut capture));
     }
 }
@@ -0,0 +1,64 @@
+//! Daily opening hours for unattended installations: `09:00-17:00`, several
+//! windows separated by commas, and windows past midnight like `20:00-02:00`.
+
+use chrono::{Local, NaiveTime, Timelike};
+use std::str::FromStr;
+
+const MINUTES_PER_DAY: u32 = 24 * 60;
+
+#[derive(Clone, Debug)]
+pub struct Schedule {
+    /// (start, end) in minutes after midnight, local time; end < start wraps
+    windows: Vec<(u32, u32)>,
+}
+
+impl Schedule {
+    /// The installation should be running right now
+    pub fn is_open(&self) -> bool {
+        let now = Local::now().time();
+        self.is_open_at(now.hour() * 60 + now.minute())
+    }
+
+    fn is_open_at(&self, minute: u32) -> bool {
+        self.windows.iter().any(|&(start, end)| {
+            if start <= end {
+                (start..end).contains(&minute)
+            } else {
+                minute >= start || minute < end
+            }
+        })
+    }
+}
+
+impl FromStr for Schedule {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let windows = s
+            .split(',')
+            .map(|window| {
+                let (start, end) = window
+                    .trim()
+                    .split_once('-')
+                    .ok_or_else(|| format!("'{}': expected a window like 09:00-17:00", window.trim()))?;
+                let (start, end) = (minute_of_day(start)?, minute_of_day(end)?);
+                if start == end {
+                    return Err(format!("'{}': the window is empty", window.trim()));
+                }
+                Ok((start, end))
+            })
+            .collect::<Result<Vec<_>, String>>()?;
+        Ok(Self { windows })
+    }
+}
+
+/// `HH:MM` as minutes after midnight; `24:00` is the end of the day
+fn minute_of_day(text: &str) -> Result<u32, String> {
+    let text = text.trim();
+    if text == "24:00" {
+        return Ok(MINUTES_PER_DAY);
+    }
+    NaiveTime::parse_from_str(text, "%H:%M")
+        .map(|time| time.hour() * 60 + time.minute())
+        .map_err(|_| format!("'{}': expected a time like 09:00", text))
+}
@@ -0,0 +1,107 @@
+//! Control sessions on disk: every command the app applies, with the seconds
+//! since the session started, one JSON object per line, e.g.
+//!
+//! ```text
+//! {"t":0.512,"command":{"DisplaceX":0.7}}
+//! {"t":1.204,"command":"SetTriangleMesh"}
+//! ```
+//!
+//! A replay feeds them back at the recorded timing, for reproducing a bug
+//! report or running a scripted performance.
+
+use crate::midi::MidiCommand;
+use serde::{Deserialize, Serialize};
+use std::fs::File;
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::path::Path;
+use std::time::Instant;
+
+#[derive(Serialize, Deserialize)]
+struct Entry {
+    /// Seconds since the start of the session
+    t: f64,
+    command: MidiCommand,
+}
+
+pub struct CommandRecorder {
+    writer: BufWriter<File>,
+    start: Instant,
+}
+
+impl CommandRecorder {
+    /// Start a session in `path`, replacing any file there
+    pub fn create(path: &Path) -> Result<Self, String> {
+        let file = File::create(path).map_err(|e| format!("Failed to create {}: {}", path.display(), e))?;
+        Ok(Self {
+            writer: BufWriter::new(file),
+            start: Instant::now(),
+        })
+    }
+
+    /// Append a command, flushed right away so a crash keeps everything up to it
+    pub fn record(&mut self, command: &MidiCommand) {
+        let entry = Entry {
+            t: self.start.elapsed().as_secs_f64(),
+            command: command.clone(),
+        };
+        let written = serde_json::to_writer(&mut self.writer, &entry)
+            .map_err(|e| e.to_string())
+            .and_then(|()| writeln!(self.writer).and_then(|()| self.writer.flush()).map_err(|e| e.to_string()));
+        if let Err(e) = written {
+            log::warn!("Failed to record command: {}", e);
+        }
+    }
+}
+
+pub struct CommandReplay {
+    /// Recorded commands, oldest last so they pop off the end
+    pending: Vec<Entry>,
+    start: Instant,
+}
+
+impl CommandReplay {
+    /// Read a recorded session; the clock starts now. Bad lines are logged and skipped.
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let file = File::open(path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+
+        let mut pending = Vec::new();
+        for (number, line) in BufReader::new(file).lines().enumerate() {
+            let line = line.map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            match serde_json::from_str::<Entry>(&line) {
+                Ok(entry) => pending.push(entry),
+                Err(e) => log::warn!("{}:{}: {}", path.display(), number + 1, e),
+            }
+        }
+        pending.sort_by(|a, b| b.t.total_cmp(&a.t));
+
+        Ok(Self {
+            pending,
+            start: Instant::now(),
+        })
+    }
+
+    /// Commands whose time has come
+    pub fn poll_all(&mut self) -> Vec<MidiCommand> {
+        self.poll_until(self.start.elapsed().as_secs_f64())
+    }
+
+    /// Commands recorded up to `now` seconds into the session, for replaying
+    /// against a clock other than the wall clock (offline rendering)
+    pub fn poll_until(&mut self, now: f64) -> Vec<MidiCommand> {
+        let mut due = Vec::new();
+        while self.pending.last().is_some_and(|entry| entry.t <= now) {
+            if let Some(entry) = self.pending.pop() {
+                due.push(entry.command);
+            }
+        }
+        due
+    }
+
+    /// Every recorded command has been played
+    pub fn is_finished(&self) -> bool {
+        self.pending.is_empty()
+    }
+}
@@ -1,9 +1,22 @@
-use crate::mesh::MeshType;
+use crate::audio::AudioBand;
+use crate::mesh::{LineRender, MeshType};
 use crate::midi::MidiCommand;
 use crate::p_lock::PLockSystem;
+use crate::noise::NoiseKind;
+use crate::params::ParamId;
+use crate::tracking::{AutoCenter, AutoCenterMode, MotionEnergy};
+use std::time::{Duration, Instant};
 
-/// Maximum number of concurrent ripples
-pub const MAX_RIPPLES: usize = 4;
+/// Upper bound on concurrent ripples (uniform array size); the count in use
+/// is `RippleSystem::count`
+pub const MAX_RIPPLES: usize = 16;
+/// Concurrent ripples unless --max-ripples says otherwise
+pub const DEFAULT_RIPPLES: usize = 4;
+/// Strongest ripple a kick spawns, the same as a click's
+pub const MAX_KICK_RIPPLE: f32 = 1.0;
+/// Shortest time between kick ripples: longer than the bass takes to rise on
+/// one hit, shorter than 8th notes at 180 BPM
+const MIN_KICK_RIPPLE_INTERVAL: Duration = Duration::from_millis(150);
 
 /// A single ripple effect (concentric wave)
 #[derive(Clone, Copy, Default)]
@@ -22,33 +35,98 @@ pub struct Ripple {
 
 impl Ripple {
     /// Convert to array for shader uniform [x, y, radius, intensity]
-    pub fn to_array(&self) -> [f32; 4] {
+    pub fn to_array(self) -> [f32; 4] {
         [self.x, self.y, self.radius, self.intensity]
     }
 }
 
+/// Ring profile of the ripples, matching ripple_displacement in the shader
+#[derive(Clone, Copy, Debug, Default, PartialEq, clap::ValueEnum)]
+pub enum RippleShape {
+    /// Smooth single swell
+    #[default]
+    Gaussian,
+    /// Wave train around the front
+    Sine,
+    /// Sharp shockwave front with a ramp behind it
+    Saw,
+    /// Hard-edged band
+    Square,
+}
+
+impl RippleShape {
+    const ALL: [RippleShape; 4] = [
+        RippleShape::Gaussian,
+        RippleShape::Sine,
+        RippleShape::Saw,
+        RippleShape::Square,
+    ];
+
+    /// Shape by index, clamped to the last one
+    pub fn from_index(index: usize) -> Self {
+        Self::ALL[index.min(Self::ALL.len() - 1)]
+    }
+
+    pub fn next(self) -> Self {
+        Self::ALL[(self as usize + 1) % Self::ALL.len()]
+    }
+}
+
 /// Manages multiple ripple effects
 pub struct RippleSystem {
     pub ripples: [Ripple; MAX_RIPPLES],
+    /// Shared by all ripples
+    pub shape: RippleShape,
+    /// Ripples in use (1 - MAX_RIPPLES); a new one replaces the oldest past this
+    count: usize,
     next_index: usize,
     /// Expansion speed
     pub expansion_rate: f32,
     /// Fade rate
     pub fade_rate: f32,
+    /// xorshift32 state for `spawn_random`, never 0
+    rng: u32,
+    /// When `kick` last spawned a ripple
+    last_kick: Option<Instant>,
+}
+
+/// Generator state for ripple seed `seed`, spreading nearby seeds apart
+fn ripple_rng_state(seed: u32) -> u32 {
+    seed.wrapping_mul(0x9E37_79B9).wrapping_add(0x6D2B_79F5).max(1)
 }
 
 impl Default for RippleSystem {
     fn default() -> Self {
         Self {
             ripples: [Ripple::default(); MAX_RIPPLES],
+            shape: RippleShape::default(),
+            count: DEFAULT_RIPPLES,
             next_index: 0,
             expansion_rate: 0.02,  // How fast ripples expand
             fade_rate: 0.02,      // How fast ripples fade
+            rng: ripple_rng_state(0),
+            last_kick: None,
         }
     }
 }
 
 impl RippleSystem {
+    /// Set how many ripples run at once, clamped to 1 - MAX_RIPPLES
+    pub fn set_count(&mut self, count: usize) {
+        if count > MAX_RIPPLES {
+            log::warn!("Limiting ripples to {}", MAX_RIPPLES);
+        }
+        self.count = count.clamp(1, MAX_RIPPLES);
+        self.next_index %= self.count;
+        for ripple in &mut self.ripples[self.count..] {
+            *ripple = Ripple::default();
+        }
+    }
+
+    pub fn count(&self) -> usize {
+        self.count
+    }
+
     /// Spawn a new ripple at a random or specified position
     pub fn spawn(&mut self, x: f32, y: f32, intensity: f32) {
         self.ripples[self.next_index] = Ripple {
@@ -58,21 +136,42 @@ impl RippleSystem {
             intensity,
             active: true,
         };
-        self.next_index = (self.next_index + 1) % MAX_RIPPLES;
+        self.next_index = (self.next_index + 1) % self.count;
     }
 
     /// Spawn ripple at random position
     pub fn spawn_random(&mut self, intensity: f32) {
-        // Simple pseudo-random using time-based seed
-        let t = std::time::SystemTime::now()
-            .duration_since(std::time::UNIX_EPOCH)
-            .unwrap_or_default()
-            .as_nanos();
-        let x = ((t % 1000) as f32) / 1000.0;
-        let y = (((t / 1000) % 1000) as f32) / 1000.0;
+        let x = self.next_random();
+        let y = self.next_random();
         self.spawn(x, y, intensity);
     }
 
+    /// Ripple at a random position for an audio kick, unless the last one was
+    /// too recent: a drum hit rises over several updates and should make one
+    pub fn kick(&mut self, intensity: f32) {
+        if self.last_kick.is_some_and(|last| last.elapsed() < MIN_KICK_RIPPLE_INTERVAL) {
+            return;
+        }
+        self.last_kick = Some(Instant::now());
+        self.spawn_random(intensity);
+    }
+
+    /// Restart the random positions; the same seed gives the same sequence
+    pub fn reseed(&mut self, seed: u32) {
+        self.rng = ripple_rng_state(seed);
+    }
+
+    /// Next random value in 0 - 1 (xorshift32)
+    fn next_random(&mut self) -> f32 {
+        let mut x = self.rng;
+        x ^= x << 13;
+        x ^= x >> 17;
+        x ^= x << 5;
+        self.rng = x;
+        // Top 24 bits, exact in an f32
+        (x >> 8) as f32 / (1 << 24) as f32
+    }
+
     /// Update all ripples (call each frame)
     pub fn update(&mut self) {
         for ripple in &mut self.ripples {
@@ -88,6 +187,269 @@ impl RippleSystem {
     }
 }
 
+/// Photosensitivity limits: no more than 3 flashes a second (the common
+/// guideline), and never a full white-out
+const MIN_FLASH_INTERVAL: Duration = Duration::from_millis(334);
+pub const MAX_FLASH_INTENSITY: f32 = 0.8;
+/// Seconds for a flash to fall to ~37%
+const FLASH_DECAY_SECS: f32 = 0.08;
+
+/// What a strobe flash does to the frame
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, clap::ValueEnum, serde::Serialize, serde::Deserialize)]
+pub enum FlashMode {
+    #[default]
+    Off,
+    /// Fade toward white
+    Brighten,
+    /// Fade toward the inverted colors
+    Invert,
+}
+
+impl FlashMode {
+    pub fn next(self) -> Self {
+        match self {
+            FlashMode::Off => FlashMode::Brighten,
+            FlashMode::Brighten => FlashMode::Invert,
+            FlashMode::Invert => FlashMode::Off,
+        }
+    }
+}
+
+/// Full-frame flash on strong audio onsets, rate and intensity capped
+pub struct Strobe {
+    pub mode: FlashMode,
+    /// Onset (kick) level that fires a flash
+    pub threshold: f32,
+    /// Peak of a flash (0 - MAX_FLASH_INTENSITY)
+    pub intensity: f32,
+    last_flash: Option<Instant>,
+    /// Peak of the flash in progress
+    peak: f32,
+}
+
+impl Default for Strobe {
+    fn default() -> Self {
+        Self {
+            mode: FlashMode::Off,
+            threshold: 0.6,
+            intensity: 0.6,
+            last_flash: None,
+            peak: 0.0,
+        }
+    }
+}
+
+impl Strobe {
+    /// Fire on an onset above the threshold, unless the last flash was too recent
+    pub fn onset(&mut self, level: f32) {
+        if self.mode == FlashMode::Off || level < self.threshold {
+            return;
+        }
+        self.pulse(self.intensity);
+    }
+
+    /// Flash at `intensity` whatever the mode (brightening when off), e.g. to
+    /// mark the p_lock loop point. Subject to the same rate limit.
+    pub fn pulse(&mut self, intensity: f32) {
+        if self.last_flash.is_some_and(|last| last.elapsed() < MIN_FLASH_INTERVAL) {
+            return;
+        }
+        self.last_flash = Some(Instant::now());
+        self.peak = intensity.clamp(0.0, MAX_FLASH_INTENSITY);
+    }
+
+    /// Current flash amount (0 - MAX_FLASH_INTENSITY), decaying in wall-clock time
+    pub fn level(&self) -> f32 {
+        let Some(last) = self.last_flash else { return 0.0 };
+        self.peak * (-last.elapsed().as_secs_f32() / FLASH_DECAY_SECS).exp()
+    }
+
+    /// A color as the flash shows it, matching the shader
+    pub fn apply(&self, color: [f32; 4]) -> [f32; 4] {
+        let flash = self.level();
+        let target = |c: f32| match self.mode {
+            FlashMode::Invert => 1.0 - c,
+            _ => 1.0,
+        };
+        let mix = |c: f32| c + (target(c) - c) * flash;
+        [mix(color[0]), mix(color[1]), mix(color[2]), color[3]]
+    }
+}
+
+/// What drives an axis' displacement
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum DisplaceSource {
+    /// Video luma times the displace amount, plus the LFO (original behavior)
+    #[default]
+    Classic,
+    /// The axis' noise texture
+    Noise,
+    /// The axis' LFO alone
+    Lfo,
+    /// Audio bass envelope
+    Audio,
+    /// Video luma alone
+    Luma,
+}
+
+impl DisplaceSource {
+    const ALL: [DisplaceSource; 5] = [
+        DisplaceSource::Classic,
+        DisplaceSource::Noise,
+        DisplaceSource::Lfo,
+        DisplaceSource::Audio,
+        DisplaceSource::Luma,
+    ];
+
+    /// Source by index, clamped to the last one
+    pub fn from_index(index: usize) -> Self {
+        Self::ALL[index.min(Self::ALL.len() - 1)]
+    }
+
+    pub fn next(self) -> Self {
+        Self::ALL[(self as usize + 1) % Self::ALL.len()]
+    }
+}
+
+/// LFO waveform, by the index `oscillate` in the shader switches on.
+/// Serialized as that index, like the bare numbers it replaces.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(try_from = "i32", into = "i32")]
+pub enum LfoShape {
+    #[default]
+    Sine,
+    /// Sign of the sine
+    Square,
+    /// Soft saw: 1 down to -1 and back once per cycle
+    Triangle,
+    /// Sampled from the X noise texture instead of the phase
+    Noise,
+}
+
+impl LfoShape {
+    const ALL: [LfoShape; 4] = [LfoShape::Sine, LfoShape::Square, LfoShape::Triangle, LfoShape::Noise];
+
+    /// Shape by shader index (0-3)
+    pub fn from_i32(index: i32) -> Option<Self> {
+        usize::try_from(index).ok().and_then(|i| Self::ALL.get(i).copied())
+    }
+
+    /// Shader index
+    pub fn to_i32(self) -> i32 {
+        self as i32
+    }
+
+    pub fn next(self) -> Self {
+        Self::ALL[(self as usize + 1) % Self::ALL.len()]
+    }
+
+    /// Waveform at `phase` (radians), -1 to 1, computed like the shader does.
+    /// Noise depends on the noise texture and gives its mean, 0.
+    pub fn evaluate(self, phase: f32) -> f32 {
+        match self {
+            LfoShape::Sine => phase.sin(),
+            // WGSL sign(0) is 0, unlike f32::signum
+            LfoShape::Square => {
+                let sine = phase.sin();
+                if sine == 0.0 {
+                    0.0
+                } else {
+                    sine.signum()
+                }
+            }
+            LfoShape::Triangle => {
+                let t = (phase / std::f32::consts::TAU).rem_euclid(1.0);
+                4.0 * (t - 0.5).abs() - 1.0
+            }
+            LfoShape::Noise => 0.0,
+        }
+    }
+}
+
+impl TryFrom<i32> for LfoShape {
+    type Error = String;
+
+    fn try_from(index: i32) -> Result<Self, Self::Error> {
+        Self::from_i32(index).ok_or_else(|| format!("LFO shape {} out of range (0-{})", index, Self::ALL.len() - 1))
+    }
+}
+
+impl From<LfoShape> for i32 {
+    fn from(shape: LfoShape) -> Self {
+        shape.to_i32()
+    }
+}
+
+/// Largest grid density offset from the [ / ] keys, beyond which the clamped
+/// grid stops changing
+const MAX_SCALE_KEY: i32 = 126;
+
+/// Per-axis noise parameters, in X, Y, Z order like `noise_theta`
+pub const NOISE_RESOLUTION: [ParamId; 3] =
+    [ParamId::XNoiseResolution, ParamId::YNoiseResolution, ParamId::ZNoiseResolution];
+pub const NOISE_AMP: [ParamId; 3] = [ParamId::XNoiseAmp, ParamId::YNoiseAmp, ParamId::ZNoiseAmp];
+
+/// Stacked LFOs summed onto the main one, two per axis in X, X, Y, Y, Z, Z order
+pub const EXTRA_LFOS: usize = 6;
+
+/// An LFO stacked on top of an axis' main LFO. Values are in p_lock-like units
+/// and scaled in `raw_render_params` the same way as the main LFO.
+#[derive(Clone, Copy, Default)]
+pub struct ExtraLfo {
+    /// Phase increment per update
+    pub rate: f32,
+    pub amp: f32,
+    /// Spatial frequency
+    pub frequency: f32,
+    pub shape: LfoShape,
+    /// Phase accumulator
+    pub phase: f32,
+}
+
+impl ExtraLfo {
+    /// Field by `ParamId::extra_lfo` index (0 = rate, 1 = amp, 2 = frequency)
+    pub fn param_mut(&mut self, field: usize) -> &mut f32 {
+        match field {
+            0 => &mut self.rate,
+            1 => &mut self.amp,
+            _ => &mut self.frequency,
+        }
+    }
+}
+
+/// Camera projection for the mesh
+#[derive(Clone, Copy, Debug, PartialEq, clap::ValueEnum)]
+pub enum ProjectionMode {
+    /// Flat, rotation only skews the mesh
+    #[value(alias = "ortho")]
+    Orthographic,
+    /// Foreshortened, so rotation reads as 3D
+    Perspective,
+}
+
+impl ProjectionMode {
+    pub fn next(self) -> Self {
+        match self {
+            ProjectionMode::Orthographic => ProjectionMode::Perspective,
+            ProjectionMode::Perspective => ProjectionMode::Orthographic,
+        }
+    }
+}
+
+/// Effect groups that can be soloed (everything else muted while held)
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Solo {
+    /// Brightness-driven X/Y displacement
+    Displacement,
+    ZLfo,
+    XLfo,
+    YLfo,
+    /// Luma key, invert and greyscale
+    Color,
+    Chroma,
+    Feedback,
+}
+
 /// All application state / parameters
 pub struct AppState {
     // Display
@@ -99,10 +461,32 @@ pub struct AppState {
     pub y_lfo_arg: f32,
     pub z_lfo_arg: f32,
 
+    // Noise animation phases (X, Y, Z), advanced by the LFO rate times the
+    // per-axis time scale (1 = in step with the LFO)
+    pub noise_theta: [f32; 3],
+    pub noise_time_scale: [f32; 3],
+    // Noise feature size and displacement gain (X, Y, Z), independent of
+    // the LFO frequencies
+    pub noise_resolution: [f32; 3],
+    pub noise_amp: [f32; 3],
+    // Noise function per axis (X, Y, Z)
+    pub noise_kind: [NoiseKind; 3],
+    // Keep the noise textures as they are (snapshot or loaded maps) while
+    // everything else animates
+    pub noise_frozen: bool,
+
+    // LFOs stacked on the main ones (amplitude 0 = off)
+    pub extra_lfos: [ExtraLfo; EXTRA_LFOS],
+
     // LFO shapes (0=sine, 1=square, 2=saw, 3=noise)
-    pub x_lfo_shape: i32,
-    pub y_lfo_shape: i32,
-    pub z_lfo_shape: i32,
+    pub x_lfo_shape: LfoShape,
+    pub y_lfo_shape: LfoShape,
+    pub z_lfo_shape: LfoShape,
+
+    // Displacement source per axis
+    pub x_source: DisplaceSource,
+    pub y_source: DisplaceSource,
+    pub z_source: DisplaceSource,
 
     // Ring modulation switches
     pub x_ringmod: bool,
@@ -125,10 +509,21 @@ pub struct AppState {
     pub invert: bool,
     pub greyscale: bool,
     pub luma_switch: bool,
+    // Glow around bright areas; the switch bypasses it without losing the settings
+    pub bloom: bool,
+    pub bloom_intensity: f32,
+    pub bloom_threshold: f32,
+    pub audio_bloom_gain: f32,
 
     // Mesh
     pub mesh_type: MeshType,
+    pub line_render: LineRender,
     pub scale: u32,
+    // Band-limit the LFO displacement to what the grid can show, so sparse
+    // meshes don't tear at high LFO frequencies (0 = off, 1 = full)
+    pub mesh_smoothing: f32,
+    // Scale knob turned up = denser grid (the original maps it the other way)
+    pub invert_scale: bool,
 
     // Transforms
     pub global_x_displace: f32,
@@ -139,6 +534,81 @@ pub struct AppState {
 
     // Stroke
     pub stroke_weight: f32,
+    // Audio added on top of the stroke weight: source, gain (0 = off) and current amount
+    pub audio_stroke_band: AudioBand,
+    pub audio_stroke_gain: f32,
+    pub audio_stroke_mod: f32,
+    // Audio added on top of the RGB split: source, gain (0 = off) and current amount
+    pub audio_chroma_band: AudioBand,
+    pub audio_chroma_gain: f32,
+    pub audio_chroma_mod: f32,
+    // Audio nudging the rotation: source, radians per axis at full level (0 = off)
+    // and the decaying offset added on top of rotate_x/y/z
+    pub audio_rotate_band: AudioBand,
+    pub audio_rotate_gain: [f32; 3],
+    pub audio_rotate_mod: [f32; 3],
+    // Audio scaling the whole mesh (breathing zoom): source, extra scale at full
+    // level (0 = off), envelope attack / release per update (0-1) and current amount
+    pub audio_zoom_band: AudioBand,
+    pub audio_zoom_amount: f32,
+    pub audio_zoom_attack: f32,
+    pub audio_zoom_release: f32,
+    pub audio_zoom_mod: f32,
+    // Multiplier on displacement, LFO amplitudes and audio modulation (0 = clean video)
+    pub master_intensity: f32,
+    // Audio displacing X and Y separately: source, amount at full level (0 = off)
+    // and the current offsets
+    pub audio_displace_band: [AudioBand; 2],
+    pub audio_displace_gain: [f32; 2],
+    pub audio_displace_mod: [f32; 2],
+
+    // Auto-framing on the video content, and the center offset it currently adds
+    pub auto_center: AutoCenter,
+    pub auto_center_offset: [f32; 2],
+
+    // Idle drift added on top of the rotation and center controls
+    pub idle_rotate: [f32; 3],
+    pub idle_center: [f32; 2],
+
+    // Crossfade between first and second video source (0 = first only)
+    pub video_mix: f32,
+
+    // RGB channel split amount (0 = off, 1 = max), and whether it pulls along
+    // the mesh displacement instead of the LFO-driven direction
+    pub chroma_split: f32,
+    pub chroma_follow: bool,
+
+    // Source correction ahead of every effect (luma key included):
+    // exposure in stops, gamma > 1 lifts the midtones
+    pub input_exposure: f32,
+    pub input_gamma: f32,
+
+    // Video feedback: fraction of the previous frame kept each frame (0 = off, 0.99 max)
+    pub feedback_decay: f32,
+
+    // Background color behind the mesh (sRGB RGBA, 0.0 - 1.0)
+    pub clear_color: [f32; 4],
+
+    // Projection; fov in degrees, near/far in mesh units (video pixels) from the camera
+    pub projection: ProjectionMode,
+    pub fov: f32,
+    pub near: f32,
+    pub far: f32,
+
+    // Ripples spawned by mouse clicks, and by audio kicks when kick_ripples is set
+    pub ripples: RippleSystem,
+    pub kick_ripples: bool,
+
+    // Beat-synced flash
+    pub strobe: Strobe,
+    // Flash marking each p_lock loop start while recording (0 = off)
+    pub loop_flash: f32,
+
+    // Master level of the whole output, mesh and background (1 = full, 0 = black)
+    pub output_level: f32,
+
+    // Transient solo override; stored values are left untouched
+    pub solo: Option<Solo>,
 
     // Keyboard offsets
     pub keyboard_offsets: KeyboardOffsets,
@@ -151,6 +621,14 @@ pub struct AppState {
     pub audio_mod_lfo: f32,
     pub audio_mod_z: f32,
 
+    // Audio bass envelope (displacement source)
+    pub audio_envelope: f32,
+
+    // Camera motion: estimator, smoothed energy (0 - 1) and its displacement gain (0 = off)
+    pub motion: MotionEnergy,
+    pub motion_energy: f32,
+    pub motion_displace: f32,
+
     // Audio wave effect - undulating lines
     pub audio_wave_phase: f32,
     pub audio_wave_amp: f32,
@@ -177,6 +655,10 @@ pub struct KeyboardOffsets {
     pub op: f32,
     pub er: f32,
     pub qw: f32,
+    /// LFO bias (numpad)
+    pub x_bias: f32,
+    pub y_bias: f32,
+    pub z_bias: f32,
     pub scale_key: i32,
 }
 
@@ -188,9 +670,19 @@ impl AppState {
             x_lfo_arg: 0.0,
             y_lfo_arg: 0.0,
             z_lfo_arg: 0.0,
-            x_lfo_shape: 0,
-            y_lfo_shape: 0,
-            z_lfo_shape: 0,
+            noise_theta: [0.0; 3],
+            noise_frozen: false,
+            noise_time_scale: [ParamId::NoiseTimeScale.spec().default; 3],
+            noise_resolution: NOISE_RESOLUTION.map(|id| id.spec().default),
+            noise_amp: NOISE_AMP.map(|id| id.spec().default),
+            noise_kind: [NoiseKind::Perlin; 3],
+            extra_lfos: [ExtraLfo::default(); EXTRA_LFOS],
+            x_lfo_shape: LfoShape::Sine,
+            y_lfo_shape: LfoShape::Sine,
+            z_lfo_shape: LfoShape::Sine,
+            x_source: DisplaceSource::Classic,
+            y_source: DisplaceSource::Classic,
+            z_source: DisplaceSource::Classic,
             x_ringmod: false,
             y_ringmod: false,
             z_ringmod: false,
@@ -205,62 +697,140 @@ impl AppState {
             invert: false,
             greyscale: false,
             luma_switch: false,
+            bloom: true,
+            bloom_intensity: ParamId::BloomIntensity.spec().default,
+            bloom_threshold: ParamId::BloomThreshold.spec().default,
+            audio_bloom_gain: ParamId::AudioBloomGain.spec().default,
             mesh_type: MeshType::Triangles,
+            line_render: LineRender::Hard,
+            mesh_smoothing: ParamId::MeshSmoothing.spec().default,
             scale: 64,
+            invert_scale: false,
             global_x_displace: 0.0,
             global_y_displace: 0.0,
             rotate_x: 0.0,
             rotate_y: 0.0,
             rotate_z: 0.0,
-            stroke_weight: 1.0,
+            stroke_weight: ParamId::StrokeWeight.spec().default,
+            audio_stroke_band: AudioBand::Bass,
+            audio_stroke_gain: ParamId::AudioStrokeGain.spec().default,
+            audio_stroke_mod: 0.0,
+            audio_chroma_band: AudioBand::Rms,
+            audio_chroma_gain: ParamId::AudioChromaGain.spec().default,
+            audio_chroma_mod: 0.0,
+            audio_rotate_band: AudioBand::Kick,
+            audio_displace_band: [AudioBand::Left, AudioBand::Right],
+            audio_displace_gain: [0.0; 2],
+            audio_displace_mod: [0.0; 2],
+            audio_rotate_gain: [0.0; 3],
+            audio_rotate_mod: [0.0; 3],
+            audio_zoom_band: AudioBand::Bass,
+            audio_zoom_amount: ParamId::AudioZoomAmount.spec().default,
+            audio_zoom_attack: 0.4,
+            audio_zoom_release: 0.08,
+            audio_zoom_mod: 0.0,
+            master_intensity: ParamId::MasterIntensity.spec().default,
+            auto_center: AutoCenter::new(AutoCenterMode::Off, ParamId::AutoCenterSpeed.spec().default),
+            auto_center_offset: [0.0; 2],
+            idle_rotate: [0.0; 3],
+            idle_center: [0.0; 2],
+            video_mix: ParamId::VideoMix.spec().default,
+            chroma_split: ParamId::ChromaSplit.spec().default,
+            chroma_follow: false,
+            input_exposure: ParamId::InputExposure.spec().default,
+            input_gamma: ParamId::InputGamma.spec().default,
+            feedback_decay: ParamId::FeedbackDecay.spec().default,
+            clear_color: [0.0, 0.0, 0.0, 1.0],
+            projection: ProjectionMode::Orthographic,
+            fov: ParamId::Fov.spec().default,
+            near: 1.0,
+            far: 10000.0,
+            ripples: RippleSystem::default(),
+            kick_ripples: false,
+            strobe: Strobe::default(),
+            loop_flash: 0.0,
+            output_level: 1.0,
+            solo: None,
             keyboard_offsets: KeyboardOffsets::default(),
             p_lock: PLockSystem::new(),
             audio_mod_displacement: 0.0,
             audio_mod_lfo: 0.0,
             audio_mod_z: 0.0,
+            audio_envelope: 0.0,
+            motion: MotionEnergy::new(),
+            motion_energy: 0.0,
+            motion_displace: 0.0,
             audio_wave_phase: 0.0,
             audio_wave_amp: 0.0,
             audio_wave_freq: 15.0, // Base wave frequency
-            audio_sensitivity: 1.0, // Default sensitivity (1.0 = normal)
+            audio_sensitivity: ParamId::AudioSensitivity.spec().default, // 1.0 = normal
         }
     }
 
     /// Process a MIDI command and update state accordingly
     pub fn process_midi(&mut self, cmd: MidiCommand) {
-        const THRESHOLD: f32 = 0.04;
-
         match cmd {
-            MidiCommand::LumaKeyLevel(v) => self.p_lock.set_with_latch(0, v, THRESHOLD),
-            MidiCommand::DisplaceX(v) => self.p_lock.set_with_latch(1, v, THRESHOLD),
-            MidiCommand::DisplaceY(v) => self.p_lock.set_with_latch(2, v, THRESHOLD),
-            MidiCommand::ZFrequency(v) => self.p_lock.set_with_latch(3, v, THRESHOLD),
-            MidiCommand::XFrequency(v) => self.p_lock.set_with_latch(4, v, THRESHOLD),
-            MidiCommand::YFrequency(v) => self.p_lock.set_with_latch(5, v, THRESHOLD),
-            MidiCommand::Zoom(v) => self.p_lock.set_with_latch(6, v, THRESHOLD),
-            MidiCommand::Scale(v) => self.p_lock.set_with_latch(7, v, THRESHOLD),
-            MidiCommand::CenterX(v) => self.p_lock.set_with_latch(8, v, THRESHOLD),
-            MidiCommand::CenterY(v) => self.p_lock.set_with_latch(9, v, THRESHOLD),
-            MidiCommand::ZLfoArg(v) => self.p_lock.set_with_latch(10, v, THRESHOLD),
-            MidiCommand::ZLfoAmp(v) => self.p_lock.set_with_latch(11, v, THRESHOLD),
-            MidiCommand::XLfoArg(v) => self.p_lock.set_with_latch(12, v, THRESHOLD),
-            MidiCommand::XLfoAmp(v) => self.p_lock.set_with_latch(13, v, THRESHOLD),
-            MidiCommand::YLfoArg(v) => self.p_lock.set_with_latch(14, v, THRESHOLD),
-            MidiCommand::YLfoAmp(v) => self.p_lock.set_with_latch(15, v, THRESHOLD),
+            MidiCommand::LumaKeyLevel(v) => self.set_param(ParamId::LumaKeyLevel, v),
+            MidiCommand::DisplaceX(v) => self.set_param(ParamId::DisplaceX, v),
+            MidiCommand::DisplaceY(v) => self.set_param(ParamId::DisplaceY, v),
+            MidiCommand::ZFrequency(v) => self.set_param(ParamId::ZFrequency, v),
+            MidiCommand::XFrequency(v) => self.set_param(ParamId::XFrequency, v),
+            MidiCommand::YFrequency(v) => self.set_param(ParamId::YFrequency, v),
+            MidiCommand::Zoom(v) => self.set_param(ParamId::Zoom, v),
+            MidiCommand::Scale(v) => {
+                let v = if self.invert_scale { 1.0 - v } else { v };
+                self.set_param(ParamId::Scale, v)
+            }
+            MidiCommand::CenterX(v) => self.set_param(ParamId::CenterX, v),
+            MidiCommand::CenterY(v) => self.set_param(ParamId::CenterY, v),
+            MidiCommand::ZLfoArg(v) => self.set_param(ParamId::ZLfoRate, v),
+            MidiCommand::ZLfoAmp(v) => self.set_param(ParamId::ZLfoAmp, v),
+            MidiCommand::XLfoArg(v) => self.set_param(ParamId::XLfoRate, v),
+            MidiCommand::XLfoAmp(v) => self.set_param(ParamId::XLfoAmp, v),
+            MidiCommand::YLfoArg(v) => self.set_param(ParamId::YLfoRate, v),
+            MidiCommand::YLfoAmp(v) => self.set_param(ParamId::YLfoAmp, v),
 
             MidiCommand::RecordStart => self.p_lock.start_recording(),
             MidiCommand::RecordStop => self.p_lock.stop_recording(),
+            MidiCommand::Quantize(slot, divisions) => {
+                match slot {
+                    Some(slot) => self.p_lock.quantize(slot, divisions),
+                    None => self.p_lock.quantize_all(divisions),
+                }
+                log::info!("Quantized {} to {} steps", slot.map_or("all parameters".to_string(), |s| format!("slot {}", s)), divisions);
+            }
+
+            MidiCommand::Clock => self.p_lock.clock_tick(),
+            MidiCommand::ClockStart => self.p_lock.clock_start(),
+            MidiCommand::ClockContinue => self.p_lock.clock_continue(),
+            MidiCommand::ClockStop => self.p_lock.clock_stop(),
             MidiCommand::Reset => {
                 self.p_lock.clear();
                 self.global_x_displace = 0.0;
                 self.global_y_displace = 0.0;
-                self.rotate_x = 0.0;
-                self.rotate_y = 0.0;
-                self.rotate_z = 0.0;
+                self.reset_rotation();
             }
 
-            MidiCommand::ZLfoShape(s) => self.z_lfo_shape = s,
-            MidiCommand::XLfoShape(s) => self.x_lfo_shape = s,
-            MidiCommand::YLfoShape(s) => self.y_lfo_shape = s,
+            MidiCommand::ZLfoShape(shape) => self.z_lfo_shape = shape,
+            MidiCommand::XLfoShape(shape) => self.x_lfo_shape = shape,
+            MidiCommand::YLfoShape(shape) => self.y_lfo_shape = shape,
+
+            MidiCommand::RippleShape(i) => self.ripples.shape = RippleShape::from_index(i),
+            MidiCommand::NoiseKind(axis, i) => {
+                if let Some(kind) = self.noise_kind.get_mut(axis) {
+                    *kind = NoiseKind::from_index(i);
+                }
+            }
+
+            MidiCommand::ExtraLfoShape(lfo, shape) => {
+                if let Some(extra) = self.extra_lfos.get_mut(lfo) {
+                    extra.shape = shape;
+                }
+            }
+
+            MidiCommand::ZSource(i) => self.z_source = DisplaceSource::from_index(i),
+            MidiCommand::XSource(i) => self.x_source = DisplaceSource::from_index(i),
+            MidiCommand::YSource(i) => self.y_source = DisplaceSource::from_index(i),
 
             MidiCommand::ZRingMod(v) => self.z_ringmod = v,
             MidiCommand::XRingMod(v) => self.x_ringmod = v,
@@ -279,36 +849,285 @@ impl AppState {
             }
             MidiCommand::SetHorizontalLines => self.mesh_type = MeshType::HorizontalLines,
             MidiCommand::SetVerticalLines => self.mesh_type = MeshType::VerticalLines,
+            MidiCommand::SetGridMesh => self.mesh_type = MeshType::Grid,
+            MidiCommand::SetLineRender(render) => self.line_render = render,
+            MidiCommand::SetFlash(mode) => self.strobe.mode = mode,
             MidiCommand::SetWireframe => {
                 self.mesh_type = MeshType::Triangles;
                 self.wireframe = true;
             }
 
             MidiCommand::Greyscale(v) => self.greyscale = v,
+            MidiCommand::FreezeNoise(v) => self.noise_frozen = v,
+            MidiCommand::Bloom(v) => self.bloom = v,
+            MidiCommand::ChromaFollow(v) => self.chroma_follow = v,
             MidiCommand::Invert(v) => self.invert = v,
             MidiCommand::BrightSwitch(v) => self.bright_switch = v,
-            MidiCommand::StrokeWeight(v) => self.stroke_weight = v,
+            MidiCommand::StrokeWeight(v) => self.set_param(ParamId::StrokeWeight, v),
+            MidiCommand::AudioStrokeGain(v) => self.set_param(ParamId::AudioStrokeGain, v),
+            MidiCommand::VideoMix(v) => self.set_param(ParamId::VideoMix, v),
+            MidiCommand::ChromaSplit(v) => self.set_param(ParamId::ChromaSplit, v),
+            MidiCommand::FeedbackDecay(v) => self.set_param(ParamId::FeedbackDecay, v),
+            MidiCommand::AutoCenterSpeed(v) => self.set_param(ParamId::AutoCenterSpeed, v),
+            MidiCommand::Fov(v) => self.set_param(ParamId::Fov, v),
+            MidiCommand::NoiseTimeScale(v) => self.set_param(ParamId::NoiseTimeScale, v),
+            MidiCommand::InputExposure(v) => self.set_param(ParamId::InputExposure, v),
+            MidiCommand::InputGamma(v) => self.set_param(ParamId::InputGamma, v),
+            MidiCommand::MeshSmoothing(v) => self.set_param(ParamId::MeshSmoothing, v),
+            MidiCommand::XLfoBias(v) => self.set_param(ParamId::XLfoBias, v),
+            MidiCommand::YLfoBias(v) => self.set_param(ParamId::YLfoBias, v),
+            MidiCommand::ZLfoBias(v) => self.set_param(ParamId::ZLfoBias, v),
+            MidiCommand::AudioZoomAmount(v) => self.set_param(ParamId::AudioZoomAmount, v),
+            MidiCommand::MasterIntensity(v) => self.set_param(ParamId::MasterIntensity, v),
+            MidiCommand::BloomIntensity(v) => self.set_param(ParamId::BloomIntensity, v),
+            MidiCommand::BloomThreshold(v) => self.set_param(ParamId::BloomThreshold, v),
+            MidiCommand::AudioBloomGain(v) => self.set_param(ParamId::AudioBloomGain, v),
+            MidiCommand::AudioChromaGain(v) => self.set_param(ParamId::AudioChromaGain, v),
+            MidiCommand::Param(id, v) => self.set_param_direct(id, v),
+            MidiCommand::Control(id, v) => self.set_param(id, v),
+            MidiCommand::SpawnRipple(intensity) => self.ripples.spawn_random(intensity.clamp(0.0, MAX_KICK_RIPPLE)),
+            MidiCommand::ClearColor(channel, v) => {
+                let id = [ParamId::BackgroundRed, ParamId::BackgroundGreen, ParamId::BackgroundBlue];
+                if let Some(&id) = id.get(channel) {
+                    self.set_param(id, v);
+                }
+            }
+            MidiCommand::NoiseResolution(axis, v) => {
+                if let Some(&id) = NOISE_RESOLUTION.get(axis) {
+                    self.set_param(id, v);
+                }
+            }
+            MidiCommand::NoiseAmp(axis, v) => {
+                if let Some(&id) = NOISE_AMP.get(axis) {
+                    self.set_param(id, v);
+                }
+            }
 
             MidiCommand::RotateX(v) => self.rotate_x = v,
             MidiCommand::RotateY(v) => self.rotate_y = v,
             MidiCommand::RotateZ(v) => self.rotate_z = v,
-            MidiCommand::GlobalXDisplace(v) => {
-                if !v {
-                    self.global_x_displace = 0.0;
-                }
-            }
-            MidiCommand::GlobalYDisplace(v) => {
-                if !v {
-                    self.global_y_displace = 0.0;
-                }
+            MidiCommand::GlobalXDisplace(false) => self.global_x_displace = 0.0,
+            MidiCommand::GlobalYDisplace(false) => self.global_y_displace = 0.0,
+            _ => {}
+        }
+    }
+
+    /// Set a parameter from a normalized control value (0 - 1), scaled through
+    /// its spec. Recordable parameters go through the p_lock latch, so a knob
+    /// only takes over once it passes the current value.
+    pub fn set_param(&mut self, id: ParamId, normalized: f32) {
+        const THRESHOLD: f32 = 0.04;
+        self.write_param(id, normalized, Some(THRESHOLD));
+    }
+
+    /// Like `set_param` without the latch, for sources that send absolute
+    /// values (text control, config)
+    pub fn set_param_direct(&mut self, id: ParamId, normalized: f32) {
+        self.write_param(id, normalized, None);
+    }
+
+    fn write_param(&mut self, id: ParamId, normalized: f32, latch: Option<f32>) {
+        let value = id.spec().scale(normalized);
+        if let Some(slot) = id.p_lock_slot() {
+            match latch {
+                Some(threshold) => self.p_lock.set_with_latch(slot, value, threshold),
+                None => self.p_lock.set(slot, value),
             }
+            return;
+        }
+        if let Some((lfo, field)) = id.extra_lfo() {
+            *self.extra_lfos[lfo].param_mut(field) = value;
+            return;
+        }
+        match id {
+            ParamId::VideoMix => self.video_mix = value,
+            ParamId::ChromaSplit => self.chroma_split = value,
+            ParamId::FeedbackDecay => self.feedback_decay = value,
+            ParamId::BackgroundRed => self.clear_color[0] = value,
+            ParamId::BackgroundGreen => self.clear_color[1] = value,
+            ParamId::BackgroundBlue => self.clear_color[2] = value,
+            ParamId::StrokeWeight => self.stroke_weight = value,
+            ParamId::AudioStrokeGain => self.audio_stroke_gain = value,
+            ParamId::AutoCenterSpeed => self.auto_center.responsiveness = value,
+            ParamId::AudioSensitivity => self.audio_sensitivity = value,
+            ParamId::Fov => self.fov = value,
+            ParamId::NoiseTimeScale => self.noise_time_scale = [value; 3],
+            ParamId::InputExposure => self.input_exposure = value,
+            ParamId::InputGamma => self.input_gamma = value,
+            ParamId::MeshSmoothing => self.mesh_smoothing = value,
+            ParamId::AudioZoomAmount => self.audio_zoom_amount = value,
+            ParamId::MasterIntensity => self.master_intensity = value,
+            ParamId::XNoiseResolution => self.noise_resolution[0] = value,
+            ParamId::YNoiseResolution => self.noise_resolution[1] = value,
+            ParamId::ZNoiseResolution => self.noise_resolution[2] = value,
+            ParamId::XNoiseAmp => self.noise_amp[0] = value,
+            ParamId::YNoiseAmp => self.noise_amp[1] = value,
+            ParamId::ZNoiseAmp => self.noise_amp[2] = value,
+            ParamId::BloomIntensity => self.bloom_intensity = value,
+            ParamId::BloomThreshold => self.bloom_threshold = value,
+            ParamId::AudioBloomGain => self.audio_bloom_gain = value,
+            ParamId::AudioChromaGain => self.audio_chroma_gain = value,
             _ => {}
         }
     }
 
+    /// Current value of a parameter, in spec units
+    pub fn param(&self, id: ParamId) -> f32 {
+        if let Some(slot) = id.p_lock_slot() {
+            return self.p_lock.get(slot);
+        }
+        if let Some((lfo, field)) = id.extra_lfo() {
+            let mut extra = self.extra_lfos[lfo];
+            return *extra.param_mut(field);
+        }
+        match id {
+            ParamId::VideoMix => self.video_mix,
+            ParamId::ChromaSplit => self.chroma_split,
+            ParamId::FeedbackDecay => self.feedback_decay,
+            ParamId::BackgroundRed => self.clear_color[0],
+            ParamId::BackgroundGreen => self.clear_color[1],
+            ParamId::BackgroundBlue => self.clear_color[2],
+            ParamId::StrokeWeight => self.stroke_weight,
+            ParamId::AudioStrokeGain => self.audio_stroke_gain,
+            ParamId::AutoCenterSpeed => self.auto_center.responsiveness,
+            ParamId::AudioSensitivity => self.audio_sensitivity,
+            ParamId::Fov => self.fov,
+            ParamId::NoiseTimeScale => self.noise_time_scale[0],
+            ParamId::InputExposure => self.input_exposure,
+            ParamId::InputGamma => self.input_gamma,
+            ParamId::MeshSmoothing => self.mesh_smoothing,
+            ParamId::AudioZoomAmount => self.audio_zoom_amount,
+            ParamId::MasterIntensity => self.master_intensity,
+            ParamId::XNoiseResolution => self.noise_resolution[0],
+            ParamId::YNoiseResolution => self.noise_resolution[1],
+            ParamId::ZNoiseResolution => self.noise_resolution[2],
+            ParamId::XNoiseAmp => self.noise_amp[0],
+            ParamId::YNoiseAmp => self.noise_amp[1],
+            ParamId::ZNoiseAmp => self.noise_amp[2],
+            ParamId::BloomIntensity => self.bloom_intensity,
+            ParamId::BloomThreshold => self.bloom_threshold,
+            ParamId::AudioBloomGain => self.audio_bloom_gain,
+            ParamId::AudioChromaGain => self.audio_chroma_gain,
+            _ => 0.0,
+        }
+    }
+
+    /// Step a parameter by `delta` in spec units, clamped to its range
+    pub fn nudge_param(&mut self, id: ParamId, delta: f32) {
+        let spec = id.spec();
+        self.set_param_direct(id, spec.normalize(self.param(id) + delta));
+    }
+
+    /// Step the [ / ] grid density offset, kept within the range that still
+    /// changes the clamped grid
+    pub fn nudge_scale_key(&mut self, delta: i32) {
+        let ko = &mut self.keyboard_offsets;
+        ko.scale_key = (ko.scale_key + delta).clamp(-MAX_SCALE_KEY, MAX_SCALE_KEY);
+    }
+
+    pub fn reset_rotation(&mut self) {
+        self.rotate_x = 0.0;
+        self.rotate_y = 0.0;
+        self.rotate_z = 0.0;
+    }
+
+    /// Line thickness including the audio modulation
+    pub fn effective_stroke_weight(&self) -> f32 {
+        self.stroke_weight + self.audio_stroke_mod
+    }
+
+    /// False if another effect is soloed
+    pub fn effect_enabled(&self, effect: Solo) -> bool {
+        self.solo.is_none_or(|solo| solo == effect)
+    }
+
     /// Calculate derived parameters for rendering
     /// All values are in clip space (-1 to 1) for the WGSL shader
     pub fn calculate_render_params(&self) -> RenderParams {
+        let mut params = self.raw_render_params();
+
+        // Solo: mute amplitudes of the other effects (phases keep running)
+        if !self.effect_enabled(Solo::Displacement) {
+            params.displace_x = 0.0;
+            params.displace_y = 0.0;
+            params.audio_displacement = 0.0;
+            params.audio_displace_xy = [0.0; 2];
+        }
+        if !self.effect_enabled(Solo::ZLfo) {
+            params.z_lfo_amp = 0.0;
+            params.z_lfo_bias = 0.0;
+            params.audio_z = 0.0;
+            params.extra_lfos[4][1] = 0.0;
+            params.extra_lfos[5][1] = 0.0;
+        }
+        if !self.effect_enabled(Solo::XLfo) {
+            params.x_lfo_amp = 0.0;
+            params.x_lfo_bias = 0.0;
+            params.extra_lfos[0][1] = 0.0;
+            params.extra_lfos[1][1] = 0.0;
+        }
+        if !self.effect_enabled(Solo::YLfo) {
+            params.y_lfo_amp = 0.0;
+            params.y_lfo_bias = 0.0;
+            params.extra_lfos[2][1] = 0.0;
+            params.extra_lfos[3][1] = 0.0;
+        }
+        if !self.effect_enabled(Solo::Color) {
+            // Threshold out of reach in either key mode
+            params.luma_key_level = if self.luma_switch { 2.0 } else { -1.0 };
+        }
+        if !self.effect_enabled(Solo::Chroma) {
+            params.chroma_split = 0.0;
+        }
+
+        // Master intensity: fade every distortion toward the clean video
+        let intensity = self.master_intensity;
+        params.displace_x *= intensity;
+        params.displace_y *= intensity;
+        params.z_lfo_amp *= intensity;
+        params.x_lfo_amp *= intensity;
+        params.y_lfo_amp *= intensity;
+        params.x_lfo_bias *= intensity;
+        params.y_lfo_bias *= intensity;
+        params.z_lfo_bias *= intensity;
+        params.audio_displacement *= intensity;
+        params.audio_z *= intensity;
+        params.audio_displace_xy = params.audio_displace_xy.map(|xy| xy * intensity);
+        for lfo in &mut params.extra_lfos {
+            lfo[1] *= intensity;
+        }
+        params
+    }
+
+    /// Keyboard offset folded back into p_lock units for a slot, mirroring the
+    /// factors in `raw_render_params`
+    pub fn keyboard_offset(&self, slot: usize) -> f32 {
+        let ko = &self.keyboard_offsets;
+        match slot {
+            0 => 0.1 * ko.az,
+            1 => ko.qw,
+            2 => ko.er,
+            3 => ko.sx / 10.0,
+            4 => ko.gb / 10.0,
+            5 => ko.kk / 10.0,
+            6 => ko.op,
+            7 => -(ko.scale_key as f32) / 126.0,
+            8 => 0.05 * ko.ty,
+            9 => 0.05 * ko.ui,
+            10 => ko.dc,
+            11 => 0.1 * ko.fv,
+            12 => ko.hn,
+            13 => 0.05 * ko.jm,
+            14 => ko.ll,
+            15 => 0.05 * ko.ylfo_amp,
+            16 => ko.x_bias,
+            17 => ko.y_bias,
+            18 => ko.z_bias,
+            _ => 0.0,
+        }
+    }
+
+    fn raw_render_params(&self) -> RenderParams {
         let ko = &self.keyboard_offsets;
 
         RenderParams {
@@ -326,8 +1145,8 @@ impl AppState {
             // Grid density (1 to 127)
             scale: ((1.0 - self.p_lock.get(7)) * 126.0 + 1.0 + ko.scale_key as f32) as u32,
             // Center offset in clip space (-1 to 1)
-            center_x: 2.0 * (self.p_lock.get(8) - 0.5) + 0.1 * ko.ty,
-            center_y: 2.0 * (self.p_lock.get(9) - 0.5) + 0.1 * ko.ui,
+            center_x: 2.0 * (self.p_lock.get(8) - 0.5) + 0.1 * ko.ty + self.idle_center[0] + self.auto_center_offset[0],
+            center_y: 2.0 * (self.p_lock.get(9) - 0.5) + 0.1 * ko.ui + self.idle_center[1] + self.auto_center_offset[1],
             // LFO phase increment (controls animation speed)
             z_lfo_arg: self.p_lock.get(10) + ko.dc,
             // LFO amplitude in clip space (small values!)
@@ -336,9 +1155,22 @@ impl AppState {
             x_lfo_amp: 0.2 * self.p_lock.get(13) + 0.01 * ko.jm + 0.1 * self.audio_mod_lfo,
             y_lfo_arg: self.p_lock.get(14) + ko.ll,
             y_lfo_amp: 0.2 * self.p_lock.get(15) + 0.01 * ko.ylfo_amp + 0.1 * self.audio_mod_lfo,
+            // LFO centers in clip space: X/Y like the displacement, Z like its amplitude
+            x_lfo_bias: 0.5 * (self.p_lock.get(16) + ko.x_bias),
+            y_lfo_bias: 0.5 * (self.p_lock.get(17) + ko.y_bias),
+            z_lfo_bias: 0.1 * (self.p_lock.get(18) + ko.z_bias),
             // Audio modulation (small values for clip space)
-            audio_displacement: 0.1 * self.audio_mod_displacement,
+            audio_displacement: 0.1 * (self.audio_mod_displacement + self.motion_displace * self.motion_energy),
             audio_z: 0.05 * self.audio_mod_z,
+            audio_displace_xy: self.audio_displace_mod.map(|m| 0.1 * m),
+            // RGB split offset in tex coords (up to 2% of the frame), audio on top
+            chroma_split: 0.02 * (self.chroma_split + self.audio_chroma_mod).min(1.0),
+            // Same amplitude (Z 0.1, X/Y 0.2) and frequency (x10) scaling as the main LFOs
+            extra_lfos: std::array::from_fn(|i| {
+                let lfo = &self.extra_lfos[i];
+                let amp_scale = if i / 2 == 2 { 0.1 } else { 0.2 };
+                [lfo.phase, amp_scale * lfo.amp, 10.0 * lfo.frequency, lfo.shape.to_i32() as f32]
+            }),
         }
     }
 }
@@ -360,6 +1192,83 @@ pub struct RenderParams {
     pub x_lfo_amp: f32,
     pub y_lfo_arg: f32,
     pub y_lfo_amp: f32,
+    /// Offset added to each main LFO's output
+    pub x_lfo_bias: f32,
+    pub y_lfo_bias: f32,
+    pub z_lfo_bias: f32,
     pub audio_displacement: f32,
     pub audio_z: f32,
+    /// Per-axis audio displacement on top of `audio_displacement`
+    pub audio_displace_xy: [f32; 2],
+    pub chroma_split: f32,
+    /// Stacked LFOs as [phase, amp, spatial frequency, shape] in clip space
+    pub extra_lfos: [[f32; 4]; EXTRA_LFOS],
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::f32::consts::{FRAC_PI_2, PI, TAU};
+
+    const QUARTERS: [f32; 5] = [0.0, FRAC_PI_2, PI, 3.0 * FRAC_PI_2, TAU];
+
+    fn assert_shape(shape: LfoShape, expected: [f32; 5]) {
+        for (phase, expected) in QUARTERS.into_iter().zip(expected) {
+            let value = shape.evaluate(phase);
+            assert!((value - expected).abs() < 1e-5, "{:?} at {} is {}, expected {}", shape, phase, value, expected);
+        }
+    }
+
+    #[test]
+    fn sine_at_quarter_cycles() {
+        assert_shape(LfoShape::Sine, [0.0, 1.0, 0.0, -1.0, 0.0]);
+    }
+
+    #[test]
+    fn square_at_quarter_cycles() {
+        assert_eq!(LfoShape::Square.evaluate(0.0), 0.0);
+        assert_eq!(LfoShape::Square.evaluate(FRAC_PI_2), 1.0);
+        assert_eq!(LfoShape::Square.evaluate(3.0 * FRAC_PI_2), -1.0);
+        // π and 2π are edges where f32 sine lands a hair off zero, so only
+        // the side is up to rounding
+        for phase in [PI, TAU] {
+            assert_eq!(LfoShape::Square.evaluate(phase).abs(), 1.0);
+        }
+    }
+
+    #[test]
+    fn triangle_at_quarter_cycles() {
+        assert_shape(LfoShape::Triangle, [1.0, 0.0, -1.0, 0.0, 1.0]);
+    }
+
+    #[test]
+    fn noise_evaluates_to_its_mean() {
+        assert_shape(LfoShape::Noise, [0.0; 5]);
+    }
+
+    #[test]
+    fn one_ripple_per_kick() {
+        let mut ripples = RippleSystem::default();
+        // A hit rising over consecutive updates
+        for _ in 0..5 {
+            ripples.kick(0.5);
+        }
+        assert_eq!(ripples.ripples.iter().filter(|ripple| ripple.active).count(), 1);
+
+        std::thread::sleep(MIN_KICK_RIPPLE_INTERVAL);
+        ripples.kick(0.5);
+        assert_eq!(ripples.ripples.iter().filter(|ripple| ripple.active).count(), 2);
+    }
+
+    #[test]
+    fn lfo_shape_index_round_trips() {
+        for shape in LfoShape::ALL {
+            assert_eq!(LfoShape::from_i32(shape.to_i32()), Some(shape));
+        }
+        for index in 0..LfoShape::ALL.len() as i32 {
+            assert_eq!(LfoShape::from_i32(index).map(LfoShape::to_i32), Some(index));
+        }
+        assert_eq!(LfoShape::from_i32(-1), None);
+        assert_eq!(LfoShape::from_i32(LfoShape::ALL.len() as i32), None);
+    }
 }
@@ -0,0 +1,41 @@
+//! Terminal preview: prints a downsampled frame with ANSI truecolor half blocks,
+//! for checking the output over SSH when nothing is looking at the screen
+
+use std::io::Write;
+
+/// Upper half block, foreground colors the top pixel and background the bottom
+const HALF_BLOCK: char = '\u{2580}';
+
+/// Pixel height of the preview for a given width, so each character cell
+/// (roughly twice as tall as wide) shows two square pixels
+pub fn preview_height(width: u32, window_width: u32, window_height: u32) -> u32 {
+    let height = (width as u64 * window_height as u64 / window_width.max(1) as u64) as u32;
+    // Even, so every character row has a top and bottom pixel
+    (height.max(2) + 1) & !1
+}
+
+/// Draw an RGBA frame at the top left of the terminal, overwriting the previous one
+pub fn print_frame(rgba: &[u8], width: u32, height: u32) {
+    let width = width as usize;
+    let mut out = String::with_capacity(width * height as usize * 20);
+    out.push_str("\x1b[H");
+    for rows in rgba.chunks(width * 4 * 2) {
+        let (top, bottom) = rows.split_at(width * 4);
+        for (t, b) in top.chunks(4).zip(bottom.chunks(4)) {
+            out.push_str(&format!(
+                "\x1b[38;2;{};{};{}m\x1b[48;2;{};{};{}m{}",
+                t[0], t[1], t[2], b[0], b[1], b[2], HALF_BLOCK
+            ));
+        }
+        out.push_str("\x1b[0m\n");
+    }
+
+    let mut stdout = std::io::stdout().lock();
+    let _ = stdout.write_all(out.as_bytes());
+    let _ = stdout.flush();
+}
+
+/// Clear the terminal before the first frame
+pub fn clear() {
+    print!("\x1b[2J");
+}
@@ -0,0 +1,161 @@
+//! Camera frame analysis on a coarse luma grid: auto-framing (where the action
+//! is, so the pivot can follow a person moving around an installation)
+//! and motion energy (how much is moving)
+
+/// Analysis grid (samples per axis); the frame is point-sampled down to this
+const GRID_W: usize = 48;
+const GRID_H: usize = 27;
+/// Minimum total weight before a new target is accepted, as a fraction of the grid
+const MIN_WEIGHT: f32 = 0.01;
+/// Mean absolute luma change that reads as full motion energy
+const FULL_MOTION: f32 = 0.15;
+
+/// Point-sample an RGBA frame down to the analysis grid, as luma (0 - 1).
+/// Returns false if the frame is smaller than its dimensions claim.
+fn sample_luma(rgba: &[u8], width: u32, height: u32, luma: &mut [f32]) -> bool {
+    if rgba.len() < (width * height * 4) as usize {
+        return false;
+    }
+    for gy in 0..GRID_H {
+        let y = (gy * height as usize) / GRID_H;
+        for gx in 0..GRID_W {
+            let x = (gx * width as usize) / GRID_W;
+            let i = (y * width as usize + x) * 4;
+            let value = 0.33 * rgba[i] as f32 + 0.5 * rgba[i + 1] as f32 + 0.16 * rgba[i + 2] as f32;
+            luma[gy * GRID_W + gx] = value / 255.0;
+        }
+    }
+    true
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, clap::ValueEnum)]
+pub enum AutoCenterMode {
+    Off,
+    /// Centroid of bright regions
+    Brightness,
+    /// Centroid of frame-to-frame change
+    Motion,
+}
+
+impl AutoCenterMode {
+    pub fn next(self) -> Self {
+        match self {
+            AutoCenterMode::Off => AutoCenterMode::Brightness,
+            AutoCenterMode::Brightness => AutoCenterMode::Motion,
+            AutoCenterMode::Motion => AutoCenterMode::Off,
+        }
+    }
+}
+
+pub struct AutoCenter {
+    pub mode: AutoCenterMode,
+    /// Fraction of the distance to the target covered per update (0 - 1)
+    pub responsiveness: f32,
+    /// Latest centroid in video coords (0 - 1, matching mesh tex coords)
+    target: (f32, f32),
+    /// Smoothed centroid
+    position: (f32, f32),
+    luma: Vec<f32>,
+    prev_luma: Vec<f32>,
+}
+
+impl AutoCenter {
+    pub fn new(mode: AutoCenterMode, responsiveness: f32) -> Self {
+        Self {
+            mode,
+            responsiveness,
+            target: (0.5, 0.5),
+            position: (0.5, 0.5),
+            luma: vec![0.0; GRID_W * GRID_H],
+            prev_luma: vec![0.0; GRID_W * GRID_H],
+        }
+    }
+
+    /// Update the target from an RGBA frame. Keeps the previous target when
+    /// there's too little brightness or motion to go on.
+    pub fn analyze(&mut self, rgba: &[u8], width: u32, height: u32) {
+        if self.mode == AutoCenterMode::Off {
+            return;
+        }
+
+        std::mem::swap(&mut self.luma, &mut self.prev_luma);
+        if !sample_luma(rgba, width, height, &mut self.luma) {
+            return;
+        }
+
+        let mut total = 0.0;
+        let mut sum_x = 0.0;
+        let mut sum_y = 0.0;
+        for (i, (&luma, &prev)) in self.luma.iter().zip(&self.prev_luma).enumerate() {
+            let weight = match self.mode {
+                // Squared so the brightest areas dominate
+                AutoCenterMode::Brightness => luma * luma,
+                AutoCenterMode::Motion => (luma - prev).abs(),
+                AutoCenterMode::Off => 0.0,
+            };
+            total += weight;
+            sum_x += weight * ((i % GRID_W) as f32 + 0.5);
+            sum_y += weight * ((i / GRID_W) as f32 + 0.5);
+        }
+
+        if total > MIN_WEIGHT * (GRID_W * GRID_H) as f32 {
+            self.target = (sum_x / total / GRID_W as f32, sum_y / total / GRID_H as f32);
+        }
+    }
+
+    /// Ease toward the target (call once per update) and return the pivot
+    /// offset in clip space that puts the zoom/LFO center on it
+    pub fn update(&mut self) -> [f32; 2] {
+        if self.mode == AutoCenterMode::Off {
+            self.position = (0.5, 0.5);
+            return [0.0; 2];
+        }
+        let k = self.responsiveness.clamp(0.0, 1.0);
+        self.position.0 += (self.target.0 - self.position.0) * k;
+        self.position.1 += (self.target.1 - self.position.1) * k;
+        [2.0 * self.position.0 - 1.0, 2.0 * self.position.1 - 1.0]
+    }
+}
+
+/// Frame-difference motion estimate: mean absolute luma change between
+/// consecutive camera frames
+pub struct MotionEnergy {
+    energy: f32,
+    luma: Vec<f32>,
+    prev_luma: Vec<f32>,
+}
+
+impl Default for MotionEnergy {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl MotionEnergy {
+    pub fn new() -> Self {
+        Self {
+            energy: 0.0,
+            luma: vec![0.0; GRID_W * GRID_H],
+            prev_luma: vec![0.0; GRID_W * GRID_H],
+        }
+    }
+
+    /// Compare an RGBA frame against the previous one. An identical frame means
+    /// the camera hasn't delivered a new one yet, so the last estimate is kept.
+    pub fn analyze(&mut self, rgba: &[u8], width: u32, height: u32) {
+        std::mem::swap(&mut self.luma, &mut self.prev_luma);
+        if !sample_luma(rgba, width, height, &mut self.luma) {
+            return;
+        }
+
+        let diff: f32 = self.luma.iter().zip(&self.prev_luma).map(|(a, b)| (a - b).abs()).sum();
+        if diff > 0.0 {
+            self.energy = (diff / (GRID_W * GRID_H) as f32 / FULL_MOTION).min(1.0);
+        }
+    }
+
+    /// Latest motion energy (0 - 1)
+    pub fn energy(&self) -> f32 {
+        self.energy
+    }
+}
@@ -1,29 +1,182 @@
 #[cfg(feature = "camera")]
 use nokhwa::{
     pixel_format::RgbFormat,
-    utils::{CameraIndex, RequestedFormat, RequestedFormatType},
+    utils::{ApiBackend, CameraIndex, RequestedFormat, RequestedFormatType},
     Camera,
 };
-use std::sync::mpsc::{channel, Receiver, Sender, TryRecvError};
+use std::io::Read;
+use std::path::Path;
+use std::process::{Child, ChildStdout, Command, Stdio};
+use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+use std::sync::mpsc::{channel, sync_channel, Receiver, Sender, SyncSender, TryRecvError};
+use std::sync::Arc;
 use std::thread;
 
+/// Interpolation used when resizing camera frames to the processing resolution
+#[derive(Clone, Copy, Debug, PartialEq, Eq, clap::ValueEnum)]
+pub enum ResizeFilter {
+    /// Point sampling - fastest, aliases when downscaling
+    Nearest,
+    /// Bilinear interpolation - smoother fine detail
+    Bilinear,
+}
+
+/// Source coordinates for one target column or row:
+/// (first sample, second sample, weight of the second in 1/256ths)
+type ResizeTap = (usize, usize, u32);
+
+/// Precomputed source coordinate tables for resizing RGB frames to RGBA,
+/// rebuilt only when the source resolution changes
+struct ResizeTable {
+    src_width: u32,
+    // Only checked by the camera thread (`matches`), images never change size
+    #[cfg_attr(not(feature = "camera"), allow(dead_code))]
+    src_height: u32,
+    columns: Vec<ResizeTap>,
+    rows: Vec<ResizeTap>,
+}
+
+impl ResizeTable {
+    fn new(filter: ResizeFilter, src_width: u32, src_height: u32, dst_width: u32, dst_height: u32) -> Self {
+        Self {
+            src_width,
+            src_height,
+            columns: Self::taps(filter, src_width, dst_width, false),
+            rows: Self::taps(filter, src_height, dst_height, true),
+        }
+    }
+
+    fn taps(filter: ResizeFilter, src: u32, dst: u32, flip: bool) -> Vec<ResizeTap> {
+        let last = src.saturating_sub(1) as usize;
+        (0..dst)
+            .map(|t| {
+                let t = if flip { dst - 1 - t } else { t };
+                match filter {
+                    ResizeFilter::Nearest => {
+                        let s = ((t as f32 / dst as f32 * src as f32) as usize).min(last);
+                        (s, s, 0)
+                    }
+                    ResizeFilter::Bilinear => {
+                        // Sample at pixel centers so a 2:1 downscale averages pixel pairs
+                        let s = ((t as f32 + 0.5) * src as f32 / dst as f32 - 0.5).clamp(0.0, last as f32);
+                        let s0 = s.floor() as usize;
+                        let s1 = (s0 + 1).min(last);
+                        (s0, s1, ((s - s0 as f32) * 256.0) as u32)
+                    }
+                }
+            })
+            .collect()
+    }
+
+    #[cfg_attr(not(feature = "camera"), allow(dead_code))]
+    fn matches(&self, src_width: u32, src_height: u32) -> bool {
+        self.src_width == src_width && self.src_height == src_height
+    }
+
+    /// Resize a tightly packed RGB frame into an RGBA frame of the table's target size
+    fn resize(&self, src: &[u8], dst: &mut [u8]) {
+        let stride = self.src_width as usize * 3;
+        let dst_width = self.columns.len();
+
+        for (ty, &(y0, y1, wy)) in self.rows.iter().enumerate() {
+            let row0 = &src[y0 * stride..(y0 + 1) * stride];
+            let row1 = &src[y1 * stride..(y1 + 1) * stride];
+
+            for (tx, &(x0, x1, wx)) in self.columns.iter().enumerate() {
+                let idx = (ty * dst_width + tx) * 4;
+                for c in 0..3 {
+                    let top = row0[x0 * 3 + c] as u32 * (256 - wx) + row0[x1 * 3 + c] as u32 * wx;
+                    let bottom = row1[x0 * 3 + c] as u32 * (256 - wx) + row1[x1 * 3 + c] as u32 * wx;
+                    dst[idx + c] = ((top * (256 - wy) + bottom * wy) >> 16) as u8;
+                }
+                dst[idx + 3] = 255;
+            }
+        }
+    }
+}
+
+/// Highest share of the running average kept per frame; above this the
+/// average barely moves
+pub const MAX_DENOISE: f32 = 0.95;
+
+/// Exponential moving average of camera frames, cleaning sensor noise at the
+/// cost of motion blur. Kept in 8.8 fixed point so slow blends still converge.
+#[cfg_attr(not(feature = "camera"), allow(dead_code))]
+#[derive(Default)]
+struct FrameBlend {
+    average: Vec<u16>,
+}
+
+#[cfg_attr(not(feature = "camera"), allow(dead_code))]
+impl FrameBlend {
+    /// Blend `frame` into the average, keeping `amount` (0 - MAX_DENOISE) of
+    /// the previous frames, and write the result back to `frame`
+    fn apply(&mut self, frame: &mut [u8], amount: f32) {
+        if amount <= 0.0 {
+            self.average.clear();
+            return;
+        }
+        if self.average.len() != frame.len() {
+            self.average = frame.iter().map(|&v| (v as u16) << 8).collect();
+            return;
+        }
+
+        let keep = (amount.min(MAX_DENOISE) * 256.0) as u32;
+        for (average, value) in self.average.iter_mut().zip(frame.iter_mut()) {
+            let blended = (*average as u32 * keep + ((*value as u32) << 8) * (256 - keep)) >> 8;
+            *average = blended as u16;
+            *value = ((blended + 128) >> 8).min(255) as u8;
+        }
+    }
+}
+
 pub struct VideoCapture {
     receiver: Receiver<Vec<u8>>,
     pub width: u32,
     pub height: u32,
     current_frame: Vec<u8>,
+    /// Resolution negotiated by the camera (0 until the stream starts)
+    source_width: Arc<AtomicU32>,
+    source_height: Arc<AtomicU32>,
+    /// Device index this capture opened
+    pub device_index: u32,
+    /// Asks the capture thread to close the camera and exit (set on drop)
+    stop: Arc<AtomicBool>,
+    /// Temporal blend amount (f32 bits), 0 = off
+    denoise_bits: Arc<AtomicU32>,
+    /// The capture thread has exited, e.g. because the camera failed to open
+    closed: bool,
     #[allow(dead_code)]
     handle: Option<thread::JoinHandle<()>>,
 }
 
 impl VideoCapture {
     #[cfg(feature = "camera")]
-    pub fn new(width: u32, height: u32, device_index: u32) -> Result<Self, String> {
+    pub fn new(width: u32, height: u32, device_index: u32, filter: ResizeFilter) -> Result<Self, String> {
         let (sender, receiver) = channel();
         let frame_size = (width * height * 4) as usize;
 
+        let source_width = Arc::new(AtomicU32::new(0));
+        let source_height = Arc::new(AtomicU32::new(0));
+        let source_width_clone = source_width.clone();
+        let source_height_clone = source_height.clone();
+        let stop = Arc::new(AtomicBool::new(false));
+        let stop_clone = stop.clone();
+        let denoise_bits = Arc::new(AtomicU32::new(0));
+        let denoise_clone = denoise_bits.clone();
+
         let handle = thread::spawn(move || {
-            Self::camera_thread(sender, width, height, device_index);
+            Self::camera_thread(
+                sender,
+                width,
+                height,
+                device_index,
+                filter,
+                source_width_clone,
+                source_height_clone,
+                stop_clone,
+                denoise_clone,
+            );
         });
 
         Ok(Self {
@@ -31,12 +184,29 @@ impl VideoCapture {
             width,
             height,
             current_frame: vec![128u8; frame_size],
+            source_width,
+            source_height,
+            device_index,
+            stop,
+            denoise_bits,
+            closed: false,
             handle: Some(handle),
         })
     }
 
     #[cfg(feature = "camera")]
-    fn camera_thread(sender: Sender<Vec<u8>>, target_width: u32, target_height: u32, device_index: u32) {
+    #[allow(clippy::too_many_arguments)]
+    fn camera_thread(
+        sender: Sender<Vec<u8>>,
+        target_width: u32,
+        target_height: u32,
+        device_index: u32,
+        filter: ResizeFilter,
+        source_width: Arc<AtomicU32>,
+        source_height: Arc<AtomicU32>,
+        stop: Arc<AtomicBool>,
+        denoise_bits: Arc<AtomicU32>,
+    ) {
         let index = CameraIndex::Index(device_index);
 
         let requested = RequestedFormat::new::<RgbFormat>(
@@ -63,10 +233,14 @@ impl VideoCapture {
 
         let resolution = camera.resolution();
         log::info!("Camera stream started at {}x{}", resolution.width(), resolution.height());
+        source_width.store(resolution.width(), Ordering::Relaxed);
+        source_height.store(resolution.height(), Ordering::Relaxed);
 
         let mut frame_count = 0u64;
+        let mut resize_table: Option<ResizeTable> = None;
+        let mut blend = FrameBlend::default();
 
-        loop {
+        while !stop.load(Ordering::Relaxed) {
             match camera.frame() {
                 Ok(frame) => {
                     match frame.decode_image::<RgbFormat>() {
@@ -74,30 +248,24 @@ impl VideoCapture {
                             let cam_width = rgb_image.width();
                             let cam_height = rgb_image.height();
 
-                            // Resize to target resolution
-                            let mut rgba = vec![0u8; (target_width * target_height * 4) as usize];
-
-                            for ty in 0..target_height {
-                                for tx in 0..target_width {
-                                    // Map target coords to source coords (flip Y)
-                                    let sx = (tx as f32 / target_width as f32 * cam_width as f32) as u32;
-                                    let sy = ((target_height - 1 - ty) as f32 / target_height as f32 * cam_height as f32) as u32;
-
-                                    let sx = sx.min(cam_width - 1);
-                                    let sy = sy.min(cam_height - 1);
-
-                                    if let Some(pixel) = rgb_image.get_pixel_checked(sx, sy) {
-                                        let idx = ((ty * target_width + tx) * 4) as usize;
-                                        rgba[idx] = pixel.0[0];     // R
-                                        rgba[idx + 1] = pixel.0[1]; // G
-                                        rgba[idx + 2] = pixel.0[2]; // B
-                                        rgba[idx + 3] = 255;        // A
-                                    }
-                                }
+                            if cam_width == 0 || cam_height == 0 {
+                                continue;
                             }
 
+                            // Coordinate tables only change with the camera resolution
+                            let table = match resize_table.take() {
+                                Some(table) if table.matches(cam_width, cam_height) => table,
+                                _ => ResizeTable::new(filter, cam_width, cam_height, target_width, target_height),
+                            };
+
+                            // Resize to target resolution (flip Y)
+                            let mut rgba = vec![0u8; (target_width * target_height * 4) as usize];
+                            table.resize(rgb_image.as_raw(), &mut rgba);
+                            resize_table = Some(table);
+                            blend.apply(&mut rgba, f32::from_bits(denoise_bits.load(Ordering::Relaxed)));
+
                             frame_count += 1;
-                            if frame_count % 60 == 0 {
+                            if frame_count.is_multiple_of(60) {
                                 log::debug!("Camera: {} frames captured", frame_count);
                             }
 
@@ -117,10 +285,15 @@ impl VideoCapture {
                 }
             }
         }
+
+        if let Err(e) = camera.stop_stream() {
+            log::warn!("Failed to stop camera {}: {}", device_index, e);
+        }
+        log::info!("Camera {} closed", device_index);
     }
 
     #[cfg(not(feature = "camera"))]
-    pub fn new(width: u32, height: u32, _device_index: u32) -> Result<Self, String> {
+    pub fn new(width: u32, height: u32, _device_index: u32, _filter: ResizeFilter) -> Result<Self, String> {
         Err("Camera support not compiled. Enable 'camera' feature.".to_string())
     }
 
@@ -133,7 +306,10 @@ impl VideoCapture {
                     got_frame = true;
                 }
                 Err(TryRecvError::Empty) => break,
-                Err(TryRecvError::Disconnected) => break,
+                Err(TryRecvError::Disconnected) => {
+                    self.closed = true;
+                    break;
+                }
             }
         }
 
@@ -147,21 +323,269 @@ impl VideoCapture {
     pub fn current_frame(&self) -> &[u8] {
         &self.current_frame
     }
+
+    /// The capture thread has exited (camera failed to open, or lost), as
+    /// seen by the last `get_frame`
+    pub fn is_closed(&self) -> bool {
+        self.closed
+    }
+
+    /// Average frames over time on the capture thread, keeping `amount`
+    /// (0 - MAX_DENOISE) of the previous frames each frame; 0 turns it off
+    pub fn set_denoise(&self, amount: f32) {
+        let amount = if amount.is_finite() { amount.clamp(0.0, MAX_DENOISE) } else { 0.0 };
+        self.denoise_bits.store(amount.to_bits(), Ordering::Relaxed);
+    }
+
+    /// Resolution the camera actually delivers, before resizing to the target.
+    /// Returns None until the capture thread has started the stream.
+    pub fn source_resolution(&self) -> Option<(u32, u32)> {
+        let width = self.source_width.load(Ordering::Relaxed);
+        let height = self.source_height.load(Ordering::Relaxed);
+        if width > 0 && height > 0 {
+            Some((width, height))
+        } else {
+            None
+        }
+    }
+}
+
+impl Drop for VideoCapture {
+    /// Stop the capture thread; it releases the camera after its current frame
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+    }
+}
+
+/// List connected cameras as (index, name) pairs
+#[cfg(feature = "camera")]
+pub fn list_cameras() -> Vec<(u32, String)> {
+    match nokhwa::query(ApiBackend::Auto) {
+        Ok(cameras) => cameras
+            .iter()
+            .enumerate()
+            .map(|(i, info)| {
+                let index = info.index().as_index().unwrap_or(i as u32);
+                (index, info.human_name())
+            })
+            .collect(),
+        Err(e) => {
+            log::warn!("Camera enumeration failed: {}", e);
+            Vec::new()
+        }
+    }
+}
+
+/// A still image held as every frame, for watching the effects over a known picture
+pub struct ImageVideoSource {
+    frame: Vec<u8>,
+}
+
+impl ImageVideoSource {
+    /// Decode a PNG/JPEG (or any format the image crate reads) and resize it to
+    /// `width` x `height` the way camera frames are, bottom row first
+    pub fn new(path: &Path, width: u32, height: u32, filter: ResizeFilter) -> Result<Self, String> {
+        let image = image::open(path)
+            .map_err(|e| format!("Failed to load {}: {}", path.display(), e))?
+            .to_rgb8();
+        let table = ResizeTable::new(filter, image.width(), image.height(), width, height);
+        let mut frame = vec![0u8; (width * height * 4) as usize];
+        table.resize(image.as_raw(), &mut frame);
+        Ok(Self { frame })
+    }
+
+    pub fn update(&self) -> &[u8] {
+        &self.frame
+    }
+}
+
+/// Video file decoded by an `ffmpeg` process from the PATH, so every format it
+/// reads plays without codec libraries in the build. Frames arrive at the
+/// file's frame rate.
+pub struct FileVideoSource {
+    receiver: Receiver<Vec<u8>>,
+    current_frame: Vec<u8>,
+    process: Child,
+    /// The decoder has stopped: end of a non-looping file, or a decode error
+    finished: bool,
+}
+
+impl FileVideoSource {
+    /// Start decoding `path` resized to `width` x `height`, starting over at the
+    /// end of the file if `looping`
+    pub fn new(path: &Path, width: u32, height: u32, filter: ResizeFilter, looping: bool) -> Result<Self, String> {
+        let flags = match filter {
+            ResizeFilter::Nearest => "neighbor",
+            ResizeFilter::Bilinear => "bilinear",
+        };
+        let mut command = Command::new("ffmpeg");
+        command.args(["-hide_banner", "-loglevel", "error", "-nostdin"]);
+        if looping {
+            command.args(["-stream_loop", "-1"]);
+        }
+        // -re paces decoding to the file's frame rate instead of the render loop;
+        // frames are flipped to bottom row first, like camera frames
+        command
+            .arg("-re")
+            .arg("-i")
+            .arg(path)
+            .args(["-an", "-vf"])
+            .arg(format!("scale={}:{}:flags={},vflip", width, height, flags))
+            .args(["-pix_fmt", "rgba", "-f", "rawvideo", "-"])
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped());
+
+        let mut process = command.spawn().map_err(|e| match e.kind() {
+            std::io::ErrorKind::NotFound => "ffmpeg not found; install it to play video files".to_string(),
+            _ => format!("Failed to start ffmpeg: {}", e),
+        })?;
+        let stdout = process.stdout.take().ok_or("ffmpeg output not captured")?;
+
+        // One decoded frame waiting at most, so ffmpeg never runs ahead
+        let frame_size = (width * height * 4) as usize;
+        let (sender, receiver) = sync_channel(1);
+        thread::spawn(move || Self::decode_thread(stdout, sender, frame_size));
+
+        Ok(Self {
+            receiver,
+            current_frame: vec![0u8; frame_size],
+            process,
+            finished: false,
+        })
+    }
+
+    fn decode_thread(mut stdout: ChildStdout, sender: SyncSender<Vec<u8>>, frame_size: usize) {
+        loop {
+            let mut frame = vec![0u8; frame_size];
+            if stdout.read_exact(&mut frame).is_err() || sender.send(frame).is_err() {
+                break;
+            }
+        }
+    }
+
+    /// Latest decoded frame; the last one stays up once the file has ended
+    pub fn next_frame(&mut self) -> &[u8] {
+        loop {
+            match self.receiver.try_recv() {
+                Ok(frame) => self.current_frame = frame,
+                Err(TryRecvError::Empty) => break,
+                Err(TryRecvError::Disconnected) => {
+                    if !self.finished {
+                        log::info!("Video file ended");
+                        self.finished = true;
+                    }
+                    break;
+                }
+            }
+        }
+        &self.current_frame
+    }
 }
 
+impl Drop for FileVideoSource {
+    /// Stop ffmpeg; the decode thread exits once its output closes
+    fn drop(&mut self) {
+        let _ = self.process.kill();
+        let _ = self.process.wait();
+    }
+}
+
+/// Resolutions a camera offers as (width, height, highest frame rate),
+/// largest first. Opens the camera briefly, so not for one already in use.
+#[cfg(feature = "camera")]
+pub fn camera_resolutions(index: u32) -> Result<Vec<(u32, u32, u32)>, String> {
+    let requested = RequestedFormat::new::<RgbFormat>(RequestedFormatType::None);
+    let mut camera = Camera::new(CameraIndex::Index(index), requested).map_err(|e| e.to_string())?;
+    let formats = camera.compatible_camera_formats().map_err(|e| e.to_string())?;
+
+    let mut resolutions: Vec<(u32, u32, u32)> = Vec::new();
+    for format in formats {
+        let (width, height, fps) = (format.width(), format.height(), format.frame_rate());
+        match resolutions.iter_mut().find(|(w, h, _)| (*w, *h) == (width, height)) {
+            Some(known) => known.2 = known.2.max(fps),
+            None => resolutions.push((width, height, fps)),
+        }
+    }
+    resolutions.sort_by_key(|&(width, height, _)| std::cmp::Reverse(width * height));
+    Ok(resolutions)
+}
+
+/// Image drawn by the dummy source
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum TestPattern {
+    /// Moving sine interference
+    #[default]
+    Plasma,
+    /// SMPTE-style color bars
+    Bars,
+    /// Horizontal hue sweep scrolling sideways
+    Gradient,
+    /// Black and white squares
+    Checkerboard,
+    /// Line grid with a center cross and circle, for lining up a projector
+    Grid,
+}
+
+impl TestPattern {
+    const ALL: [TestPattern; 5] = [
+        TestPattern::Plasma,
+        TestPattern::Bars,
+        TestPattern::Gradient,
+        TestPattern::Checkerboard,
+        TestPattern::Grid,
+    ];
+
+    pub fn next(self) -> Self {
+        Self::ALL[(self as usize + 1) % Self::ALL.len()]
+    }
+}
+
+/// SMPTE bar colors at 75%: white, yellow, cyan, green, magenta, red, blue
+const BARS: [[u8; 3]; 7] = [
+    [191, 191, 191],
+    [191, 191, 0],
+    [0, 191, 191],
+    [0, 191, 0],
+    [191, 0, 191],
+    [191, 0, 0],
+    [0, 0, 191],
+];
+/// The short strip under the bars: the same bars reversed, black between
+const BARS_STRIP: [[u8; 3]; 7] = [
+    [0, 0, 191],
+    [19, 19, 19],
+    [191, 0, 191],
+    [19, 19, 19],
+    [0, 191, 191],
+    [19, 19, 19],
+    [191, 191, 191],
+];
+/// Bottom row: -I, white, +Q, then black with the PLUGE steps
+const BARS_BOTTOM: [[u8; 3]; 7] = [
+    [0, 33, 76],
+    [255, 255, 255],
+    [50, 0, 106],
+    [19, 19, 19],
+    [9, 9, 9],
+    [19, 19, 19],
+    [29, 29, 29],
+];
+
 /// Dummy video source for testing without camera
 pub struct DummyVideoSource {
     pub width: u32,
     pub height: u32,
+    pub pattern: TestPattern,
     frame: Vec<u8>,
     frame_count: u32,
 }
 
 impl DummyVideoSource {
-    pub fn new(width: u32, height: u32) -> Self {
+    pub fn new(width: u32, height: u32, pattern: TestPattern) -> Self {
         Self {
             width,
             height,
+            pattern,
             frame: vec![0u8; (width * height * 4) as usize],
             frame_count: 0,
         }
@@ -173,17 +597,7 @@ impl DummyVideoSource {
         for y in 0..self.height {
             for x in 0..self.width {
                 let idx = ((y * self.width + x) * 4) as usize;
-                let fx = x as f32 / self.width as f32;
-                let fy = y as f32 / self.height as f32;
-
-                let v1 = (fx * 10.0 + phase).sin();
-                let v2 = (fy * 10.0 + phase * 1.5).sin();
-                let v3 = ((fx + fy) * 8.0 + phase * 0.5).sin();
-                let v4 = ((fx * fx + fy * fy).sqrt() * 15.0 + phase * 2.0).sin();
-
-                let r = ((v1 + v2 + 2.0) / 4.0 * 255.0) as u8;
-                let g = ((v2 + v3 + 2.0) / 4.0 * 255.0) as u8;
-                let b = ((v3 + v4 + 2.0) / 4.0 * 255.0) as u8;
+                let [r, g, b] = self.pixel(x, y, phase);
 
                 self.frame[idx] = r;
                 self.frame[idx + 1] = g;
@@ -195,4 +609,71 @@ impl DummyVideoSource {
         self.frame_count = self.frame_count.wrapping_add(1);
         &self.frame
     }
+
+    /// Color at a pixel of the frame buffer (row 0 at the bottom, like camera frames)
+    fn pixel(&self, x: u32, row: u32, phase: f32) -> [u8; 3] {
+        let fx = x as f32 / self.width as f32;
+        // The patterns below count rows from the top
+        let y = self.height - 1 - row;
+        let fy = y as f32 / self.height as f32;
+
+        match self.pattern {
+            TestPattern::Plasma => {
+                let fy = row as f32 / self.height as f32;
+                let v1 = (fx * 10.0 + phase).sin();
+                let v2 = (fy * 10.0 + phase * 1.5).sin();
+                let v3 = ((fx + fy) * 8.0 + phase * 0.5).sin();
+                let v4 = ((fx * fx + fy * fy).sqrt() * 15.0 + phase * 2.0).sin();
+
+                [
+                    ((v1 + v2 + 2.0) / 4.0 * 255.0) as u8,
+                    ((v2 + v3 + 2.0) / 4.0 * 255.0) as u8,
+                    ((v3 + v4 + 2.0) / 4.0 * 255.0) as u8,
+                ]
+            }
+            TestPattern::Bars => {
+                let bar = ((fx * 7.0) as usize).min(6);
+                if fy < 0.67 {
+                    BARS[bar]
+                } else if fy < 0.75 {
+                    BARS_STRIP[bar]
+                } else {
+                    BARS_BOTTOM[bar]
+                }
+            }
+            TestPattern::Gradient => {
+                // Hue across, full brightness at the top fading down
+                let hue = (fx + phase * 0.1).fract() * 6.0;
+                let ramp = |offset: f32| (((hue + offset) % 6.0 - 3.0).abs() - 1.0).clamp(0.0, 1.0);
+                let value = 1.0 - fy;
+                [ramp(0.0), ramp(4.0), ramp(2.0)].map(|c| (c * value * 255.0) as u8)
+            }
+            TestPattern::Checkerboard => {
+                // Square cells, 16 across
+                let cell = (self.width / 16).max(1);
+                if (x / cell + y / cell).is_multiple_of(2) {
+                    [255; 3]
+                } else {
+                    [0; 3]
+                }
+            }
+            TestPattern::Grid => {
+                let (cx, cy) = (self.width / 2, self.height / 2);
+                let dx = x as f32 - cx as f32;
+                let dy = y as f32 - cy as f32;
+                let radius = self.height as f32 * 0.4;
+                let on_circle = ((dx * dx + dy * dy).sqrt() - radius).abs() < 1.0;
+                let edge = x == 0 || y == 0 || x == self.width - 1 || y == self.height - 1;
+                if x == cx || y == cy || on_circle || edge {
+                    [255, 255, 255]
+                } else if x.is_multiple_of(64) || y.is_multiple_of(64) {
+                    [160, 160, 160]
+                } else if x.is_multiple_of(16) || y.is_multiple_of(16) {
+                    [64, 64, 64]
+                } else {
+                    [0, 0, 0]
+                }
+            }
+        }
+    }
 }